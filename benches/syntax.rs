@@ -0,0 +1,97 @@
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use dwparser::DWSyntax;
+
+fn big_file() -> String {
+    include_str!("../assets/big_file.srd").trim_start_matches('\u{feff}').to_owned()
+}
+
+/// 大量控件
+fn many_items(count: usize) -> String {
+    let mut syn = String::from("release 19;\r\ndatawindow(units=0 processing=0)\r\n");
+    for i in 0..count {
+        syn += &format!(
+            "text(band=detail alignment=\"0\" text=\"text{i}\" border=\"0\" x=\"{}\" y=\"8\" \
+             height=\"56\" width=\"200\" name=t_{i} font.face=\"Arial\" font.height=\"-9\" \
+             background.color=\"536870912\" )\r\n",
+            i * 10
+        );
+    }
+    syn
+}
+
+/// 大数据量的`data`项
+fn huge_data(rows: usize) -> String {
+    let mut syn = String::from(
+        "release 19;\r\ntable(column=(type=long name=id dbname=\"id\" ) column=(type=char(20) name=name \
+         dbname=\"name\" ) column=(type=datetime name=ts dbname=\"ts\" ) )\r\ndata("
+    );
+    for i in 0..rows {
+        syn += &format!("{i},\"name~\"{i}\",2020-01-01 12:00:00:000000,");
+    }
+    syn += " )\r\n";
+    syn
+}
+
+/// 很长的嵌套列表
+fn deep_lists(count: usize) -> String {
+    let args: Vec<String> = (0..count).map(|i| format!("(\"arg{i}\", string)")).collect();
+    let by: Vec<String> = (0..count).map(|i| format!("\"col{i}\"")).collect();
+    format!(
+        "release 19;\r\ntable(column=(type=char(10) name=col0 dbname=\"col0\" ) \
+         retrieve=\"select col0 from t\" arguments=({}) )\r\n\
+         group(level=1 header.height=0 trailer.height=0 by=({}) )\r\n",
+        args.join(", "),
+        by.join(", ")
+    )
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, input) in [
+        ("big_file", big_file()),
+        ("many_items", many_items(10000)),
+        ("huge_data", huge_data(50000)),
+        ("deep_lists", deep_lists(10000))
+    ] {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &input, |b, input| {
+            b.iter(|| DWSyntax::parse(black_box(input)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_display(c: &mut Criterion) {
+    let input = big_file();
+    let syn = DWSyntax::parse(&input).unwrap();
+    let mut group = c.benchmark_group("display");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("to_string", |b| b.iter(|| black_box(&syn).to_string()));
+    group.bench_function("round_trip", |b| {
+        b.iter(|| {
+            let output = black_box(&syn).to_string();
+            DWSyntax::parse(&output).unwrap().items.len()
+        })
+    });
+    group.finish();
+}
+
+fn bench_query(c: &mut Criterion) {
+    for (name, input) in [("big_file", big_file()), ("many_items", many_items(10000))] {
+        let mut syn = DWSyntax::parse(&input).unwrap();
+        //最后一个控件,线性查找的最坏情况
+        let last = syn.items.iter().rev().find_map(|item| item.name.as_ref()).unwrap().to_string();
+        let describe = format!("{last}.x");
+        let modify = format!("{last}.x=\"100\"");
+        let mut group = c.benchmark_group(format!("query/{name}"));
+        group.bench_function("describe", |b| b.iter(|| syn.describe(black_box(&describe))));
+        group.bench_function("modify", |b| b.iter(|| syn.modify(black_box(&modify))));
+        syn.build_index();
+        group.bench_function("describe_indexed", |b| b.iter(|| syn.describe(black_box(&describe))));
+        group.bench_function("modify_indexed", |b| b.iter(|| syn.modify(black_box(&modify))));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_parse, bench_display, bench_query);
+criterion_main!(benches);
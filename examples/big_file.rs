@@ -13,7 +13,7 @@ fn from_file(file_path: &str) -> io::Result<String> {
     //检查UTF-8 BOM
     let mut bom = [0; 3];
     rd.read_exact(&mut bom)?;
-    if &bom != &[0xEF, 0xBB, 0xBF] {
+    if bom != [0xEF, 0xBB, 0xBF] {
         rd.seek_relative(-3)?;
     }
 
@@ -23,6 +23,8 @@ fn from_file(file_path: &str) -> io::Result<String> {
     Ok(syn)
 }
 
+//串行: cargo run --release --example big_file
+//并行: cargo run --release --example big_file --features rayon
 fn main() {
     let dwsyn = from_file("assets/big_file.srd").unwrap();
     let now = Instant::now();
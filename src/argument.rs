@@ -0,0 +1,398 @@
+use crate::{ast::*, data::*, parser::pbselect::PbSelect, prelude::*, update::SqlStatement};
+use std::{
+    fmt::{self, Display}, str::FromStr
+};
+
+/// 检索参数
+///
+/// # Syntax
+///
+/// ```txt
+/// table(arguments=(("name", string), ("ids", numberlist)))
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetrieveArgument {
+    pub name: String,
+    pub ty: ArgumentType
+}
+
+/// 检索参数类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArgumentType {
+    String,
+    Number,
+    Decimal,
+    Date,
+    DateTime,
+    Time,
+    StringList,
+    NumberList,
+    DecimalList,
+    DateList,
+    DateTimeList,
+    TimeList
+}
+
+impl ArgumentType {
+    /// 是否为数组类型
+    pub fn is_list(&self) -> bool {
+        matches!(
+            self,
+            ArgumentType::StringList |
+                ArgumentType::NumberList |
+                ArgumentType::DecimalList |
+                ArgumentType::DateList |
+                ArgumentType::DateTimeList |
+                ArgumentType::TimeList
+        )
+    }
+
+    /// 数组元素的类型
+    pub fn element(&self) -> ArgumentType {
+        match self {
+            ArgumentType::StringList => ArgumentType::String,
+            ArgumentType::NumberList => ArgumentType::Number,
+            ArgumentType::DecimalList => ArgumentType::Decimal,
+            ArgumentType::DateList => ArgumentType::Date,
+            ArgumentType::DateTimeList => ArgumentType::DateTime,
+            ArgumentType::TimeList => ArgumentType::Time,
+            ty => *ty
+        }
+    }
+}
+
+impl FromStr for ArgumentType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ty = match s.trim().to_ascii_lowercase().as_str() {
+            "string" => ArgumentType::String,
+            "number" => ArgumentType::Number,
+            "decimal" => ArgumentType::Decimal,
+            "date" => ArgumentType::Date,
+            "datetime" => ArgumentType::DateTime,
+            "time" => ArgumentType::Time,
+            "stringlist" => ArgumentType::StringList,
+            "numberlist" => ArgumentType::NumberList,
+            "decimallist" => ArgumentType::DecimalList,
+            "datelist" => ArgumentType::DateList,
+            "datetimelist" => ArgumentType::DateTimeList,
+            "timelist" => ArgumentType::TimeList,
+            _ => return Err(format!("invalid argument type: {s}"))
+        };
+        Ok(ty)
+    }
+}
+
+impl Display for ArgumentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ArgumentType::String => "string",
+            ArgumentType::Number => "number",
+            ArgumentType::Decimal => "decimal",
+            ArgumentType::Date => "date",
+            ArgumentType::DateTime => "datetime",
+            ArgumentType::Time => "time",
+            ArgumentType::StringList => "stringlist",
+            ArgumentType::NumberList => "numberlist",
+            ArgumentType::DecimalList => "decimallist",
+            ArgumentType::DateList => "datelist",
+            ArgumentType::DateTimeList => "datetimelist",
+            ArgumentType::TimeList => "timelist"
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// 参数占位符风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Placeholder {
+    /// `?`(ODBC/SQLite/MySQL)
+    #[default]
+    Question,
+    /// `$1`(PostgreSQL)
+    Dollar,
+    /// `@p1`(SQL Server)
+    AtP
+}
+
+impl Placeholder {
+    /// 第`n`个(从1开始)参数的占位符
+    pub(crate) fn write(&self, sql: &mut String, n: usize) {
+        match self {
+            Placeholder::Question => sql.push('?'),
+            Placeholder::Dollar => *sql += &format!("${n}"),
+            Placeholder::AtP => *sql += &format!("@p{n}")
+        }
+    }
+}
+
+/// 检索参数值
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentValue {
+    Single(DataValue),
+    List(Vec<DataValue>)
+}
+
+impl<T: Into<DataValue>> From<T> for ArgumentValue {
+    fn from(v: T) -> Self { ArgumentValue::Single(v.into()) }
+}
+
+/// 解析`:arg`引用后的检索SQL
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetrieveSql {
+    segments: Vec<Segment>,
+    /// 声明但未引用的参数
+    pub unused: Vec<String>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    /// 参数索引
+    Argument(usize)
+}
+
+impl RetrieveSql {
+    /// 解析SQL中的`:arg`引用
+    ///
+    /// 忽略字符串、引号标识符、注释及`::`类型转换,引用未声明的参数时返回错误
+    pub fn parse(sql: &str, args: &[RetrieveArgument]) -> Result<Self, String> {
+        let mut segments = vec![];
+        let mut text = String::new();
+        let mut used = vec![false; args.len()];
+        let mut chars = sql.char_indices().peekable();
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\'' | '"' => {
+                    text.push(c);
+                    for (_, v) in chars.by_ref() {
+                        text.push(v);
+                        if v == c {
+                            break;
+                        }
+                    }
+                },
+                '-' if sql[i + 1..].starts_with('-') => {
+                    let end = sql[i..].find('\n').map(|v| i + v).unwrap_or(sql.len());
+                    text += &sql[i..end];
+                    while matches!(chars.peek(), Some((j, _)) if *j < end) {
+                        chars.next();
+                    }
+                },
+                '/' if sql[i + 1..].starts_with('*') => {
+                    let end = sql[i + 2..].find("*/").map(|v| i + v + 4).unwrap_or(sql.len());
+                    text += &sql[i..end];
+                    while matches!(chars.peek(), Some((j, _)) if *j < end) {
+                        chars.next();
+                    }
+                },
+                ':' if sql[i + 1..].starts_with(':') => {
+                    text += "::";
+                    chars.next();
+                },
+                ':' if sql[i + 1..].starts_with(|c: char| c.is_alphabetic() || c == '_') => {
+                    let end =
+                        sql[i + 1..].find(|c: char| !is_ident(c)).map(|v| i + 1 + v).unwrap_or(sql.len());
+                    let name = &sql[i + 1..end];
+                    let index = args
+                        .iter()
+                        .position(|arg| arg.name.eq_ignore_ascii_case(name))
+                        .ok_or_else(|| format!("undeclared argument: {name}"))?;
+                    used[index] = true;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Argument(index));
+                    while matches!(chars.peek(), Some((j, _)) if *j < end) {
+                        chars.next();
+                    }
+                },
+                c => text.push(c)
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(RetrieveSql {
+            segments,
+            unused: args.iter().zip(used).filter(|(_, used)| !used).map(|(arg, _)| arg.name.clone()).collect()
+        })
+    }
+
+    /// 生成参数化SQL
+    ///
+    /// 返回SQL及每个占位符对应的参数索引,数组参数使用单个占位符
+    pub fn to_sql(&self, placeholder: Placeholder) -> (String, Vec<usize>) {
+        let mut sql = String::new();
+        let mut params = vec![];
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(v) => sql += v,
+                Segment::Argument(index) => {
+                    params.push(*index);
+                    placeholder.write(&mut sql, params.len());
+                }
+            }
+        }
+        (sql, params)
+    }
+
+    /// 绑定参数值
+    ///
+    /// 数组参数展开为多个占位符(空数组展开为`NULL`)
+    pub fn bind(
+        &self,
+        args: &[RetrieveArgument],
+        values: &[ArgumentValue],
+        placeholder: Placeholder
+    ) -> Result<SqlStatement, String> {
+        if values.len() != args.len() {
+            return Err(format!("expected {} arguments, got {}", args.len(), values.len()));
+        }
+        let mut sql = String::new();
+        let mut params = vec![];
+        for segment in &self.segments {
+            let index = match segment {
+                Segment::Text(v) => {
+                    sql += v;
+                    continue;
+                },
+                Segment::Argument(index) => *index
+            };
+            let arg = args.get(index).ok_or_else(|| format!("invalid argument index: {index}"))?;
+            match (&values[index], arg.ty.is_list()) {
+                (ArgumentValue::Single(value), false) => {
+                    params.push(value.clone());
+                    placeholder.write(&mut sql, params.len());
+                },
+                (ArgumentValue::List(list), true) => {
+                    if list.is_empty() {
+                        sql += "NULL";
+                    }
+                    for (i, value) in list.iter().enumerate() {
+                        if i > 0 {
+                            sql += ", ";
+                        }
+                        params.push(value.clone());
+                        placeholder.write(&mut sql, params.len());
+                    }
+                },
+                _ => return Err(format!("argument type mismatch: {} {}", arg.name, arg.ty))
+            }
+        }
+        Ok(SqlStatement {
+            sql,
+            params
+        })
+    }
+}
+
+impl<'a> ItemTable<'a> {
+    /// 获取检索参数(`arguments`)
+    pub fn arguments(&self) -> Result<Vec<RetrieveArgument>, String> {
+        let list = match self.values.get(&"arguments".into_key()) {
+            Some(Value::List(list)) => list,
+            Some(v) => return Err(format!("invalid arguments: {v:#}")),
+            None => return Ok(vec![])
+        };
+        list.iter()
+            .map(|arg| {
+                match arg {
+                    Value::List(v) if v.len() == 2 => {
+                        Ok(RetrieveArgument {
+                            name: v[0].as_str().map(unescape).unwrap_or_default(),
+                            ty: v[1].as_str().unwrap_or_default().parse()?
+                        })
+                    },
+                    v => Err(format!("invalid argument: {v:#}"))
+                }
+            })
+            .collect()
+    }
+
+    /// 设置检索参数(`arguments`),为空时删除
+    pub fn set_arguments(&mut self, args: &[RetrieveArgument]) {
+        if args.is_empty() {
+            #[cfg(feature = "preserve_order")]
+            self.values.shift_remove(&"arguments".into_key());
+            #[cfg(not(feature = "preserve_order"))]
+            self.values.remove(&"arguments".into_key());
+            return;
+        }
+        let list = args
+            .iter()
+            .map(|arg| {
+                Value::List(vec![
+                    Value::DoubleQuotedString(escape(&arg.name).into()),
+                    Value::Literal(arg.ty.to_string().into()),
+                ])
+            })
+            .collect();
+        self.values.insert("arguments".into_key(), Value::List(list));
+    }
+
+    /// 获取检索SQL(`retrieve`)
+    ///
+    /// `PBSELECT`语法转换为SQL
+    pub fn retrieve_sql(&self) -> Option<String> {
+        let retrieve = self.values.get(&"retrieve".into_key())?.as_str()?;
+        match PbSelect::parse(retrieve) {
+            Some(select) => Some(select.to_sql()),
+            None => Some(unescape(retrieve))
+        }
+    }
+
+    /// 解析检索SQL中的参数引用
+    pub fn retrieve(&self) -> Result<RetrieveSql, String> {
+        let sql = self.retrieve_sql().ok_or("no retrieve sql")?;
+        RetrieveSql::parse(&sql, &self.arguments()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+table(column=(type=long name=id dbname="emp.id") column=(type=char(20) name=name dbname="emp.name")
+ retrieve="SELECT id, name FROM emp WHERE dept = :dept AND id IN (:ids) AND name <> ':dept' AND note::text = :Dept -- :x
+" arguments=(("dept", number), ("ids", numberlist), ("unused", string)))
+"#;
+
+    #[test]
+    fn test_retrieve_arguments() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let args = dw.table.arguments().unwrap();
+        assert_eq!(args[1], RetrieveArgument {
+            name: "ids".to_owned(),
+            ty: ArgumentType::NumberList
+        });
+        assert!(args[1].ty.is_list());
+        assert_eq!(args[1].ty.element(), ArgumentType::Number);
+
+        let retrieve = dw.table.retrieve().unwrap();
+        assert_eq!(retrieve.unused, vec!["unused"]);
+        let (sql, params) = retrieve.to_sql(Placeholder::Dollar);
+        assert_eq!(
+            sql,
+            "SELECT id, name FROM emp WHERE dept = $1 AND id IN ($2) AND name <> ':dept' AND note::text = $3 -- :x\n"
+        );
+        assert_eq!(params, vec![0, 1, 0]);
+
+        let values = [ArgumentValue::from(10.), ArgumentValue::List(vec![1.0.into(), 2.0.into()]), "".into()];
+        let stmt = retrieve.bind(&args, &values, Placeholder::AtP).unwrap();
+        assert!(stmt.sql.contains("dept = @p1 AND id IN (@p2, @p3)"));
+        assert!(stmt.sql.contains("note::text = @p4"));
+        assert_eq!(stmt.params, vec![10.0.into(), 1.0.into(), 2.0.into(), 10.0.into()]);
+        let values = [ArgumentValue::from(10.), 1.0.into(), "".into()];
+        assert!(retrieve.bind(&args, &values, Placeholder::Question).is_err());
+
+        assert_eq!(RetrieveSql::parse("SELECT :nosuch", &args).unwrap_err(), "undeclared argument: nosuch");
+
+        dw.table.set_arguments(&args[..1]);
+        assert!(dw.to_string().contains("arguments=((\"dept\", number))"));
+        assert!(dw.table.retrieve().is_err());
+    }
+}
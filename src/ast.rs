@@ -1,10 +1,13 @@
-use crate::{parser, prelude::*};
+use crate::{SyntaxIndex, parser, prelude::*};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
 
 /// DataWindow语法结构
-#[derive(Debug, PartialEq)]
+///
+/// 包含私有的索引及大小写匹配方式,不能使用结构体字面量构造,通过`DWSyntax::new`、
+/// `DWSyntax::parse`或`DWSyntaxBuilder`创建
+#[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DWSyntax<'a> {
     /// `.srd`文件对象名
@@ -105,43 +108,107 @@ pub struct DWSyntax<'a> {
     /// compute(key=value key=value)
     /// ```
     #[cfg_attr(feature = "serde", serde(borrow))]
-    pub items: Vec<Item<'a>>
+    pub items: Vec<Item<'a>>,
+    /// 语法项索引,未创建时线性查找
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) index: SyntaxIndex,
+    /// `describe`/`modify`时忽略名称及参数名的大小写
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_case_insensitive"))]
+    pub(crate) case_insensitive: bool
+}
+
+/// 默认的大小写匹配方式,启用`case_insensitive`特性时默认忽略大小写
+pub(crate) fn default_case_insensitive() -> bool { cfg!(feature = "case_insensitive") }
+
+/// 比较语法内容,不比较索引及大小写匹配方式
+impl PartialEq for DWSyntax<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name &&
+            self.comment == other.comment &&
+            self.version == other.version &&
+            self.datawindow == other.datawindow &&
+            self.header == other.header &&
+            self.summary == other.summary &&
+            self.footer == other.footer &&
+            self.detail == other.detail &&
+            self.table == other.table &&
+            self.data == other.data &&
+            self.items == other.items
+    }
 }
 
 impl<'a> DWSyntax<'a> {
+    /// 创建指定语法版本的空语法
+    pub fn new(version: f64) -> Self {
+        DWSyntax {
+            name: None,
+            comment: None,
+            version,
+            datawindow: Default::default(),
+            header: Default::default(),
+            summary: Default::default(),
+            footer: Default::default(),
+            detail: Default::default(),
+            table: Default::default(),
+            data: Default::default(),
+            items: vec![],
+            index: Default::default(),
+            case_insensitive: default_case_insensitive()
+        }
+    }
+
     /// 解析语法
     pub fn parse(input: &'a str) -> Result<Self, String> {
         parser::parse(input).map_err(|e| parser::friendly_error(input, e))
     }
 
+    /// `describe`/`modify`时是否忽略名称及参数名的大小写
+    pub fn is_case_insensitive(&self) -> bool { self.case_insensitive }
+
+    /// 设置`describe`/`modify`时是否忽略名称及参数名的大小写
+    ///
+    /// 默认值取决于`case_insensitive`特性;启用该特性时`values`的键类型本身忽略大小写,
+    /// 直接调用`get`查找参数不受此设置影响
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+    }
+
+    /// 是否忽略大小写
+    pub(crate) fn ignore_case(&self) -> bool { self.case_insensitive }
+
+    /// 语法项索引
+    pub fn index(&self) -> &SyntaxIndex { &self.index }
+
+    /// 拷贝值并协变为目标生命期
+    pub fn to_owned<'r>(&self) -> DWSyntax<'r> {
+        DWSyntax {
+            name: self.name.as_ref().map(|v| Cow::Owned(v.clone().into_owned())),
+            comment: self.comment.as_ref().map(|v| Cow::Owned(v.clone().into_owned())),
+            version: self.version,
+            datawindow: map_to_owned(&self.datawindow),
+            header: map_to_owned(&self.header),
+            summary: map_to_owned(&self.summary),
+            footer: map_to_owned(&self.footer),
+            detail: map_to_owned(&self.detail),
+            table: self.table.to_owned(),
+            data: self.data.iter().map(|v| v.to_owned()).collect(),
+            items: self.items.iter().map(|v| v.to_owned()).collect(),
+            index: self.index.clone(),
+            case_insensitive: self.case_insensitive
+        }
+    }
+
     /// 获取指定语法项的参数值
     ///
     /// 兼容`DataWindow::Describe`参数和返回值
     #[cfg(feature = "query")]
     pub fn describe(&self, selector: &str) -> String {
+        if let Some(v) = self.describe_pseudo(selector) {
+            return v;
+        }
         match parser::query::find(self, selector) {
             Ok(Some(v)) => v.to_string(),
-            Ok(None) => {
-                #[cfg(feature = "case_insensitive")]
-                let selector = selector.to_ascii_lowercase();
-                match selector.trim() {
-                    "datawindow.column.count" => self.table.columns.len().to_string(),
-                    "datawindow.syntax" => self.to_string(),
-                    "datawindow.objects" => {
-                        let mut objects = String::with_capacity(self.items.len() * 20);
-                        for item in &self.items {
-                            if let Some(name) = &item.name {
-                                if !objects.is_empty() {
-                                    objects += "\t";
-                                }
-                                objects += name;
-                            }
-                        }
-                        objects
-                    },
-                    _ => "?".to_owned()
-                }
-            },
+            Ok(None) => "?".to_owned(),
             Err(_) => "!".to_owned()
         }
     }
@@ -159,11 +226,31 @@ impl<'a> DWSyntax<'a> {
     /// 兼容`DataWindow::Modify`参数和返回值
     #[cfg(feature = "query")]
     pub fn modify(&mut self, modifier: &str) -> String {
-        match parser::query::modify(self, modifier) {
+        match self.try_modify(modifier) {
             Ok(_) => "".to_owned(),
             Err(e) => e.to_string()
         }
     }
+
+    /// 修改语法项的参数值
+    ///
+    /// 兼容`DataWindow::Modify`参数
+    #[cfg(feature = "query")]
+    pub fn try_modify(&mut self, modifier: &str) -> Result<(), parser::query::ModifyError> {
+        parser::query::modify(self, modifier).map_err(|e| parser::query::ModifyError::from_error(modifier, e))
+    }
+
+    /// 预演修改
+    ///
+    /// 执行后撤销(语法保持不变),返回执行`modify`将产生的变更
+    #[cfg(feature = "query")]
+    pub fn modify_dry_run(
+        &mut self,
+        modifier: &str
+    ) -> Result<Vec<parser::query::ModifyChange>, parser::query::ModifyError> {
+        parser::query::modify_dry_run(self, modifier)
+            .map_err(|e| parser::query::ModifyError::from_error(modifier, e))
+    }
 }
 
 impl<'a> Display for DWSyntax<'a> {
@@ -417,6 +504,43 @@ impl<'a> Value<'a> {
         }
     }
 
+    /// 获取字面值或字符串
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Literal(v) | Value::DoubleQuotedString(v) | Value::SingleQuotedString(v) => Some(v),
+            _ => None
+        }
+    }
+
+    /// 转换为数值
+    ///
+    /// 兼容字符串格式的数值,如:`"100"`
+    pub fn to_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(v) => Some(*v),
+            _ => self.as_str().and_then(|v| v.trim().parse().ok())
+        }
+    }
+
+    /// 转换为布尔值
+    ///
+    /// 兼容`yes`/`no`,`true`/`false`和`1`/`0`
+    pub fn to_bool(&self) -> Option<bool> {
+        match self {
+            Value::Number(v) => Some(*v != 0.),
+            _ => {
+                let v = self.as_str()?.trim();
+                if v.eq_ignore_ascii_case("yes") || v.eq_ignore_ascii_case("true") || v == "1" {
+                    Some(true)
+                } else if v.eq_ignore_ascii_case("no") || v.eq_ignore_ascii_case("false") || v == "0" {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
     /// 拷贝值并协变为目标生命期
     pub(crate) fn to_owned<'r>(&self) -> Value<'r> {
         match self {
@@ -439,7 +563,7 @@ impl<'a> Display for Value<'a> {
         match self {
             Value::Literal(v) => write!(f, "{v}"),
             Value::DoubleQuotedString(v) => {
-                if f.alternate() || v.contains(&['\r', '\n', '\t']) {
+                if f.alternate() || v.contains(['\r', '\n', '\t']) {
                     write!(f, "\"{v}\"")
                 } else {
                     if v.contains("~") {
@@ -450,7 +574,7 @@ impl<'a> Display for Value<'a> {
                 }
             },
             Value::SingleQuotedString(v) => {
-                if f.alternate() || v.contains(&['\r', '\n', '\t']) {
+                if f.alternate() || v.contains(['\r', '\n', '\t']) {
                     write!(f, "'{v}'")
                 } else {
                     if v.contains("~") {
@@ -461,12 +585,17 @@ impl<'a> Display for Value<'a> {
                 }
             },
             Value::Number(v) => write!(f, "{v}"),
-            Value::Map(v) => write!(f, "({})", MapDisplay(&v)),
-            Value::List(v) => write!(f, "({})", ListDisplay(&v))
+            Value::Map(v) => write!(f, "({})", MapDisplay(v)),
+            Value::List(v) => write!(f, "({})", ListDisplay(v))
         }
     }
 }
 
+/// 拷贝参数列表并协变为目标生命期
+pub(crate) fn map_to_owned<'r>(values: &HashMap<Key, Value>) -> HashMap<Key<'r>, Value<'r>> {
+    values.iter().map(|(k, v)| (Cow::clone(k).into_owned().into_key(), v.to_owned())).collect()
+}
+
 struct MapDisplay<'a>(&'a HashMap<Key<'a>, Value<'a>>);
 
 impl<'a> Display for MapDisplay<'a> {
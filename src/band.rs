@@ -0,0 +1,111 @@
+use crate::{ast::*, prelude::*};
+use std::{
+    fmt::{self, Display}, str::FromStr
+};
+
+/// 控件所在的带区
+///
+/// # Syntax
+///
+/// ```txt
+/// text(band=header ...)
+/// compute(band=trailer.1 ...)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Band {
+    /// `header`
+    Header,
+    /// `detail`
+    Detail,
+    /// `summary`
+    Summary,
+    /// `footer`
+    Footer,
+    /// `header.<group #>`
+    GroupHeader(u32),
+    /// `trailer.<group #>`
+    GroupTrailer(u32),
+    /// `foreground`
+    Foreground,
+    /// `background`
+    Background
+}
+
+impl Band {
+    /// 分组带区的级别
+    pub fn group_level(&self) -> Option<u32> {
+        match self {
+            Band::GroupHeader(level) | Band::GroupTrailer(level) => Some(*level),
+            _ => None
+        }
+    }
+}
+
+impl FromStr for Band {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, level) = match s.split_once('.') {
+            Some((name, level)) => {
+                (name, Some(level.trim().parse::<u32>().map_err(|_| format!("invalid band: {s}"))?))
+            },
+            None => (s, None)
+        };
+        let band = match (name.to_ascii_lowercase().as_str(), level) {
+            ("header", None) => Band::Header,
+            ("detail", None) => Band::Detail,
+            ("summary", None) => Band::Summary,
+            ("footer", None) => Band::Footer,
+            ("foreground", None) => Band::Foreground,
+            ("background", None) => Band::Background,
+            ("header", Some(level)) => Band::GroupHeader(level),
+            ("trailer", Some(level)) => Band::GroupTrailer(level),
+            _ => return Err(format!("invalid band: {s}"))
+        };
+        Ok(band)
+    }
+}
+
+impl Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Band::Header => write!(f, "header"),
+            Band::Detail => write!(f, "detail"),
+            Band::Summary => write!(f, "summary"),
+            Band::Footer => write!(f, "footer"),
+            Band::GroupHeader(level) => write!(f, "header.{level}"),
+            Band::GroupTrailer(level) => write!(f, "trailer.{level}"),
+            Band::Foreground => write!(f, "foreground"),
+            Band::Background => write!(f, "background")
+        }
+    }
+}
+
+impl<'a> Item<'a> {
+    /// 获取控件所在的带区
+    pub fn band(&self) -> Option<Band> {
+        self.values.get(&"band".into_key())?.as_str()?.parse().ok()
+    }
+
+    /// 设置控件所在的带区
+    pub fn set_band(&mut self, band: Band) {
+        self.values.insert("band".into_key(), Value::Literal(band.to_string().into()));
+    }
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 按显示顺序获取所有带区
+    ///
+    /// `header`/`header.N`/`detail`/`trailer.N`/`summary`/`footer`
+    pub fn bands(&self) -> Vec<Band> {
+        let levels: Vec<u32> = self.groups().iter().filter_map(|group| group.level).collect();
+        let mut bands = vec![Band::Header];
+        bands.extend(levels.iter().map(|level| Band::GroupHeader(*level)));
+        bands.push(Band::Detail);
+        bands.extend(levels.iter().rev().map(|level| Band::GroupTrailer(*level)));
+        bands.push(Band::Summary);
+        bands.push(Band::Footer);
+        bands
+    }
+}
@@ -0,0 +1,463 @@
+use crate::{ast::*, band::Band, data::*, edit_style::EditStyle, prelude::*, rect::Rect};
+
+/// 默认字体及背景参数
+const FONT: [(&str, &str); 8] = [
+    ("font.face", "Tahoma"),
+    ("font.height", "-10"),
+    ("font.weight", "400"),
+    ("font.family", "2"),
+    ("font.pitch", "2"),
+    ("font.charset", "0"),
+    ("background.mode", "1"),
+    ("background.color", "536870912")
+];
+/// 默认文本颜色
+const COLOR: &str = "33554432";
+/// 默认带区背景色(透明)
+const BAND_COLOR: &str = "536870912";
+/// `tabsequence`步长
+const TAB_STEP: u32 = 10;
+
+/// DataWindow语法构造器
+///
+/// 填充与PB画板新建时一致的默认参数
+///
+/// # Example
+///
+/// ```
+/// use dwparser::{Band, ColType, DWSyntax, Rect};
+///
+/// let dw = DWSyntax::builder()
+///     .column("id", ColType::Long)
+///     .column("name", ColType::Char(20))
+///     .key("id")
+///     .text(Band::Header, "id_t", "Id", (9, 8, 200, 64))
+///     .column_control(Band::Detail, "id", Rect::new(9, 8, 200, 76))
+///     .retrieve_sql("SELECT id, name FROM emp")
+///     .update_table("emp", 1)
+///     .build()
+///     .unwrap();
+/// assert_eq!(dw.table.columns.len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct DWSyntaxBuilder {
+    syntax: DWSyntax<'static>,
+    /// 下一个字段控件的`tabsequence`
+    tab_sequence: u32,
+    /// 首个错误,`build`时返回
+    error: Option<String>
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 创建语法构造器
+    pub fn builder() -> DWSyntaxBuilder { DWSyntaxBuilder::new() }
+}
+
+impl Default for DWSyntaxBuilder {
+    fn default() -> Self { Self::new() }
+}
+
+impl DWSyntaxBuilder {
+    pub fn new() -> Self {
+        let mut datawindow = HashMap::default();
+        for (key, value) in [
+            ("units", 0.),
+            ("timer_interval", 0.),
+            ("color", 1073741824.),
+            ("processing", 0.),
+            ("print.orientation", 0.),
+            ("print.margin.left", 110.),
+            ("print.margin.right", 110.),
+            ("print.margin.top", 96.),
+            ("print.margin.bottom", 96.),
+            ("print.paper.source", 0.),
+            ("print.paper.size", 0.)
+        ] {
+            datawindow.insert(key.into_key(), Value::Number(value));
+        }
+        for key in ["print.prompt", "print.buttons", "print.preview.buttons"] {
+            datawindow.insert(key.into_key(), literal("no"));
+        }
+        datawindow.insert("print.canusedefaultprinter".into_key(), literal("yes"));
+        DWSyntaxBuilder {
+            syntax: DWSyntax {
+                datawindow,
+                header: band(72.),
+                summary: band(0.),
+                footer: band(0.),
+                detail: band(84.),
+                ..DWSyntax::new(19.)
+            },
+            tab_sequence: TAB_STEP,
+            error: None
+        }
+    }
+
+    /// `.srd`文件对象名
+    pub fn name(mut self, name: &str) -> Self {
+        self.syntax.name = Some(Cow::Owned(name.to_owned()));
+        self
+    }
+
+    /// `.srd`文件备注
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.syntax.comment = Some(Cow::Owned(comment.to_owned()));
+        self
+    }
+
+    /// 语法版本
+    pub fn release(mut self, version: f64) -> Self {
+        self.syntax.version = version;
+        self
+    }
+
+    /// `datawindow.units`
+    pub fn units(self, units: u32) -> Self { self.datawindow("units", Value::Number(units as f64)) }
+
+    /// `datawindow.processing`
+    pub fn processing(self, processing: u32) -> Self {
+        self.datawindow("processing", Value::Number(processing as f64))
+    }
+
+    /// 设置`datawindow`参数
+    pub fn datawindow(mut self, key: &str, value: Value<'static>) -> Self {
+        self.syntax.datawindow.insert(key.to_owned().into_key(), value);
+        self
+    }
+
+    /// 添加`table`字段,`dbname`与字段名相同
+    pub fn column(self, name: &str, ty: ColType) -> Self { self.column_db(name, ty, name) }
+
+    /// 添加`table`字段
+    pub fn column_db(mut self, name: &str, ty: ColType, dbname: &str) -> Self {
+        if self.syntax.table_column_index(name).is_some() {
+            return self.fail(format!("duplicate column: {name}"));
+        }
+        let mut column = ItemTableColumn {
+            name: Some(name.to_owned().into_key()),
+            values: HashMap::default()
+        };
+        column.set_col_type(ty);
+        column.values.insert("updatewhereclause".into_key(), literal("yes"));
+        column.values.insert("name".into_key(), literal(name));
+        column.values.insert("dbname".into_key(), string(dbname));
+        self.syntax.table.columns.push(column);
+        self
+    }
+
+    /// 将`table`字段设置为主键(`key=yes`)
+    pub fn key(mut self, name: &str) -> Self {
+        match self.syntax.table_column_index(name) {
+            Some(index) => {
+                self.syntax.table.columns[index].values.insert("key".into_key(), literal("yes"));
+                self
+            },
+            None => self.fail(format!("unknown column: {name}"))
+        }
+    }
+
+    /// `header`带区高度
+    pub fn band_header(mut self, height: u32) -> Self {
+        self.syntax.header.insert("height".into_key(), Value::Number(height as f64));
+        self
+    }
+
+    /// `detail`带区高度
+    pub fn band_detail(mut self, height: u32) -> Self {
+        self.syntax.detail.insert("height".into_key(), Value::Number(height as f64));
+        self
+    }
+
+    /// `summary`带区高度
+    pub fn band_summary(mut self, height: u32) -> Self {
+        self.syntax.summary.insert("height".into_key(), Value::Number(height as f64));
+        self
+    }
+
+    /// `footer`带区高度
+    pub fn band_footer(mut self, height: u32) -> Self {
+        self.syntax.footer.insert("height".into_key(), Value::Number(height as f64));
+        self
+    }
+
+    /// 添加下一级分组
+    pub fn group(mut self, by: &[&str], header_height: u32, trailer_height: u32) -> Self {
+        let level = self.syntax.groups().len() as u32 + 1;
+        let by: Vec<String> = by.iter().map(|v| v.to_string()).collect();
+        if let Err(e) = self.syntax.insert_group(level, by) {
+            return self.fail(e);
+        }
+        //SAFETY: 已插入
+        let group = self.syntax.group_mut(level).unwrap();
+        group.values.insert("header.height".into_key(), Value::Number(header_height as f64));
+        group.values.insert("trailer.height".into_key(), Value::Number(trailer_height as f64));
+        self
+    }
+
+    /// 添加文本控件
+    pub fn text(self, band: Band, name: &str, text: &str, rect: impl Into<Rect>) -> Self {
+        let mut values = HashMap::default();
+        values.insert("band".into_key(), literal(&band.to_string()));
+        values.insert("alignment".into_key(), string(if band == Band::Header { "2" } else { "0" }));
+        values.insert("text".into_key(), string(text));
+        values.insert("border".into_key(), string("0"));
+        values.insert("color".into_key(), string(COLOR));
+        self.control("text", name, None, values, rect.into())
+    }
+
+    /// 添加字段控件
+    ///
+    /// 控件名为字段名,按添加顺序分配`tabsequence`,使用`edit`编辑风格
+    pub fn column_control(mut self, band: Band, column: &str, rect: impl Into<Rect>) -> Self {
+        let index = match self.syntax.table_column_index(column) {
+            Some(index) => index,
+            None => return self.fail(format!("unknown column: {column}"))
+        };
+        let ty = self.syntax.table.columns[index].col_type();
+        let (alignment, format) = match ty {
+            Some(ty) if ty.is_numeric() => ("1", "[general]"),
+            Some(ColType::Date) => ("0", "[shortdate]"),
+            Some(ColType::DateTime | ColType::Timestamp) => ("0", "[shortdate] [time]"),
+            Some(ColType::Time) => ("0", "[time]"),
+            _ => ("0", "[general]")
+        };
+        let limit = match ty {
+            Some(ColType::Char(size)) => size,
+            _ => 0
+        };
+        let mut values = HashMap::default();
+        values.insert("band".into_key(), literal(&band.to_string()));
+        values.insert("id".into_key(), Value::Number((index + 1) as f64));
+        values.insert("alignment".into_key(), string(alignment));
+        values.insert("tabsequence".into_key(), Value::Number(self.tab_sequence as f64));
+        values.insert("border".into_key(), string("0"));
+        values.insert("color".into_key(), string(COLOR));
+        values.insert("format".into_key(), string(format));
+        self.tab_sequence += TAB_STEP;
+        let name = self.syntax.table.columns[index].name.as_ref().map(|v| v.to_string()).unwrap_or_default();
+        self = self.control("column", &name, Some(index as u32 + 1), values, rect.into());
+        let edit = [
+            ("edit.limit", Value::Number(limit as f64)),
+            ("edit.case", literal("any")),
+            ("edit.focusrectangle", literal("no")),
+            ("edit.autoselect", literal("yes")),
+            ("edit.autohscroll", literal("yes"))
+        ];
+        if let Some(item) = self.syntax.items.last_mut().filter(|item| item.kind == "column") {
+            for (key, value) in edit {
+                item.values.insert(key.into_key(), value);
+            }
+        }
+        self
+    }
+
+    /// 设置字段控件的编辑风格
+    pub fn edit_style(mut self, column: &str, style: &EditStyle<'static>) -> Self {
+        match self.item_mut("column", column) {
+            Some(item) => {
+                item.set_edit_style(style);
+                self
+            },
+            None => self.fail(format!("unknown column control: {column}"))
+        }
+    }
+
+    /// 添加计算字段控件
+    pub fn compute(self, band: Band, name: &str, expression: &str, rect: impl Into<Rect>) -> Self {
+        let mut values = HashMap::default();
+        values.insert("band".into_key(), literal(&band.to_string()));
+        values.insert("alignment".into_key(), string("0"));
+        values.insert("expression".into_key(), string(expression));
+        values.insert("border".into_key(), string("0"));
+        values.insert("color".into_key(), string(COLOR));
+        values.insert("format".into_key(), string("[general]"));
+        self.control("compute", name, None, values, rect.into())
+    }
+
+    /// 添加普通语法项
+    pub fn item(mut self, item: Item<'static>) -> Self {
+        self.syntax.items.push(item);
+        self
+    }
+
+    /// 数据源SQL(`table.retrieve`)
+    pub fn retrieve_sql(self, sql: &str) -> Self { self.table("retrieve", string(sql)) }
+
+    /// 更新表及更新条件(`table.update`/`table.updatewhere`)
+    ///
+    /// `updatewhere`: 0-主键 1-主键及可更新字段 2-主键及修改的字段
+    ///
+    /// 已添加的字段均设置为可更新(`update=yes`)
+    pub fn update_table(mut self, table: &str, updatewhere: u32) -> Self {
+        for column in &mut self.syntax.table.columns {
+            column.values.insert("update".into_key(), literal("yes"));
+        }
+        self.table("update", string(table))
+            .table("updatewhere", Value::Number(updatewhere as f64))
+            .table("updatekeyinplace", literal("no"))
+    }
+
+    /// 添加检索参数(`table.arguments`)
+    ///
+    /// `ty`为PB参数类型,如`string`/`number`/`date`
+    pub fn argument(mut self, name: &str, ty: &str) -> Self {
+        let arg = Value::List(vec![string(name), literal(ty)]);
+        match self.syntax.table.values.get_mut(&"arguments".into_key()) {
+            Some(Value::List(args)) => args.push(arg),
+            _ => {
+                self.syntax.table.values.insert("arguments".into_key(), Value::List(vec![arg]));
+            }
+        }
+        self
+    }
+
+    /// 设置`table`参数
+    pub fn table(mut self, key: &str, value: Value<'static>) -> Self {
+        self.syntax.table.values.insert(key.to_owned().into_key(), value);
+        self
+    }
+
+    /// 生成语法
+    ///
+    /// 返回构造过程中的首个错误
+    pub fn build(self) -> Result<DWSyntax<'static>, String> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let mut syntax = self.syntax;
+        if syntax.table.columns.is_empty() {
+            return Err("no table column".to_owned());
+        }
+        if !syntax.items.iter().any(|item| item.kind == "htmltable") {
+            let mut values = HashMap::default();
+            values.insert("border".into_key(), string("1"));
+            syntax.items.push(Item {
+                kind: "htmltable".into_key(),
+                name: None,
+                id: None,
+                level: None,
+                values
+            });
+        }
+        if !syntax.items.iter().any(|item| item.kind == "export.xml") {
+            let mut values = HashMap::default();
+            values.insert("headgroups".into_key(), string("1"));
+            values.insert("includewhitespace".into_key(), string("0"));
+            values.insert("metadatatype".into_key(), Value::Number(0.));
+            values.insert("savemetadata".into_key(), Value::Number(0.));
+            syntax.items.push(Item {
+                kind: "export.xml".into_key(),
+                name: None,
+                id: None,
+                level: None,
+                values
+            });
+        }
+        Ok(syntax)
+    }
+
+    /// 添加控件并填充默认参数
+    fn control(
+        mut self,
+        kind: &'static str,
+        name: &str,
+        id: Option<u32>,
+        mut values: HashMap<Key<'static>, Value<'static>>,
+        rect: Rect
+    ) -> Self {
+        let exists = |item: &Item| matches!(&item.name, Some(v) if self.syntax.name_eq(v, name));
+        if self.syntax.items.iter().any(exists) {
+            return self.fail(format!("duplicate control name: {name}"));
+        }
+        for (key, value) in [("x", rect.x), ("y", rect.y), ("height", rect.height), ("width", rect.width)] {
+            values.insert(key.into_key(), string(&value.to_string()));
+        }
+        values.insert("html.valueishtml".into_key(), string("0"));
+        values.insert("name".into_key(), literal(name));
+        values.insert("visible".into_key(), string("1"));
+        for (key, value) in FONT {
+            values.insert(key.into_key(), string(value));
+        }
+        self.syntax.items.push(Item {
+            kind: kind.into_key(),
+            name: Some(name.to_owned().into_key()),
+            id,
+            level: None,
+            values
+        });
+        self
+    }
+
+    fn item_mut(&mut self, kind: &str, name: &str) -> Option<&mut Item<'static>> {
+        let index = self.syntax.items.iter().position(|item| {
+            item.kind == kind && matches!(&item.name, Some(v) if self.syntax.name_eq(v, name))
+        })?;
+        Some(&mut self.syntax.items[index])
+    }
+
+    /// 记录错误(保留首个)
+    fn fail(mut self, error: String) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+}
+
+fn band(height: f64) -> HashMap<Key<'static>, Value<'static>> {
+    let mut values = HashMap::default();
+    values.insert("height".into_key(), Value::Number(height));
+    values.insert("color".into_key(), string(BAND_COLOR));
+    values
+}
+
+fn literal(value: &str) -> Value<'static> { Value::Literal(value.to_owned().into()) }
+
+fn string(value: &str) -> Value<'static> { Value::DoubleQuotedString(escape(value).into()) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presentation::PresentationStyle;
+
+    #[test]
+    fn test_builder() {
+        let dw = DWSyntax::builder()
+            .name("d_emp")
+            .column("emp_id", ColType::Long)
+            .column_db("emp_name", ColType::Char(40), "employee.emp_name")
+            .column("dept_id", ColType::Long)
+            .key("emp_id")
+            .band_header(80)
+            .band_detail(92)
+            .group(&["dept_id"], 76, 0)
+            .text(Band::Header, "emp_id_t", "Emp Id", (9, 8, 200, 64))
+            .text(Band::Header, "emp_name_t", "Name \"A\"", (219, 8, 400, 64))
+            .column_control(Band::Detail, "emp_id", (9, 8, 200, 76))
+            .column_control(Band::Detail, "emp_name", (219, 8, 400, 76))
+            .compute(Band::Summary, "cnt", "count(emp_id for all)", (9, 8, 200, 76))
+            .retrieve_sql("SELECT emp_id, emp_name, dept_id FROM employee WHERE dept_id = :dept")
+            .argument("dept", "number")
+            .update_table("employee", 1)
+            .build()
+            .unwrap();
+
+        //输出可重新解析
+        let syntax = dw.to_string();
+        let dw = DWSyntax::parse(&syntax).unwrap();
+        assert_eq!(dw.presentation_style(), PresentationStyle::Tabular);
+        assert_eq!(dw.table.columns[1].col_type(), Some(ColType::Char(40)));
+        assert_eq!(dw.table.columns[0].values.get(&"key".into_key()).and_then(|v| v.to_bool()), Some(true));
+        assert_eq!(dw.groups()[0].group_by(), vec!["dept_id"]);
+        let column = dw.items.iter().find(|item| matches!(&item.name, Some(v) if *v == "emp_name")).unwrap();
+        assert_eq!(column.id, Some(2));
+        assert_eq!(column.values.get(&"tabsequence".into_key()).and_then(|v| v.to_number()), Some(20.));
+        assert_eq!(column.values.get(&"edit.limit".into_key()).and_then(|v| v.to_number()), Some(40.));
+        assert!(syntax.contains("text=\"Name ~\"A~\"\""));
+        assert!(syntax.contains("arguments=((\"dept\", number))"));
+        assert!(syntax.contains("update=\"employee\" updatewhere=1"));
+
+        let err =
+            DWSyntax::builder().column("id", ColType::Long).column_control(Band::Detail, "x", (0, 0, 0, 0));
+        assert_eq!(err.build().unwrap_err(), "unknown column: x");
+        assert!(DWSyntax::builder().build().is_err());
+    }
+}
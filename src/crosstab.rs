@@ -0,0 +1,367 @@
+use crate::{
+    ast::*, band::Band, data::*, graph::{is_ident, parse_aggregate, split_list}, prelude::*,
+    presentation::PresentationStyle
+};
+use std::{cmp::Ordering, collections::BTreeMap};
+
+/// 交叉表的值表达式
+///
+/// `sum(amount for crosstab)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrosstabValue {
+    /// 聚合函数名(小写)
+    pub func: String,
+    /// 源字段名
+    pub column: String
+}
+
+impl CrosstabValue {
+    /// 解析值表达式
+    ///
+    /// 仅有字段名时按`sum`处理
+    pub fn parse(expr: &str) -> Option<Self> {
+        let expr = expr.trim();
+        if is_ident(expr) {
+            return Some(CrosstabValue {
+                func: "sum".to_owned(),
+                column: expr.to_owned()
+            });
+        }
+        let (func, column) = parse_aggregate(expr, "crosstab")?;
+        Some(CrosstabValue {
+            func,
+            column
+        })
+    }
+}
+
+impl std::fmt::Display for CrosstabValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({} for crosstab)", self.func, self.column)
+    }
+}
+
+/// 交叉表生成的汇总计算列
+///
+/// `compute(name=grand_sum_amount expression="crosstabsum(1)" ...)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrosstabCompute {
+    pub name: Option<String>,
+    /// 汇总函数名(去掉`crosstab`前缀,小写)
+    pub func: String,
+    /// 值表达式序号(从1开始)
+    pub value: usize
+}
+
+impl CrosstabCompute {
+    /// 解析`crosstabxxx(n)`表达式
+    pub fn parse(expr: &str) -> Option<Self> {
+        let (func, rest) = expr.trim().split_once('(')?;
+        let func = func.trim().to_ascii_lowercase();
+        let func = func.strip_prefix("crosstab")?;
+        let value = rest.trim_end().strip_suffix(')')?.trim().parse().ok()?;
+        Some(CrosstabCompute {
+            name: None,
+            func: func.to_owned(),
+            value
+        })
+    }
+}
+
+/// 交叉表定义
+///
+/// # Syntax
+///
+/// ```txt
+/// datawindow(processing=4 ...)
+/// crosstab(band=foreground columns="year" rows="region" values="sum(amount for crosstab)"
+///     sourcenames="region, year, amount")
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crosstab {
+    pub band: Option<Band>,
+    /// 列分组字段
+    pub columns: Vec<String>,
+    /// 行分组字段
+    pub rows: Vec<String>,
+    /// 值表达式
+    pub values: Vec<CrosstabValue>,
+    /// 源字段名(与`table`字段按顺序对应)
+    pub source_names: Vec<String>,
+    /// 汇总计算列
+    pub computes: Vec<CrosstabCompute>
+}
+
+impl Crosstab {
+    /// 从`crosstab`语法项读取
+    pub fn from_item(item: &Item) -> Option<Self> {
+        if item.kind != "crosstab" {
+            return None;
+        }
+        let string = |key: &'static str| {
+            item.values.get(&key.into_key()).and_then(|v| v.as_str()).map(unescape).unwrap_or_default()
+        };
+        let names = |key: &'static str| {
+            string(key)
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+        Some(Crosstab {
+            band: item.band(),
+            columns: names("columns"),
+            rows: names("rows"),
+            values: split_list(&string("values")).into_iter().filter_map(CrosstabValue::parse).collect(),
+            source_names: names("sourcenames"),
+            computes: vec![]
+        })
+    }
+
+    /// 写入`crosstab`语法项的参数列表
+    pub fn write_to<'a>(&self, values: &mut HashMap<Key<'a>, Value<'a>>) {
+        let string = |v: String| Value::DoubleQuotedString(escape(&v).into());
+        let values_expr = self.values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        values.insert("columns".into_key(), string(self.columns.join(", ")));
+        values.insert("rows".into_key(), string(self.rows.join(", ")));
+        values.insert("values".into_key(), string(values_expr.join(", ")));
+        values.insert("sourcenames".into_key(), string(self.source_names.join(", ")));
+    }
+
+    /// 按PB的方式对`data`项中的数据进行交叉汇总
+    ///
+    /// 行/列分组值按升序排列,没有数据的单元格为`None`
+    pub fn pivot(&self, syn: &DWSyntax) -> Result<CrosstabData, String> {
+        let column = |name: &str| {
+            self.source_names
+                .iter()
+                .position(|v| syn.name_eq(v, name))
+                .or_else(|| syn.table_column_index(name))
+                .filter(|i| *i < syn.table.columns.len())
+                .ok_or_else(|| format!("unknown column: {name}"))
+        };
+        let rows = self.rows.iter().map(|v| column(v)).collect::<Result<Vec<_>, _>>()?;
+        let columns = self.columns.iter().map(|v| column(v)).collect::<Result<Vec<_>, _>>()?;
+        let mut values = Vec::with_capacity(self.values.len());
+        for value in &self.values {
+            if !matches!(value.func.as_str(), "sum" | "count" | "avg" | "min" | "max") {
+                return Err(format!("unsupported crosstab function: {}", value.func));
+            }
+            values.push((column(&value.column)?, value.func.as_str()));
+        }
+
+        let source = syn.data_rows();
+        let keys =
+            |row: &[DataValue], cols: &[usize]| cols.iter().map(|i| row[*i].clone()).collect::<Vec<_>>();
+        //分组值 -> 列位置
+        let mut column_index = BTreeMap::new();
+        for row in &source {
+            column_index.entry(GroupKey(keys(row, &columns))).or_insert(0);
+        }
+        let mut data = CrosstabData {
+            columns: Vec::with_capacity(column_index.len()),
+            rows: vec![],
+            value_count: values.len()
+        };
+        for (ci, (key, index)) in column_index.iter_mut().enumerate() {
+            *index = ci;
+            data.columns.push(key.0.clone());
+        }
+        //行分组值 -> [column * values + value]
+        let mut buckets: BTreeMap<GroupKey, Vec<Option<Accumulator>>> = BTreeMap::new();
+        for row in &source {
+            let ci = column_index[&GroupKey(keys(row, &columns))];
+            let acc = buckets
+                .entry(GroupKey(keys(row, &rows)))
+                .or_insert_with(|| vec![None; data.columns.len() * values.len()]);
+            for (vi, (col, func)) in values.iter().enumerate() {
+                let slot = acc[ci * values.len() + vi].get_or_insert((0., 0, f64::MAX, f64::MIN));
+                match row[*col].as_number() {
+                    Some(v) => {
+                        slot.0 += v;
+                        slot.1 += 1;
+                        slot.2 = slot.2.min(v);
+                        slot.3 = slot.3.max(v);
+                    },
+                    None if !row[*col].is_null() && *func == "count" => slot.1 += 1,
+                    None => {}
+                }
+            }
+        }
+        for (key, acc) in buckets {
+            let cells = acc
+                .into_iter()
+                .enumerate()
+                .map(|(i, slot)| {
+                    let (sum, count, min, max) = slot?;
+                    match values[i % values.len()].1 {
+                        "count" => Some(count as f64),
+                        _ if count == 0 => None,
+                        "avg" => Some(sum / count as f64),
+                        "min" => Some(min),
+                        "max" => Some(max),
+                        _ => Some(sum)
+                    }
+                })
+                .collect();
+            data.rows.push(CrosstabRow {
+                keys: key.0,
+                cells
+            });
+        }
+        Ok(data)
+    }
+}
+
+/// 交叉汇总结果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CrosstabData {
+    /// 列分组值
+    pub columns: Vec<Vec<DataValue>>,
+    /// 数据行
+    pub rows: Vec<CrosstabRow>,
+    /// 值表达式数量
+    pub value_count: usize
+}
+
+/// 交叉汇总结果行
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CrosstabRow {
+    /// 行分组值
+    pub keys: Vec<DataValue>,
+    /// 单元格(`cells[column * value_count + value]`)
+    pub cells: Vec<Option<f64>>
+}
+
+impl CrosstabData {
+    /// 获取单元格的值
+    pub fn cell(&self, row: usize, column: usize, value: usize) -> Option<f64> {
+        *self.rows.get(row)?.cells.get(column * self.value_count + value)?
+    }
+
+    /// 计算行的汇总列(`crosstabsum(n)`等)
+    ///
+    /// 忽略没有数据的单元格
+    pub fn compute(&self, row: usize, compute: &CrosstabCompute) -> Option<f64> {
+        let value = compute.value.checked_sub(1).filter(|v| *v < self.value_count)?;
+        let cells: Vec<f64> = (0..self.columns.len()).filter_map(|c| self.cell(row, c, value)).collect();
+        match compute.func.as_str() {
+            "count" => return Some(cells.len() as f64),
+            _ if cells.is_empty() => return None,
+            _ => {}
+        }
+        match compute.func.as_str() {
+            "sum" | "sumdec" => Some(cells.iter().sum()),
+            "avg" | "avgdec" => Some(cells.iter().sum::<f64>() / cells.len() as f64),
+            "min" | "mindec" => cells.into_iter().reduce(f64::min),
+            "max" | "maxdec" => cells.into_iter().reduce(f64::max),
+            _ => None
+        }
+    }
+}
+
+/// (sum, count, min, max)
+type Accumulator = (f64, usize, f64, f64);
+
+/// 分组值(按`DataValue`的排序规则比较)
+struct GroupKey(Vec<DataValue>);
+
+impl Ord for GroupKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .map(|(a, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .find(|v| v.is_ne())
+            .unwrap_or_else(|| self.0.len().cmp(&other.0.len()))
+    }
+}
+
+impl PartialOrd for GroupKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl PartialEq for GroupKey {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other).is_eq() }
+}
+
+impl Eq for GroupKey {}
+
+impl<'a> DWSyntax<'a> {
+    /// 是否为交叉表(`datawindow.processing=4`)
+    pub fn is_crosstab(&self) -> bool {
+        self.presentation_style() == PresentationStyle::Crosstab
+    }
+
+    /// 获取交叉表定义
+    ///
+    /// 同时收集使用`crosstabxxx(n)`表达式的计算列
+    pub fn crosstab(&self) -> Option<Crosstab> {
+        let mut crosstab = self.items.iter().find_map(Crosstab::from_item)?;
+        for item in &self.items {
+            if item.kind != "compute" {
+                continue;
+            }
+            let expr = item.values.get(&"expression".into_key()).and_then(|v| v.as_str()).map(unescape);
+            if let Some(mut compute) = expr.and_then(|v| CrosstabCompute::parse(&v)) {
+                compute.name = item.name.as_ref().map(|v| v.to_string());
+                crosstab.computes.push(compute);
+            }
+        }
+        Some(crosstab)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+datawindow(units=0 processing=4 print.margin.left=24)
+table(column=(type=char(10) name=region dbname="region") column=(type=long name=year dbname="year") column=(type=long name=amount dbname="amount"))
+compute(band=detail alignment="1" expression="crosstabsum(1)" name=grand_sum_amount x="0" y="0")
+compute(band=detail alignment="1" expression="crosstabcount(2)" name=grand_count x="0" y="0")
+crosstab(band=foreground crosstabonly=yes columns="year" rows="region" values="sum(amount for crosstab), count(amount for crosstab)" sourcenames="region, year, amount")
+data("north", 2024, 20, "south", 2023, 5, "north", 2023, 10, "north", 2023, 1, "east", 2024, null, )
+"#;
+
+    #[test]
+    fn test_crosstab() {
+        let dw = DWSyntax::parse(SYNTAX).unwrap();
+        assert!(dw.is_crosstab());
+        let crosstab = dw.crosstab().unwrap();
+        assert_eq!(crosstab.band, Some(Band::Foreground));
+        assert_eq!(crosstab.columns, vec!["year"]);
+        assert_eq!(crosstab.rows, vec!["region"]);
+        assert_eq!(crosstab.source_names, vec!["region", "year", "amount"]);
+        assert_eq!(crosstab.values[1], CrosstabValue {
+            func: "count".to_owned(),
+            column: "amount".to_owned()
+        });
+        assert_eq!(crosstab.computes.len(), 2);
+        assert_eq!(crosstab.computes[0].name.as_deref(), Some("grand_sum_amount"));
+
+        let data = crosstab.pivot(&dw).unwrap();
+        assert_eq!(data.columns, vec![vec![DataValue::Number(2023.)], vec![DataValue::Number(2024.)]]);
+        let keys: Vec<String> = data.rows.iter().map(|v| v.keys[0].to_string()).collect();
+        assert_eq!(keys, vec!["east", "north", "south"]);
+        assert_eq!(data.rows[0].cells, vec![None, None, None, Some(0.)]);
+        assert_eq!(data.rows[1].cells, vec![Some(11.), Some(2.), Some(20.), Some(1.)]);
+        assert_eq!(data.cell(2, 0, 0), Some(5.));
+        assert_eq!(data.cell(2, 1, 0), None);
+        assert_eq!(data.compute(1, &crosstab.computes[0]), Some(31.));
+        assert_eq!(data.compute(1, &crosstab.computes[1]), Some(2.));
+        assert_eq!(data.compute(0, &crosstab.computes[0]), None);
+
+        let mut item = dw.items.iter().find(|v| v.kind == "crosstab").unwrap().to_owned();
+        let mut crosstab = crosstab.clone();
+        crosstab.columns = vec!["region".to_owned()];
+        crosstab.rows = vec!["year".to_owned()];
+        crosstab.write_to(&mut item.values);
+        let crosstab = Crosstab::from_item(&item).unwrap();
+        let data = crosstab.pivot(&dw).unwrap();
+        assert_eq!(data.columns.len(), 3);
+        assert_eq!(data.cell(0, 1, 0), Some(11.));
+    }
+}
@@ -0,0 +1,505 @@
+use crate::{ast::*, prelude::*};
+use std::{
+    cmp::Ordering, fmt::{self, Display}, str::FromStr
+};
+
+/// 字段数据类型
+///
+/// # Syntax
+///
+/// ```txt
+/// table(column=(type=char(10) ...) column=(type=decimal(2) ...))
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColType {
+    /// `char(n)`
+    Char(u32),
+    /// `int`
+    Int,
+    /// `long`
+    Long,
+    /// `ulong`
+    ULong,
+    /// `longlong`
+    LongLong,
+    /// `decimal(n)`
+    Decimal(u32),
+    /// `number`
+    Number,
+    /// `real`
+    Real,
+    /// `date`
+    Date,
+    /// `datetime`
+    DateTime,
+    /// `time`
+    Time,
+    /// `timestamp`
+    Timestamp,
+    /// `blob`
+    Blob
+}
+
+impl ColType {
+    /// 是否为数值类型
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            ColType::Int |
+                ColType::Long |
+                ColType::ULong |
+                ColType::LongLong |
+                ColType::Decimal(_) |
+                ColType::Number |
+                ColType::Real
+        )
+    }
+
+    /// 是否为日期时间类型
+    pub fn is_temporal(&self) -> bool {
+        matches!(self, ColType::Date | ColType::DateTime | ColType::Time | ColType::Timestamp)
+    }
+
+    /// 是否为字符串类型
+    pub fn is_string(&self) -> bool { matches!(self, ColType::Char(_)) }
+}
+
+impl FromStr for ColType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid column type: {s}");
+        let (name, size) = match s.split_once('(') {
+            Some((name, size)) => {
+                let size = size.trim_end().strip_suffix(')').ok_or_else(err)?;
+                (name.trim(), Some(size.trim().parse::<u32>().map_err(|_| err())?))
+            },
+            None => (s.trim(), None)
+        };
+        let ty = match (name.to_ascii_lowercase().as_str(), size) {
+            ("char" | "string", Some(size)) => ColType::Char(size),
+            ("int", None) => ColType::Int,
+            ("long", None) => ColType::Long,
+            ("ulong", None) => ColType::ULong,
+            ("longlong", None) => ColType::LongLong,
+            ("decimal", Some(size)) => ColType::Decimal(size),
+            ("number", None) => ColType::Number,
+            ("real", None) => ColType::Real,
+            ("date", None) => ColType::Date,
+            ("datetime", None) => ColType::DateTime,
+            ("time", None) => ColType::Time,
+            ("timestamp", None) => ColType::Timestamp,
+            ("blob", None) => ColType::Blob,
+            _ => return Err(err())
+        };
+        Ok(ty)
+    }
+}
+
+impl Display for ColType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColType::Char(size) => write!(f, "char({size})"),
+            ColType::Int => write!(f, "int"),
+            ColType::Long => write!(f, "long"),
+            ColType::ULong => write!(f, "ulong"),
+            ColType::LongLong => write!(f, "longlong"),
+            ColType::Decimal(size) => write!(f, "decimal({size})"),
+            ColType::Number => write!(f, "number"),
+            ColType::Real => write!(f, "real"),
+            ColType::Date => write!(f, "date"),
+            ColType::DateTime => write!(f, "datetime"),
+            ColType::Time => write!(f, "time"),
+            ColType::Timestamp => write!(f, "timestamp"),
+            ColType::Blob => write!(f, "blob")
+        }
+    }
+}
+
+impl<'a> ItemTableColumn<'a> {
+    /// 获取字段数据类型
+    pub fn col_type(&self) -> Option<ColType> { self.values.get(&"type".into_key())?.as_str()?.parse().ok() }
+
+    /// 设置字段数据类型
+    pub fn set_col_type(&mut self, ty: ColType) {
+        self.values.insert("type".into_key(), Value::Literal(ty.to_string().into()));
+    }
+}
+
+/// 日期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Self {
+        Date {
+            year,
+            month,
+            day
+        }
+    }
+
+    /// 星期几(`0`为星期日)
+    pub fn weekday(&self) -> u32 {
+        //Zeller's congruence
+        let (y, m) = if self.month < 3 {
+            (self.year - 1, self.month + 12)
+        } else {
+            (self.year, self.month)
+        };
+        let (k, j) = (y.rem_euclid(100), y.div_euclid(100));
+        let h = (self.day as i32 + 13 * (m as i32 + 1) / 5 + k + k / 4 + j / 4 + 5 * j).rem_euclid(7);
+        ((h + 6) % 7) as u32
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    /// `yyyy-mm-dd`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid date: {s}");
+        let mut parts = s.trim().splitn(3, ['-', '/']);
+        let mut next = || parts.next().and_then(|v| v.trim().parse::<i32>().ok()).ok_or_else(err);
+        let (year, month, day) = (next()?, next()?, next()?);
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(err());
+        }
+        Ok(Date::new(year, month as u32, day as u32))
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// 时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Time {
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub microsecond: u32
+}
+
+impl Time {
+    pub fn new(hour: u32, minute: u32, second: u32, microsecond: u32) -> Self {
+        Time {
+            hour,
+            minute,
+            second,
+            microsecond
+        }
+    }
+}
+
+impl FromStr for Time {
+    type Err = String;
+
+    /// `hh:mm:ss:ffffff`/`hh:mm:ss.ffffff`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid time: {s}");
+        let mut parts = s.trim().splitn(4, [':', '.']);
+        let mut next = |required: bool| match parts.next() {
+            Some(v) => v.trim().parse::<u32>().map(|n| (n, v.trim().len())).map_err(|_| err()),
+            None if required => Err(err()),
+            None => Ok((0, 6))
+        };
+        let (hour, _) = next(true)?;
+        let (minute, _) = next(true)?;
+        let (second, _) = next(false)?;
+        let (fraction, digits) = next(false)?;
+        if hour > 23 || minute > 59 || second > 59 || digits > 6 {
+            return Err(err());
+        }
+        Ok(Time::new(hour, minute, second, fraction * 10u32.pow(6 - digits as u32)))
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        if self.microsecond != 0 {
+            write!(f, ".{:06}", self.microsecond)?;
+        }
+        Ok(())
+    }
+}
+
+/// 数据值
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataValue {
+    Null,
+    Number(f64),
+    String(String),
+    Date(Date),
+    Time(Time),
+    DateTime(Date, Time)
+}
+
+impl DataValue {
+    pub fn is_null(&self) -> bool { matches!(self, DataValue::Null) }
+
+    /// 获取数值
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            DataValue::Number(v) => Some(*v),
+            _ => None
+        }
+    }
+
+    /// 获取字符串
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            DataValue::String(v) => Some(v),
+            _ => None
+        }
+    }
+
+    /// 从`data`项的参数值转换
+    pub fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Number(v) => DataValue::Number(*v),
+            Value::DoubleQuotedString(v) | Value::SingleQuotedString(v) => DataValue::String(unescape(v)),
+            Value::Literal(v) => {
+                if v.eq_ignore_ascii_case("null") {
+                    return DataValue::Null;
+                }
+                if let Some((date, time)) = v.split_once(' ') {
+                    if let (Ok(date), Ok(time)) = (date.parse(), time.parse()) {
+                        return DataValue::DateTime(date, time);
+                    }
+                } else if let Ok(date) = v.parse() {
+                    return DataValue::Date(date);
+                } else if let Ok(time) = v.parse() {
+                    return DataValue::Time(time);
+                }
+                DataValue::String(v.to_string())
+            },
+            Value::Map(_) | Value::List(_) => DataValue::String(value.to_string())
+        }
+    }
+
+    /// 转换为`data`项的参数值
+    pub fn to_value<'r>(&self) -> Value<'r> {
+        /// `hh:mm:ss:ffffff`
+        fn time(v: &Time) -> String {
+            format!("{:02}:{:02}:{:02}:{:06}", v.hour, v.minute, v.second, v.microsecond)
+        }
+        match self {
+            DataValue::Null => Value::Literal("null".into()),
+            DataValue::Number(v) => Value::Number(*v),
+            DataValue::String(v) => Value::DoubleQuotedString(escape(v).into()),
+            DataValue::Date(v) => Value::Literal(v.to_string().into()),
+            DataValue::Time(v) => Value::Literal(time(v).into()),
+            DataValue::DateTime(d, t) => Value::Literal(format!("{d} {}", time(t)).into())
+        }
+    }
+
+    /// 按字段类型转换
+    ///
+    /// 无法转换时返回`None`
+    pub fn coerce(&self, ty: ColType) -> Option<DataValue> {
+        let rv = match (self, ty) {
+            (DataValue::Null, _) => DataValue::Null,
+            (DataValue::Number(v), ty) if ty.is_numeric() => DataValue::Number(*v),
+            (DataValue::Number(v), ColType::Char(_)) => DataValue::String(v.to_string()),
+            (DataValue::String(v), ty) if ty.is_numeric() => DataValue::Number(v.trim().parse().ok()?),
+            (DataValue::String(v), ColType::Date) => DataValue::Date(v.parse().ok()?),
+            (DataValue::String(v), ColType::Time) => DataValue::Time(v.parse().ok()?),
+            (DataValue::String(v), ColType::DateTime | ColType::Timestamp) => {
+                let (date, time) = v.trim().split_once([' ', 'T']).unwrap_or((v.trim(), "00:00:00"));
+                DataValue::DateTime(date.parse().ok()?, time.parse().ok()?)
+            },
+            (DataValue::String(_), ColType::Char(_) | ColType::Blob) => self.clone(),
+            (DataValue::Date(v), ColType::DateTime | ColType::Timestamp) => {
+                DataValue::DateTime(*v, Time::default())
+            },
+            (DataValue::Date(_), ColType::Date) | (DataValue::Time(_), ColType::Time) => self.clone(),
+            (DataValue::DateTime(..), ColType::DateTime | ColType::Timestamp) => self.clone(),
+            (DataValue::DateTime(d, _), ColType::Date) => DataValue::Date(*d),
+            (DataValue::DateTime(_, t), ColType::Time) => DataValue::Time(*t),
+            (_, ColType::Char(_)) => DataValue::String(self.to_string()),
+            _ => return None
+        };
+        Some(rv)
+    }
+}
+
+/// 排序规则:`Null`最小,同类型按值比较,不同类型按`Number`/`String`/`Date`/`Time`/`DateTime`顺序
+impl PartialOrd for DataValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        fn rank(v: &DataValue) -> u8 {
+            match v {
+                DataValue::Null => 0,
+                DataValue::Number(_) => 1,
+                DataValue::String(_) => 2,
+                DataValue::Date(_) => 3,
+                DataValue::Time(_) => 4,
+                DataValue::DateTime(..) => 5
+            }
+        }
+        match (self, other) {
+            (DataValue::Number(a), DataValue::Number(b)) => a.partial_cmp(b),
+            (DataValue::String(a), DataValue::String(b)) => Some(a.cmp(b)),
+            (DataValue::Date(a), DataValue::Date(b)) => Some(a.cmp(b)),
+            (DataValue::Time(a), DataValue::Time(b)) => Some(a.cmp(b)),
+            (DataValue::DateTime(d1, t1), DataValue::DateTime(d2, t2)) => Some((d1, t1).cmp(&(d2, t2))),
+            _ => Some(rank(self).cmp(&rank(other)))
+        }
+    }
+}
+
+impl Display for DataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataValue::Null => Ok(()),
+            DataValue::Number(v) => write!(f, "{v}"),
+            DataValue::String(v) => write!(f, "{v}"),
+            DataValue::Date(v) => write!(f, "{v}"),
+            DataValue::Time(v) => write!(f, "{v}"),
+            DataValue::DateTime(d, t) => write!(f, "{d} {t}")
+        }
+    }
+}
+
+impl From<f64> for DataValue {
+    fn from(v: f64) -> Self { DataValue::Number(v) }
+}
+
+impl From<&str> for DataValue {
+    fn from(v: &str) -> Self { DataValue::String(v.to_owned()) }
+}
+
+impl From<String> for DataValue {
+    fn from(v: String) -> Self { DataValue::String(v) }
+}
+
+impl From<Date> for DataValue {
+    fn from(v: Date) -> Self { DataValue::Date(v) }
+}
+
+impl From<Time> for DataValue {
+    fn from(v: Time) -> Self { DataValue::Time(v) }
+}
+
+impl<T: Into<DataValue>> From<Option<T>> for DataValue {
+    fn from(v: Option<T>) -> Self { v.map(Into::into).unwrap_or(DataValue::Null) }
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 按`table`字段数拆分`data`项,得到数据行
+    ///
+    /// 最后不完整的行将被忽略
+    pub fn data_rows(&self) -> Vec<Vec<DataValue>> {
+        let count = self.table.columns.len();
+        if count == 0 {
+            return vec![];
+        }
+        self.data.chunks_exact(count).map(|row| row.iter().map(DataValue::from_value).collect()).collect()
+    }
+
+    /// 获取`table`字段的索引,按`is_case_insensitive`比较字段名
+    pub(crate) fn table_column_index(&self, name: &str) -> Option<usize> { self.table_column_position(name) }
+}
+
+/// PB字符串转义还原
+///
+/// `~~` `~"` `~'` `~t` `~r` `~n`
+pub fn unescape(s: &str) -> String {
+    if !s.contains('~') {
+        return s.to_owned();
+    }
+    let mut rv = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            rv.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => rv.push('\t'),
+            Some('r') => rv.push('\r'),
+            Some('n') => rv.push('\n'),
+            Some(c) => rv.push(c),
+            None => rv.push('~')
+        }
+    }
+    rv
+}
+
+/// PB字符串转义(双引号字符串)
+pub fn escape(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '~' => rv.push_str("~~"),
+            '"' => rv.push_str("~\""),
+            _ => rv.push(c)
+        }
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_value() {
+        assert_eq!("char ( 80)".parse(), Ok(ColType::Char(80)));
+        assert_eq!("decimal(2)".parse(), Ok(ColType::Decimal(2)));
+        assert_eq!("Long".parse(), Ok(ColType::Long));
+        assert!("char".parse::<ColType>().is_err());
+        assert_eq!(ColType::Decimal(0).to_string(), "decimal(0)");
+
+        assert_eq!(Date::new(2023, 1, 1).weekday(), 0);
+        assert_eq!(Date::new(2024, 2, 29).weekday(), 4);
+        assert_eq!("11:11:23:0000".parse(), Ok(Time::new(11, 11, 23, 0)));
+        assert_eq!("11:11:23.5".parse(), Ok(Time::new(11, 11, 23, 500000)));
+        assert_eq!("11:11".parse(), Ok(Time::new(11, 11, 0, 0)));
+
+        let values = [
+            (Value::Literal("null".into()), DataValue::Null),
+            (Value::Number(1.5), DataValue::Number(1.5)),
+            (Value::DoubleQuotedString("a~\"b".into()), DataValue::String("a\"b".to_owned())),
+            (Value::Literal("2001-12-31".into()), DataValue::Date(Date::new(2001, 12, 31))),
+            (Value::Literal("12:00:12:000000".into()), DataValue::Time(Time::new(12, 0, 12, 0))),
+            (
+                Value::Literal("2001-12-31 12:00:12:000000".into()),
+                DataValue::DateTime(Date::new(2001, 12, 31), Time::new(12, 0, 12, 0))
+            )
+        ];
+        for (value, data) in values {
+            assert_eq!(DataValue::from_value(&value), data);
+            assert_eq!(data.to_value(), value);
+        }
+        assert_eq!(
+            DataValue::from("2001-12-31 08:00").coerce(ColType::DateTime),
+            Some(DataValue::DateTime(Date::new(2001, 12, 31), Time::new(8, 0, 0, 0)))
+        );
+        assert_eq!(DataValue::from(" 12 ").coerce(ColType::Long), Some(DataValue::Number(12.)));
+        assert_eq!(DataValue::from("x").coerce(ColType::Long), None);
+
+        let dw = DWSyntax::parse(
+            "release 19;\r\ntable(column=(type=long name=a) column=(type=char(10) name=b))\r\ndata(1, \"x\", null null, 3)"
+        )
+        .unwrap();
+        assert_eq!(dw.data_rows(), vec![
+            vec![DataValue::Number(1.), DataValue::String("x".to_owned())],
+            vec![DataValue::Null, DataValue::Null]
+        ]);
+        assert_eq!(dw.table_column_index("b"), Some(1));
+        let mut dw = dw;
+        dw.set_case_insensitive(false);
+        assert_eq!(dw.table_column_index("B"), None);
+        dw.set_case_insensitive(true);
+        assert_eq!(dw.table_column_index("B"), Some(1));
+    }
+}
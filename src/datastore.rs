@@ -0,0 +1,557 @@
+use crate::{
+    argument::Placeholder,
+    ast::*,
+    data::*,
+    parser::expr::Expr,
+    prelude::*,
+    update::{SqlStatement, UpdateSpec}
+};
+use std::{cmp::Ordering, ops::Range};
+
+/// 数据缓冲区
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Buffer {
+    /// 主缓冲区
+    #[default]
+    Primary,
+    /// 过滤缓冲区
+    Filter,
+    /// 删除缓冲区
+    Delete
+}
+
+/// 行/列状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ItemStatus {
+    /// 未修改
+    #[default]
+    NotModified,
+    /// 已修改
+    DataModified,
+    /// 新增行,未修改
+    New,
+    /// 新增行,已修改
+    NewModified
+}
+
+/// 数据字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataColumn {
+    pub name: String,
+    pub ty: ColType,
+    /// 新增行的初始值(`initial`)
+    pub initial: DataValue
+}
+
+/// 数据行
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataRow {
+    values: Vec<DataValue>,
+    original: Vec<DataValue>,
+    modified: Vec<bool>,
+    status: ItemStatus
+}
+
+impl DataRow {
+    fn new(values: Vec<DataValue>, status: ItemStatus) -> Self {
+        DataRow {
+            original: values.clone(),
+            modified: vec![false; values.len()],
+            values,
+            status
+        }
+    }
+
+    /// 当前值
+    pub fn values(&self) -> &[DataValue] { &self.values }
+
+    /// 原始值(检索或新增时的值)
+    pub fn original(&self) -> &[DataValue] { &self.original }
+
+    /// 行状态
+    pub fn status(&self) -> ItemStatus { self.status }
+
+    /// 列状态(`NotModified`/`DataModified`)
+    pub fn column_status(&self, column: usize) -> ItemStatus {
+        match self.modified.get(column) {
+            Some(true) => ItemStatus::DataModified,
+            _ => ItemStatus::NotModified
+        }
+    }
+}
+
+/// 内存数据存储
+///
+/// 对应PB的`DataStore`,数据分为主缓冲区、过滤缓冲区和删除缓冲区,行号从`0`开始,
+/// 字段可通过字段名或`#id`(从`1`开始)引用
+#[derive(Debug, Clone)]
+pub struct DataStore {
+    columns: Vec<DataColumn>,
+    primary: Vec<DataRow>,
+    filtered: Vec<DataRow>,
+    deleted: Vec<DataRow>,
+    sort: Vec<(Expr, bool)>,
+    filter: Option<Expr>,
+    /// 各级分组依据
+    groups: Vec<Vec<Expr>>,
+    update: Option<UpdateSpec>,
+    /// 字段名是否忽略大小写(取自语法的`is_case_insensitive`)
+    ignore_case: bool
+}
+
+impl DataStore {
+    /// 根据`table`字段、`data`项及`group`分组创建
+    ///
+    /// `data`项作为已检索数据(`NotModified`)载入,并按`table`的`filter`/`sort`过滤和排序
+    pub fn new(syn: &DWSyntax) -> Result<Self, String> {
+        let mut columns = Vec::with_capacity(syn.table.columns.len());
+        for col in &syn.table.columns {
+            let name = col.name.as_ref().map(|v| v.to_string()).unwrap_or_default();
+            let ty = col.col_type().ok_or_else(|| format!("invalid column type: {name}"))?;
+            let initial = col
+                .values
+                .get(&"initial".into_key())
+                .and_then(|v| v.as_str())
+                .and_then(|v| DataValue::String(unescape(v)).coerce(ty))
+                .unwrap_or(DataValue::Null);
+            columns.push(DataColumn {
+                name,
+                ty,
+                initial
+            });
+        }
+        let mut primary = vec![];
+        for values in syn.data_rows() {
+            let values = values
+                .into_iter()
+                .zip(&columns)
+                .map(|(value, col)| coerce(col, value))
+                .collect::<Result<Vec<_>, _>>()?;
+            primary.push(DataRow::new(values, ItemStatus::NotModified));
+        }
+        let mut ds = DataStore {
+            columns,
+            primary,
+            filtered: vec![],
+            deleted: vec![],
+            sort: vec![],
+            filter: None,
+            groups: vec![],
+            update: syn.table.update_spec(),
+            ignore_case: syn.is_case_insensitive()
+        };
+        for group in syn.groups() {
+            let by = group.group_by().iter().map(|v| ds.parse_expr(v)).collect::<Result<_, _>>()?;
+            ds.groups.push(by);
+        }
+        let string = |key: &'static str| {
+            syn.table.values.get(&key.into_key()).and_then(|v| v.as_str()).map(unescape)
+        };
+        if let Some(filter) = string("filter") {
+            ds.set_filter(&filter)?;
+            ds.filter()?;
+        }
+        if let Some(sort) = string("sort") {
+            ds.set_sort(&sort)?;
+            ds.sort()?;
+        }
+        Ok(ds)
+    }
+
+    /// 字段列表
+    pub fn columns(&self) -> &[DataColumn] { &self.columns }
+
+    /// 获取字段索引
+    ///
+    /// `column`为字段名(大小写匹配方式与语法一致)或`#id`
+    pub fn column_index(&self, column: &str) -> Result<usize, String> {
+        let column = column.trim();
+        let index = match column.strip_prefix('#') {
+            Some(id) => {
+                id.parse::<usize>().ok().filter(|id| (1..=self.columns.len()).contains(id)).map(|id| id - 1)
+            },
+            None if self.ignore_case => {
+                self.columns.iter().position(|col| col.name.eq_ignore_ascii_case(column))
+            },
+            None => self.columns.iter().position(|col| col.name == column)
+        };
+        index.ok_or_else(|| format!("column not found: {column}"))
+    }
+
+    /// 主缓冲区行数
+    pub fn row_count(&self) -> usize { self.primary.len() }
+
+    /// 过滤缓冲区行数
+    pub fn filtered_count(&self) -> usize { self.filtered.len() }
+
+    /// 删除缓冲区行数
+    pub fn deleted_count(&self) -> usize { self.deleted.len() }
+
+    /// 获取缓冲区的所有行
+    pub fn rows(&self, buffer: Buffer) -> &[DataRow] {
+        match buffer {
+            Buffer::Primary => &self.primary,
+            Buffer::Filter => &self.filtered,
+            Buffer::Delete => &self.deleted
+        }
+    }
+
+    /// 在`row`之前插入新行,`row`等于行数时追加到末尾,返回新行的行号
+    ///
+    /// 新行使用字段的初始值,状态为`New`
+    pub fn insert_row(&mut self, row: usize) -> Result<usize, String> {
+        if row > self.primary.len() {
+            return Err(format!("row out of range: {row}"));
+        }
+        let values = self.columns.iter().map(|col| col.initial.clone()).collect();
+        self.primary.insert(row, DataRow::new(values, ItemStatus::New));
+        Ok(row)
+    }
+
+    /// 删除行
+    ///
+    /// 新增行直接丢弃,其它行移到删除缓冲区
+    pub fn delete_row(&mut self, row: usize) -> Result<(), String> {
+        if row >= self.primary.len() {
+            return Err(format!("row out of range: {row}"));
+        }
+        let row = self.primary.remove(row);
+        if !matches!(row.status, ItemStatus::New | ItemStatus::NewModified) {
+            self.deleted.push(row);
+        }
+        Ok(())
+    }
+
+    /// 获取主缓冲区的值
+    pub fn get_item(&self, row: usize, column: &str) -> Result<&DataValue, String> {
+        let column = self.column_index(column)?;
+        let row = self.primary.get(row).ok_or_else(|| format!("row out of range: {row}"))?;
+        Ok(&row.values[column])
+    }
+
+    /// 设置主缓冲区的值
+    ///
+    /// 值按字段类型转换,并将列状态设置为`DataModified`,行状态设置为`DataModified`/`NewModified`
+    pub fn set_item(&mut self, row: usize, column: &str, value: impl Into<DataValue>) -> Result<(), String> {
+        let index = self.column_index(column)?;
+        let value = coerce(&self.columns[index], value.into())?;
+        let row = self.primary.get_mut(row).ok_or_else(|| format!("row out of range: {row}"))?;
+        row.values[index] = value;
+        row.modified[index] = true;
+        row.status = match row.status {
+            ItemStatus::New | ItemStatus::NewModified => ItemStatus::NewModified,
+            _ => ItemStatus::DataModified
+        };
+        Ok(())
+    }
+
+    /// 获取行状态
+    pub fn row_status(&self, row: usize, buffer: Buffer) -> Result<ItemStatus, String> {
+        self.rows(buffer).get(row).map(|v| v.status).ok_or_else(|| format!("row out of range: {row}"))
+    }
+
+    /// 获取列状态
+    pub fn item_status(&self, row: usize, column: &str, buffer: Buffer) -> Result<ItemStatus, String> {
+        let column = self.column_index(column)?;
+        let row = self.rows(buffer).get(row).ok_or_else(|| format!("row out of range: {row}"))?;
+        Ok(row.column_status(column))
+    }
+
+    /// 设置排序条件,如`dept A, #2 D`,为空时不排序
+    pub fn set_sort(&mut self, sort: &str) -> Result<(), String> {
+        self.sort = Expr::parse_sort(sort, &|name| self.column_index(name).ok())?;
+        Ok(())
+    }
+
+    /// 按排序条件对主缓冲区排序(稳定排序,`Null`最小)
+    pub fn sort(&mut self) -> Result<(), String> {
+        if self.sort.is_empty() {
+            return Ok(());
+        }
+        //先计算排序值,出错时不修改缓冲区
+        let keys = self
+            .primary
+            .iter()
+            .map(|row| self.sort.iter().map(|(expr, _)| expr.eval(&row.values)).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut keyed: Vec<_> = keys.into_iter().zip(self.primary.drain(..)).collect();
+        keyed.sort_by(|(a, _), (b, _)| {
+            a.iter()
+                .zip(b)
+                .zip(&self.sort)
+                .map(|((a, b), (_, asc))| {
+                    let ord = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+                    if *asc { ord } else { ord.reverse() }
+                })
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        self.primary = keyed.into_iter().map(|(_, row)| row).collect();
+        Ok(())
+    }
+
+    /// 设置过滤条件,为空时不过滤
+    pub fn set_filter(&mut self, filter: &str) -> Result<(), String> {
+        self.filter = match filter.trim() {
+            "" => None,
+            filter => Some(self.parse_expr(filter)?)
+        };
+        Ok(())
+    }
+
+    /// 按过滤条件过滤数据
+    ///
+    /// 过滤缓冲区的行先移回主缓冲区末尾,再将不满足条件(或结果为`Null`)的行移到过滤缓冲区
+    pub fn filter(&mut self) -> Result<(), String> {
+        let filter = match &self.filter {
+            Some(filter) => filter,
+            None => {
+                self.primary.append(&mut self.filtered);
+                return Ok(());
+            }
+        };
+        //先计算过滤结果,出错时不修改缓冲区
+        let keep = self
+            .primary
+            .iter()
+            .chain(&self.filtered)
+            .map(|row| {
+                match filter.eval(&row.values)? {
+                    DataValue::Null => Ok(false),
+                    DataValue::Number(v) => Ok(v != 0.),
+                    v => Err(format!("filter is not a boolean expression: {v}"))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut rows = std::mem::take(&mut self.primary);
+        rows.append(&mut self.filtered);
+        for (row, keep) in rows.into_iter().zip(keep) {
+            if keep {
+                self.primary.push(row);
+            } else {
+                self.filtered.push(row);
+            }
+        }
+        Ok(())
+    }
+
+    /// 获取指定级别(从`1`开始)分组在主缓冲区中的行范围
+    ///
+    /// 当前级别或上级分组依据的值变化时开始新的分组,数据需预先按分组依据排序
+    pub fn group_ranges(&self, level: u32) -> Result<Vec<Range<usize>>, String> {
+        let level = level as usize;
+        if level == 0 || level > self.groups.len() {
+            return Err(format!("group not found: {level}"));
+        }
+        let mut ranges: Vec<Range<usize>> = vec![];
+        let mut prev: Option<Vec<DataValue>> = None;
+        for (i, row) in self.primary.iter().enumerate() {
+            let keys = self.groups[..level]
+                .iter()
+                .flatten()
+                .map(|expr| expr.eval(&row.values))
+                .collect::<Result<Vec<_>, _>>()?;
+            if prev.as_ref() != Some(&keys) {
+                if let Some(last) = ranges.last_mut() {
+                    last.end = i;
+                }
+                ranges.push(i..self.primary.len());
+                prev = Some(keys);
+            }
+        }
+        Ok(ranges)
+    }
+
+    /// 将主缓冲区和过滤缓冲区的数据写入`data`项
+    pub fn write_to(&self, syn: &mut DWSyntax) {
+        syn.data = self
+            .primary
+            .iter()
+            .chain(&self.filtered)
+            .flat_map(|row| row.values.iter().map(DataValue::to_value))
+            .collect();
+    }
+
+    /// 更新属性
+    pub fn update_spec(&self) -> Option<&UpdateSpec> { self.update.as_ref() }
+
+    /// 生成保存修改的SQL
+    ///
+    /// 依次为删除缓冲区的`DELETE`、`NewModified`行的`INSERT`及`DataModified`行的`UPDATE`,
+    /// `UPDATE`按列状态确定修改的字段,`WHERE`子句按`updatewhere`使用原始值生成,
+    /// 占位符风格与`RetrieveSql::bind`一致
+    pub fn update(&self, placeholder: Placeholder) -> Result<Vec<SqlStatement>, String> {
+        let spec = self.update.as_ref().ok_or("datastore is not updatable")?;
+        spec.validate()?;
+        let mut statements = vec![];
+        for row in &self.deleted {
+            statements.push(spec.delete_modified(&row.original, &row.modified, placeholder)?);
+        }
+        let rows: Vec<&DataRow> = self.primary.iter().chain(&self.filtered).collect();
+        for row in rows.iter().filter(|row| row.status == ItemStatus::NewModified) {
+            statements.push(spec.insert_sql(&row.values, placeholder)?);
+        }
+        for row in rows.iter().filter(|row| row.status == ItemStatus::DataModified) {
+            statements.extend(spec.update_modified(&row.original, &row.values, &row.modified, placeholder)?);
+        }
+        Ok(statements)
+    }
+
+    /// 保存成功后重置状态
+    ///
+    /// 清空删除缓冲区,所有行的状态设置为`NotModified`,当前值作为原始值
+    pub fn reset_update(&mut self) {
+        self.deleted.clear();
+        for row in self.primary.iter_mut().chain(&mut self.filtered) {
+            row.original = row.values.clone();
+            row.modified.fill(false);
+            row.status = ItemStatus::NotModified;
+        }
+    }
+
+    fn parse_expr(&self, expr: &str) -> Result<Expr, String> {
+        Expr::parse(expr, &|name| self.column_index(name).ok())
+    }
+}
+
+/// 按字段类型转换
+fn coerce(col: &DataColumn, value: DataValue) -> Result<DataValue, String> {
+    value.coerce(col.ty).ok_or_else(|| format!("invalid value for column {}: {value}", col.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+table(column=(type=long name=id dbname="emp.id" key=yes) column=(type=char(20) name=name dbname="emp.name")
+ column=(type=long name=dept dbname="emp.dept" initial="10") column=(type=decimal(2) name=salary dbname="emp.salary")
+ sort="dept A, salary D" filter="salary > 0")
+group(level=1 header.height=76 trailer.height=0 by=("dept"))
+data(1, "Tom", 10, 1000, 2, "Jerry", 20, 3000, 3, "Spike", 10, 2000, 4, "Tyke", 20, 0, )
+"#;
+
+    #[test]
+    fn test_datastore() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.set_case_insensitive(false);
+        assert!(DataStore::new(&dw).unwrap().column_index("Salary").is_err());
+        dw.set_case_insensitive(true);
+        let mut ds = DataStore::new(&dw).unwrap();
+        assert_eq!(ds.row_count(), 3);
+        assert_eq!(ds.filtered_count(), 1);
+        let ids = |ds: &DataStore| {
+            (0..ds.row_count()).map(|i| ds.get_item(i, "#1").unwrap().clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&ds), vec![3.0.into(), 1.0.into(), 2.0.into()]);
+        assert_eq!(ds.group_ranges(1).unwrap(), vec![0..2, 2..3]);
+        assert!(ds.group_ranges(2).is_err());
+
+        ds.set_item(1, "Salary", "1500").unwrap();
+        assert_eq!(ds.get_item(1, "salary").unwrap(), &1500.0.into());
+        assert_eq!(ds.row_status(1, Buffer::Primary).unwrap(), ItemStatus::DataModified);
+        assert_eq!(ds.item_status(1, "salary", Buffer::Primary).unwrap(), ItemStatus::DataModified);
+        assert_eq!(ds.item_status(1, "name", Buffer::Primary).unwrap(), ItemStatus::NotModified);
+        assert_eq!(ds.rows(Buffer::Primary)[1].original()[3], 1000.0.into());
+        assert!(ds.set_item(1, "salary", "abc").is_err());
+        assert!(ds.set_item(1, "#5", 1.).is_err());
+
+        let row = ds.insert_row(ds.row_count()).unwrap();
+        assert_eq!(ds.get_item(row, "dept").unwrap(), &10.0.into());
+        assert_eq!(ds.row_status(row, Buffer::Primary).unwrap(), ItemStatus::New);
+        ds.set_item(row, "id", 5.).unwrap();
+        assert_eq!(ds.row_status(row, Buffer::Primary).unwrap(), ItemStatus::NewModified);
+        ds.delete_row(row).unwrap();
+        ds.delete_row(0).unwrap();
+        assert_eq!(ds.deleted_count(), 1);
+        assert_eq!(ds.rows(Buffer::Delete)[0].values()[0], 3.0.into());
+
+        ds.set_filter("").unwrap();
+        ds.filter().unwrap();
+        ds.set_sort("#1 D").unwrap();
+        ds.sort().unwrap();
+        assert_eq!(ids(&ds), vec![4.0.into(), 2.0.into(), 1.0.into()]);
+        assert!(ds.set_sort("nosuch A").is_err());
+        assert!(ds.set_filter("salary >").is_err());
+
+        //计算出错时不修改缓冲区
+        ds.set_filter("salary / (dept - dept) > 0").unwrap();
+        assert!(ds.filter().is_err());
+        ds.set_filter("name").unwrap();
+        assert!(ds.filter().is_err());
+        ds.set_sort("salary / (dept - dept) A").unwrap();
+        assert!(ds.sort().is_err());
+        assert_eq!(ids(&ds), vec![4.0.into(), 2.0.into(), 1.0.into()]);
+        assert_eq!(ds.filtered_count(), 0);
+
+        ds.write_to(&mut dw);
+        assert_eq!(dw.data_rows()[2], vec![1.0.into(), "Tom".into(), 10.0.into(), 1500.0.into()]);
+    }
+
+    const UPDATE_SYNTAX: &str = r#"release 19;
+table(column=(type=long name=id dbname="emp.id" key=yes update=yes updatewhereclause=yes)
+ column=(type=char(20) name=name dbname="emp.name" update=yes updatewhereclause=yes)
+ column=(type=long name=dept dbname="emp.dept" initial="10" update=yes updatewhereclause=yes)
+ column=(type=decimal(2) name=salary dbname="emp.salary" update=yes updatewhereclause=yes)
+ update="emp" updatewhere=2 updatekeyinplace=no)
+data(1, "Tom", 10, 1000, 2, "Jerry", 20, 3000, 3, "Spike", 10, 2000, )
+"#;
+
+    #[test]
+    fn test_update() {
+        let sqls = |ds: &DataStore| {
+            let stmts = ds.update(Placeholder::Question).unwrap();
+            stmts.into_iter().map(|v| (v.sql, v.params)).collect::<Vec<_>>()
+        };
+        let mut ds = DataStore::new(&DWSyntax::parse(UPDATE_SYNTAX).unwrap()).unwrap();
+        assert!(ds.update(Placeholder::Question).unwrap().is_empty());
+        ds.set_item(0, "salary", 1500.).unwrap();
+        ds.delete_row(1).unwrap();
+        let row = ds.insert_row(ds.row_count()).unwrap();
+        ds.set_item(row, "id", 4.).unwrap();
+        ds.set_item(row, "name", "Tyke").unwrap();
+        ds.insert_row(0).unwrap();
+        assert_eq!(sqls(&ds), vec![
+            ("DELETE FROM emp WHERE id = ?".to_owned(), vec![2.0.into()]),
+            ("INSERT INTO emp (id, name, dept) VALUES (?, ?, ?)".to_owned(), vec![
+                4.0.into(),
+                "Tyke".into(),
+                10.0.into(),
+            ]),
+            ("UPDATE emp SET salary = ? WHERE id = ? AND salary = ?".to_owned(), vec![
+                1500.0.into(),
+                1.0.into(),
+                1000.0.into(),
+            ]),
+        ]);
+
+        ds.reset_update();
+        assert_eq!(ds.deleted_count(), 0);
+        assert_eq!(ds.row_status(3, Buffer::Primary).unwrap(), ItemStatus::NotModified);
+        assert!(ds.update(Placeholder::Question).unwrap().is_empty());
+
+        //修改主键时先删除再插入
+        ds.set_item(1, "id", 10.).unwrap();
+        let stmts = sqls(&ds);
+        assert_eq!(stmts[0], ("DELETE FROM emp WHERE id = ?".to_owned(), vec![1.0.into()]));
+        assert!(stmts[1].0.starts_with("INSERT INTO emp (id, name, dept, salary)"));
+        ds.reset_update();
+
+        //删除修改过的行时WHERE子句包含修改字段的原始值
+        ds.set_item(1, "salary", 1800.).unwrap();
+        ds.delete_row(1).unwrap();
+        let stmts = ds.update(Placeholder::Dollar).unwrap();
+        assert_eq!(stmts[0].sql, "DELETE FROM emp WHERE id = $1 AND salary = $2");
+        assert_eq!(stmts[0].params, vec![10.0.into(), 1500.0.into()]);
+
+        let syntax = UPDATE_SYNTAX.replace("updatewhere=2", "updatewhere=1");
+        let mut ds = DataStore::new(&DWSyntax::parse(&syntax).unwrap()).unwrap();
+        ds.set_item(1, "name", "Jerry2").unwrap();
+        assert_eq!(
+            sqls(&ds)[0].0,
+            "UPDATE emp SET name = ? WHERE id = ? AND name = ? AND dept = ? AND salary = ?"
+        );
+        let ds = DataStore::new(&DWSyntax::parse(SYNTAX).unwrap()).unwrap();
+        assert!(ds.update(Placeholder::Question).is_err());
+    }
+}
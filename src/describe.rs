@@ -0,0 +1,184 @@
+use crate::{ast::*, prelude::*};
+
+/// 控件的通用属性
+const COMMON_ATTRIBUTES: &[&str] = &[
+    "band", "x", "y", "height", "width", "name", "visible", "moveable", "resizeable", "pointer", "tag",
+    "slideleft", "slideup", "accessibledescription", "accessiblename", "accessiblerole"
+];
+/// 字体及背景属性
+const FONT_ATTRIBUTES: &[&str] = &[
+    "font.face", "font.height", "font.weight", "font.family", "font.pitch", "font.charset", "font.italic",
+    "font.underline", "font.strikethrough", "font.escapement", "background.mode", "background.color"
+];
+/// 各类型控件的专有属性
+const ATTRIBUTES: &[(&str, &[&str], bool)] = &[
+    ("text", &["alignment", "border", "color", "text", "height.autosize"], true),
+    (
+        "column",
+        &[
+            "id", "alignment", "border", "color", "format", "tabsequence", "protect", "height.autosize", "criteria"
+        ],
+        true
+    ),
+    ("compute", &["alignment", "border", "color", "expression", "format", "height.autosize"], true),
+    ("line", &["x1", "y1", "x2", "y2", "pen.style", "pen.width", "pen.color", "background.mode"], false),
+    ("rectangle", &["brush.hatch", "brush.color", "pen.style", "pen.width", "pen.color"], false),
+    (
+        "roundrectangle",
+        &["brush.hatch", "brush.color", "pen.style", "pen.width", "pen.color", "ellipseheight"],
+        false
+    ),
+    ("ellipse", &["brush.hatch", "brush.color", "pen.style", "pen.width", "pen.color"], false),
+    ("bitmap", &["filename", "border", "invert"], false),
+    (
+        "report",
+        &["dataobject", "border", "height.autosize", "newpage", "trail_footer", "nest_arguments"],
+        false
+    ),
+    ("groupbox", &["alignment", "border", "color", "text"], true),
+    ("button", &["text", "action", "defaultpicture", "picturename", "htextalign", "vtextalign"], true),
+    ("graph", &["graphtype", "category", "values", "series", "title", "legend", "border"], false)
+];
+
+impl<'a> DWSyntax<'a> {
+    /// `DataWindow::Describe`伪属性(不存在于语法中的属性)
+    ///
+    /// - `datawindow.column.count`
+    /// - `datawindow.syntax`
+    /// - `datawindow.objects`: 可见控件名称列表
+    /// - `datawindow.bands`: 带区列表
+    /// - `datawindow.selected`: 选中的控件(语法中无选中状态,始终为空)
+    /// - `datawindow.table.select`/`datawindow.table.sqlselect`: `PBSELECT`转换后的SQL
+    /// - `datawindow.table.update`/`datawindow.table.updatetable`: 更新表名
+    /// - `<obj>.type`: 控件类型
+    /// - `<obj>.attributes`: 控件的属性列表(按控件类型列出常用属性,并附加语法中的其它属性)
+    /// - `<col>.id`: 字段序号
+    ///
+    /// 列表以`\t`分隔,非伪属性返回`None`
+    pub(crate) fn describe_pseudo(&self, selector: &str) -> Option<String> {
+        let selector = if self.ignore_case() {
+            Cow::Owned(selector.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(selector)
+        };
+        let rv = match selector.trim() {
+            "datawindow.column.count" => self.table.columns.len().to_string(),
+            "datawindow.syntax" => self.to_string(),
+            "datawindow.objects" => {
+                let names: Vec<String> = self
+                    .items
+                    .iter()
+                    .filter(|item| is_visible(item))
+                    .filter_map(|item| item.name.as_ref().map(|v| v.to_string()))
+                    .collect();
+                names.join("\t")
+            },
+            "datawindow.bands" => {
+                self.bands().iter().map(|v| v.to_string()).collect::<Vec<_>>().join("\t")
+            },
+            "datawindow.selected" => String::new(),
+            "datawindow.table.select" | "datawindow.table.sqlselect" => self.table.retrieve_sql()?,
+            "datawindow.table.update" | "datawindow.table.updatetable" => {
+                self.table.values.get(&"update".into_key()).map(|v| v.to_string()).unwrap_or_default()
+            },
+            selector => {
+                let (name, prop) = selector.split_once('.')?;
+                if !matches!(prop, "type" | "attributes" | "id") {
+                    return None;
+                }
+                let item = match name.strip_prefix('#') {
+                    Some(id) => id.parse().ok().and_then(|id| self.column_control_by_id(id)),
+                    None => self.item_by_name(name)
+                };
+                match (prop, item) {
+                    ("type", Some(item)) => item.kind.to_string(),
+                    ("attributes", Some(item)) => attributes(item).join("\t"),
+                    ("id", Some(item)) if item.id.is_some() => item.id.unwrap_or_default().to_string(),
+                    //没有字段控件的`table`字段
+                    ("id", None) => {
+                        let index = self.table_column_position(name)?;
+                        (index + 1).to_string()
+                    },
+                    _ => return None
+                }
+            }
+        };
+        Some(rv)
+    }
+}
+
+/// 获取控件的属性列表
+fn attributes(item: &Item) -> Vec<String> {
+    let mut names: Vec<String> = match ATTRIBUTES.iter().find(|(kind, ..)| item.kind == *kind) {
+        Some((_, attrs, font)) => {
+            let font: &[&str] = if *font { FONT_ATTRIBUTES } else { &[] };
+            COMMON_ATTRIBUTES.iter().chain(attrs.iter()).chain(font).map(|v| v.to_string()).collect()
+        },
+        None => vec![]
+    };
+    for key in item.values.keys() {
+        if !names.iter().any(|v| v.eq_ignore_ascii_case(key)) {
+            names.push(key.to_string());
+        }
+    }
+    names
+}
+
+/// 控件是否可见
+///
+/// `visible="0"`或表达式的默认值为`0`时不可见
+fn is_visible(item: &Item) -> bool {
+    match item.values.get(&"visible".into_key()) {
+        Some(Value::Number(v)) => *v != 0.,
+        Some(v) => {
+            v.as_str().map(|v| v.split("~t").next().unwrap_or_default().trim() != "0").unwrap_or(true)
+        },
+        None => true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+datawindow(units=0 processing=0)
+table(column=(type=long name=id dbname="emp.id") column=(type=char(20) name=name dbname="emp.name") column=(type=long name=dept dbname="emp.dept")
+ retrieve="PBSELECT( VERSION(400) TABLE(NAME=~"emp~" ) COLUMN(NAME=~"emp.id~") COLUMN(NAME=~"emp.name~") COLUMN(NAME=~"emp.dept~")WHERE(    EXP1 =~"emp.dept~"   OP =~"=~"    EXP2 =~":dept~" ) ) ARG(NAME = ~"dept~" TYPE = number)" update="emp" updatewhere=0 updatekeyinplace=no arguments=(("dept", number)))
+group(level=1 header.height=0 trailer.height=0 by=("dept"))
+text(band=header alignment="2" text="Id" x="9" y="8" height="64" width="200" name=id_t visible="1")
+column(band=detail id=1 alignment="1" x="9" y="8" height="76" width="200" name=id visible="1")
+column(band=detail id=2 alignment="0" x="219" y="8" height="76" width="400" name=name visible="0~tif(id > 0, 1, 0)")
+compute(band=trailer.1 expression="count(id for group 1)" x="0" y="0" name=cnt)
+"#;
+
+    #[test]
+    fn test_describe_pseudo() {
+        let dw = DWSyntax::parse(SYNTAX).unwrap();
+        let describe = |selector: &str| dw.describe_pseudo(selector);
+        assert_eq!(describe("datawindow.column.count").as_deref(), Some("3"));
+        assert_eq!(describe("datawindow.objects").as_deref(), Some("id_t\tid\tcnt"));
+        assert_eq!(
+            describe("datawindow.bands").as_deref(),
+            Some("header\theader.1\tdetail\ttrailer.1\tsummary\tfooter")
+        );
+        assert_eq!(describe("datawindow.selected").as_deref(), Some(""));
+        assert_eq!(
+            describe("datawindow.table.select").as_deref(),
+            Some("SELECT emp.id, emp.name, emp.dept FROM emp WHERE ( emp.dept = :dept )")
+        );
+        assert_eq!(describe("datawindow.table.update").as_deref(), Some("emp"));
+        assert_eq!(describe("cnt.type").as_deref(), Some("compute"));
+        let attrs = describe("id_t.attributes").unwrap();
+        let attrs: Vec<&str> = attrs.split('\t').collect();
+        assert_eq!(attrs[..4], ["band", "x", "y", "height"]);
+        assert!(attrs.contains(&"text") && attrs.contains(&"font.face") && !attrs.contains(&"id"));
+        let attrs = describe("cnt.attributes").unwrap();
+        assert!(attrs.split('\t').any(|v| v == "expression"));
+        assert_eq!(describe("name.id").as_deref(), Some("2"));
+        assert_eq!(describe("#2.type").as_deref(), Some("column"));
+        assert_eq!(describe("dept.id").as_deref(), Some("3"));
+        assert_eq!(describe("unknown.id"), None);
+        assert_eq!(describe("id.x"), None);
+    }
+}
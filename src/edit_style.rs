@@ -0,0 +1,697 @@
+use crate::{ast::*, prelude::*};
+use std::fmt::{self, Display};
+
+/// 编辑风格参数前缀
+const PREFIXES: [&str; 6] = ["dddw.", "ddlb.", "checkbox.", "radiobuttons.", "editmask.", "edit."];
+
+/// 字段控件的编辑风格
+///
+/// # Syntax
+///
+/// ```txt
+/// column(edit.limit=0 edit.case=any ...)
+/// column(dddw.name=d_lookup dddw.displaycolumn=name dddw.datacolumn=id ...)
+/// ```
+#[derive(Debug, PartialEq)]
+pub enum EditStyle<'a> {
+    /// `edit.*`
+    Edit(Edit<'a>),
+    /// `dddw.*`
+    DropDownDataWindow(DropDownDataWindow<'a>),
+    /// `ddlb.*`
+    DropDownListBox(DropDownListBox<'a>),
+    /// `checkbox.*`
+    CheckBox(CheckBox<'a>),
+    /// `radiobuttons.*`
+    RadioButtons(RadioButtons<'a>),
+    /// `editmask.*`
+    EditMask(EditMask<'a>)
+}
+
+/// 大小写转换
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextCase {
+    #[default]
+    Any,
+    Upper,
+    Lower
+}
+
+impl TextCase {
+    fn parse(s: &str) -> Self {
+        if s.eq_ignore_ascii_case("upper") {
+            TextCase::Upper
+        } else if s.eq_ignore_ascii_case("lower") {
+            TextCase::Lower
+        } else {
+            TextCase::Any
+        }
+    }
+}
+
+impl Display for TextCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextCase::Any => write!(f, "any"),
+            TextCase::Upper => write!(f, "upper"),
+            TextCase::Lower => write!(f, "lower")
+        }
+    }
+}
+
+/// 编辑框
+///
+/// `edit.limit=0 edit.case=any edit.autoselect=yes edit.required=no`
+#[derive(Debug, PartialEq, Default)]
+pub struct Edit<'a> {
+    /// `edit.limit`
+    pub limit: u32,
+    /// `edit.case`
+    pub case: TextCase,
+    /// `edit.required`
+    pub required: bool,
+    /// `edit.password`
+    pub password: bool,
+    /// `edit.displayonly`
+    pub display_only: bool,
+    /// `edit.autoselect`
+    pub auto_select: bool,
+    /// `edit.codetable`
+    pub code_table: bool,
+    /// `edit.validatecode`
+    pub validate_code: bool,
+    /// `edit.nilisnull`
+    pub nil_is_null: bool,
+    /// 其它参数(不含前缀)
+    pub others: HashMap<Key<'a>, Value<'a>>
+}
+
+/// 下拉DataWindow
+///
+/// `dddw.name=d_lookup dddw.displaycolumn=name dddw.datacolumn=id dddw.allowedit=no`
+#[derive(Debug, PartialEq, Default)]
+pub struct DropDownDataWindow<'a> {
+    /// `dddw.name`
+    pub name: Cow<'a, str>,
+    /// `dddw.displaycolumn`
+    pub display_column: Cow<'a, str>,
+    /// `dddw.datacolumn`
+    pub data_column: Cow<'a, str>,
+    /// `dddw.percentwidth`
+    pub percent_width: u32,
+    /// `dddw.lines`
+    pub lines: u32,
+    /// `dddw.limit`
+    pub limit: u32,
+    /// `dddw.allowedit`
+    pub allow_edit: bool,
+    /// `dddw.case`
+    pub case: TextCase,
+    /// `dddw.required`
+    pub required: bool,
+    /// `dddw.nilisnull`
+    pub nil_is_null: bool,
+    /// 其它参数(不含前缀)
+    pub others: HashMap<Key<'a>, Value<'a>>
+}
+
+/// 下拉列表框
+///
+/// `ddlb.limit=0 ddlb.allowedit=no ddlb.case=any ddlb.required=no`
+#[derive(Debug, PartialEq, Default)]
+pub struct DropDownListBox<'a> {
+    /// `ddlb.limit`
+    pub limit: u32,
+    /// `ddlb.allowedit`
+    pub allow_edit: bool,
+    /// `ddlb.case`
+    pub case: TextCase,
+    /// `ddlb.required`
+    pub required: bool,
+    /// `ddlb.sorted`
+    pub sorted: bool,
+    /// `ddlb.nilisnull`
+    pub nil_is_null: bool,
+    /// 其它参数(不含前缀)
+    pub others: HashMap<Key<'a>, Value<'a>>
+}
+
+/// 复选框
+///
+/// `checkbox.text="" checkbox.on="Y" checkbox.off="N"`
+#[derive(Debug, PartialEq, Default)]
+pub struct CheckBox<'a> {
+    /// `checkbox.text`
+    pub text: Cow<'a, str>,
+    /// `checkbox.on`
+    pub on: Cow<'a, str>,
+    /// `checkbox.off`
+    pub off: Cow<'a, str>,
+    /// `checkbox.other`(三态)
+    pub other: Option<Cow<'a, str>>,
+    /// `checkbox.lefttext`
+    pub left_text: bool,
+    /// 其它参数(不含前缀)
+    pub others: HashMap<Key<'a>, Value<'a>>
+}
+
+/// 单选按钮
+///
+/// `radiobuttons.columns=2 radiobuttons.lefttext=no`
+#[derive(Debug, PartialEq, Default)]
+pub struct RadioButtons<'a> {
+    /// `radiobuttons.columns`
+    pub columns: u32,
+    /// `radiobuttons.lefttext`
+    pub left_text: bool,
+    /// 其它参数(不含前缀)
+    pub others: HashMap<Key<'a>, Value<'a>>
+}
+
+/// 掩码编辑框
+///
+/// `editmask.mask="###,##0.00" editmask.spin=no`
+#[derive(Debug, PartialEq, Default)]
+pub struct EditMask<'a> {
+    /// `editmask.mask`
+    pub mask: Cow<'a, str>,
+    /// `editmask.spin`
+    pub spin: bool,
+    /// `editmask.codetable`
+    pub code_table: bool,
+    /// `editmask.required`
+    pub required: bool,
+    /// `editmask.readonly`
+    pub read_only: bool,
+    /// 其它参数(不含前缀)
+    pub others: HashMap<Key<'a>, Value<'a>>
+}
+
+impl<'a> EditStyle<'a> {
+    /// 从控件参数列表中读取编辑风格
+    pub fn from_values(values: &HashMap<Key<'a>, Value<'a>>) -> Option<Self> {
+        let prefix = PREFIXES.iter().find(|prefix| values.keys().any(|key| has_prefix(key, prefix)))?;
+        let mut props = Props::new(values, prefix);
+        let style = match *prefix {
+            "edit." => EditStyle::Edit(Edit {
+                limit: props.number("limit"),
+                case: props.case("case"),
+                required: props.bool("required"),
+                password: props.bool("password"),
+                display_only: props.bool("displayonly"),
+                auto_select: props.bool("autoselect"),
+                code_table: props.bool("codetable"),
+                validate_code: props.bool("validatecode"),
+                nil_is_null: props.bool("nilisnull"),
+                others: props.into_others()
+            }),
+            "dddw." => EditStyle::DropDownDataWindow(DropDownDataWindow {
+                name: props.string("name"),
+                display_column: props.string("displaycolumn"),
+                data_column: props.string("datacolumn"),
+                percent_width: props.number("percentwidth"),
+                lines: props.number("lines"),
+                limit: props.number("limit"),
+                allow_edit: props.bool("allowedit"),
+                case: props.case("case"),
+                required: props.bool("required"),
+                nil_is_null: props.bool("nilisnull"),
+                others: props.into_others()
+            }),
+            "ddlb." => EditStyle::DropDownListBox(DropDownListBox {
+                limit: props.number("limit"),
+                allow_edit: props.bool("allowedit"),
+                case: props.case("case"),
+                required: props.bool("required"),
+                sorted: props.bool("sorted"),
+                nil_is_null: props.bool("nilisnull"),
+                others: props.into_others()
+            }),
+            "checkbox." => EditStyle::CheckBox(CheckBox {
+                text: props.string("text"),
+                on: props.string("on"),
+                off: props.string("off"),
+                other: props.take("other").and_then(|v| v.as_str().map(|v| Cow::Owned(v.to_owned()))),
+                left_text: props.bool("lefttext"),
+                others: props.into_others()
+            }),
+            "radiobuttons." => EditStyle::RadioButtons(RadioButtons {
+                columns: props.number("columns"),
+                left_text: props.bool("lefttext"),
+                others: props.into_others()
+            }),
+            _ => EditStyle::EditMask(EditMask {
+                mask: props.string("mask"),
+                spin: props.bool("spin"),
+                code_table: props.bool("codetable"),
+                required: props.bool("required"),
+                read_only: props.bool("readonly"),
+                others: props.into_others()
+            })
+        };
+        Some(style)
+    }
+
+    /// 写入控件参数列表
+    ///
+    /// 原有的编辑风格参数将被清除
+    pub fn write_to(&self, values: &mut HashMap<Key<'a>, Value<'a>>) {
+        values.retain(|key, _| !PREFIXES.iter().any(|prefix| has_prefix(key, prefix)));
+        let mut put = |key: &str, value: Value<'a>| {
+            values.insert(format!("{}{key}", self.prefix()).into_key(), value);
+        };
+        let others = match self {
+            EditStyle::Edit(v) => {
+                put("limit", Value::Number(v.limit as f64));
+                put("case", lit(v.case));
+                put("required", yes_no(v.required));
+                put("password", yes_no(v.password));
+                put("displayonly", yes_no(v.display_only));
+                put("autoselect", yes_no(v.auto_select));
+                put("codetable", yes_no(v.code_table));
+                put("validatecode", yes_no(v.validate_code));
+                put("nilisnull", yes_no(v.nil_is_null));
+                &v.others
+            },
+            EditStyle::DropDownDataWindow(v) => {
+                //未指定的名称不写入,避免生成无法解析的空值
+                for (key, name) in
+                    [("name", &v.name), ("displaycolumn", &v.display_column), ("datacolumn", &v.data_column)]
+                {
+                    if !name.is_empty() {
+                        put(key, Value::Literal(name.clone()));
+                    }
+                }
+                put("percentwidth", Value::Number(v.percent_width as f64));
+                put("lines", Value::Number(v.lines as f64));
+                put("limit", Value::Number(v.limit as f64));
+                put("allowedit", yes_no(v.allow_edit));
+                put("case", lit(v.case));
+                put("required", yes_no(v.required));
+                put("nilisnull", yes_no(v.nil_is_null));
+                &v.others
+            },
+            EditStyle::DropDownListBox(v) => {
+                put("limit", Value::Number(v.limit as f64));
+                put("allowedit", yes_no(v.allow_edit));
+                put("case", lit(v.case));
+                put("required", yes_no(v.required));
+                put("sorted", yes_no(v.sorted));
+                put("nilisnull", yes_no(v.nil_is_null));
+                &v.others
+            },
+            EditStyle::CheckBox(v) => {
+                put("text", Value::DoubleQuotedString(v.text.clone()));
+                put("on", Value::DoubleQuotedString(v.on.clone()));
+                put("off", Value::DoubleQuotedString(v.off.clone()));
+                if let Some(other) = &v.other {
+                    put("other", Value::DoubleQuotedString(other.clone()));
+                }
+                put("lefttext", yes_no(v.left_text));
+                &v.others
+            },
+            EditStyle::RadioButtons(v) => {
+                put("columns", Value::Number(v.columns as f64));
+                put("lefttext", yes_no(v.left_text));
+                &v.others
+            },
+            EditStyle::EditMask(v) => {
+                put("mask", Value::DoubleQuotedString(v.mask.clone()));
+                put("spin", yes_no(v.spin));
+                put("codetable", yes_no(v.code_table));
+                put("required", yes_no(v.required));
+                put("readonly", yes_no(v.read_only));
+                &v.others
+            }
+        };
+        for (key, value) in others {
+            put(key, value.to_owned());
+        }
+    }
+
+    /// 参数前缀
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            EditStyle::Edit(_) => "edit.",
+            EditStyle::DropDownDataWindow(_) => "dddw.",
+            EditStyle::DropDownListBox(_) => "ddlb.",
+            EditStyle::CheckBox(_) => "checkbox.",
+            EditStyle::RadioButtons(_) => "radiobuttons.",
+            EditStyle::EditMask(_) => "editmask."
+        }
+    }
+
+    /// 是否必须输入
+    pub fn required(&self) -> bool {
+        match self {
+            EditStyle::Edit(v) => v.required,
+            EditStyle::DropDownDataWindow(v) => v.required,
+            EditStyle::DropDownListBox(v) => v.required,
+            EditStyle::EditMask(v) => v.required,
+            EditStyle::CheckBox(_) | EditStyle::RadioButtons(_) => false
+        }
+    }
+
+    /// 最大输入长度(`0`为不限制)
+    pub fn limit(&self) -> u32 {
+        match self {
+            EditStyle::Edit(v) => v.limit,
+            EditStyle::DropDownDataWindow(v) => v.limit,
+            EditStyle::DropDownListBox(v) => v.limit,
+            _ => 0
+        }
+    }
+
+    /// 输入值是否必须在代码表中
+    pub fn validates_code(&self) -> bool {
+        match self {
+            EditStyle::Edit(v) => v.code_table && v.validate_code,
+            EditStyle::DropDownListBox(v) => !v.allow_edit,
+            EditStyle::RadioButtons(_) => true,
+            _ => false
+        }
+    }
+
+    /// 按编辑风格检查输入值
+    ///
+    /// - `value`: 数据值,`None`表示空值
+    /// - `code_table`: 控件的代码表
+    pub fn validate(&self, value: Option<&str>, code_table: Option<&CodeTable>) -> Result<(), String> {
+        let value = match value {
+            Some(v) if !v.is_empty() => v,
+            _ => {
+                return if self.required() {
+                    Err("value is required".to_owned())
+                } else {
+                    Ok(())
+                };
+            }
+        };
+        let limit = self.limit() as usize;
+        if limit > 0 && value.chars().count() > limit {
+            return Err(format!("value exceeds limit of {limit} characters"));
+        }
+        if let EditStyle::CheckBox(v) = self {
+            if value != v.on && value != v.off && v.other.as_deref() != Some(value) {
+                return Err(format!("invalid checkbox value: {value}"));
+            }
+        }
+        if self.validates_code() {
+            if let Some(code_table) = code_table {
+                if code_table.display_value(value).is_none() {
+                    return Err(format!("value not in code table: {value}"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Item<'a> {
+    /// 获取字段控件的编辑风格
+    pub fn edit_style(&self) -> Option<EditStyle<'a>> { EditStyle::from_values(&self.values) }
+
+    /// 设置字段控件的编辑风格
+    pub fn set_edit_style(&mut self, style: &EditStyle<'a>) { style.write_to(&mut self.values) }
+
+    /// 获取控件的代码表
+    ///
+    /// `values="显示值\t数据值/显示值\t数据值/"`
+    pub fn code_table(&self) -> Option<CodeTable> {
+        self.values.get(&"values".into_key()).and_then(|v| v.as_str()).map(CodeTable::parse)
+    }
+
+    /// 设置控件的代码表
+    pub fn set_code_table(&mut self, code_table: &CodeTable) {
+        self.values.insert("values".into_key(), Value::DoubleQuotedString(code_table.to_string().into()));
+    }
+}
+
+impl<'a> ItemTableColumn<'a> {
+    /// 获取字段的初始代码表
+    ///
+    /// `values="显示值\t数据值/显示值\t数据值/"`
+    pub fn code_table(&self) -> Option<CodeTable> {
+        self.values.get(&"values".into_key()).and_then(|v| v.as_str()).map(CodeTable::parse)
+    }
+
+    /// 设置字段的初始代码表
+    pub fn set_code_table(&mut self, code_table: &CodeTable) {
+        self.values.insert("values".into_key(), Value::DoubleQuotedString(code_table.to_string().into()));
+    }
+}
+
+/// 代码表
+///
+/// # Syntax
+///
+/// ```txt
+/// values="盘盈~tP/盘亏~tL/正常~tN/"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CodeTable {
+    pub entries: Vec<CodeTableEntry>
+}
+
+/// 代码表项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeTableEntry {
+    /// 显示值
+    pub display: String,
+    /// 数据值
+    pub data: String
+}
+
+impl CodeTable {
+    /// 解析代码表
+    ///
+    /// 显示值和数据值以`\t`(或`~t`)分隔,代码项以`/`分隔(`~/`/`~~`/`~\t`/`~"`转义)
+    pub fn parse(input: &str) -> Self {
+        let mut entries = vec![];
+        let mut field = String::new();
+        let mut display = None;
+        let mut chars = input.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '~' => match chars.next() {
+                    Some('t') => display = Some(std::mem::take(&mut field)),
+                    Some(c) => field.push(c),
+                    None => field.push('~')
+                },
+                '\t' => display = Some(std::mem::take(&mut field)),
+                '/' => {
+                    let data = std::mem::take(&mut field);
+                    let display = display.take().unwrap_or_else(|| data.clone());
+                    entries.push(CodeTableEntry {
+                        display,
+                        data
+                    });
+                },
+                _ => field.push(c)
+            }
+        }
+        if !field.is_empty() || display.is_some() {
+            let display = display.unwrap_or_else(|| field.clone());
+            entries.push(CodeTableEntry {
+                display,
+                data: field
+            });
+        }
+        CodeTable {
+            entries
+        }
+    }
+
+    /// 通过数据值获取显示值
+    pub fn display_value(&self, data: &str) -> Option<&str> {
+        self.entries.iter().find(|v| v.data == data).map(|v| v.display.as_str())
+    }
+
+    /// 通过显示值获取数据值
+    pub fn data_value(&self, display: &str) -> Option<&str> {
+        self.entries.iter().find(|v| v.display == display).map(|v| v.data.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    pub fn len(&self) -> usize { self.entries.len() }
+}
+
+impl Display for CodeTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            write!(f, "{}\t{}/", escape(&entry.display), escape(&entry.data))?;
+        }
+        Ok(())
+    }
+}
+
+/// 代码表值转义(`~`/`/`/`\t`/`"`前加`~`)
+fn escape(v: &str) -> String {
+    let mut result = String::with_capacity(v.len());
+    for c in v.chars() {
+        if matches!(c, '~' | '/' | '\t' | '"') {
+            result.push('~');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// 参数名是否以指定前缀开头(忽略大小写)
+fn has_prefix(key: &str, prefix: &str) -> bool {
+    key.len() > prefix.len() &&
+        key.is_char_boundary(prefix.len()) &&
+        key[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+fn yes_no<'a>(v: bool) -> Value<'a> { Value::Literal(if v { "yes" } else { "no" }.into()) }
+
+fn lit<'a>(v: impl Display) -> Value<'a> { Value::Literal(v.to_string().into()) }
+
+/// 编辑风格参数读取
+struct Props<'a> {
+    values: HashMap<String, Value<'a>>
+}
+
+impl<'a> Props<'a> {
+    fn new(values: &HashMap<Key<'a>, Value<'a>>, prefix: &str) -> Self {
+        Props {
+            values: values
+                .iter()
+                .filter(|(key, _)| has_prefix(key, prefix))
+                .map(|(key, value)| (key[prefix.len()..].to_ascii_lowercase(), value.to_owned()))
+                .collect()
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Option<Value<'a>> {
+        #[cfg(feature = "preserve_order")]
+        return self.values.shift_remove(key);
+        #[cfg(not(feature = "preserve_order"))]
+        return self.values.remove(key);
+    }
+
+    fn bool(&mut self, key: &str) -> bool { self.take(key).and_then(|v| v.to_bool()).unwrap_or_default() }
+
+    fn number(&mut self, key: &str) -> u32 {
+        self.take(key).and_then(|v| v.to_number()).unwrap_or_default() as u32
+    }
+
+    fn string(&mut self, key: &str) -> Cow<'a, str> {
+        match self.take(key) {
+            Some(Value::Literal(v) | Value::DoubleQuotedString(v) | Value::SingleQuotedString(v)) => v,
+            _ => Cow::Borrowed("")
+        }
+    }
+
+    fn case(&mut self, key: &str) -> TextCase {
+        self.take(key).and_then(|v| v.as_str().map(TextCase::parse)).unwrap_or_default()
+    }
+
+    fn into_others(self) -> HashMap<Key<'a>, Value<'a>> {
+        self.values.into_iter().map(|(key, value)| (key.into_key(), value)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_style() {
+        let dw = DWSyntax::parse(
+            r#"release 19;
+table(column=(type=char(10) name=status dbname="status" values="盘盈	P/盘亏	L/正常	N/"))
+column(band=detail id=1 name=status dddw.name=d_status dddw.displaycolumn=name dddw.datacolumn=code dddw.percentwidth=120 dddw.lines=0 dddw.limit=0 dddw.allowedit=no dddw.useasborder=yes dddw.case=upper dddw.required=yes)
+column(band=detail id=1 name=flag checkbox.text="启用" checkbox.on="Y" checkbox.off="N" checkbox.scale=no)
+column(band=detail id=1 name=kind values="A~tX/B	Y/" ddlb.limit=0 ddlb.allowedit=no ddlb.case=any)
+"#
+        )
+        .unwrap();
+        let code_table = dw.table.columns[0].code_table().unwrap();
+        assert_eq!(code_table.len(), 3);
+        assert_eq!(code_table.display_value("L"), Some("盘亏"));
+        assert_eq!(code_table.data_value("正常"), Some("N"));
+        assert_eq!(code_table.to_string(), "盘盈\tP/盘亏\tL/正常\tN/");
+
+        let mut item = dw.items[0].to_owned();
+        let style = item.edit_style().unwrap();
+        match &style {
+            EditStyle::DropDownDataWindow(v) => {
+                assert_eq!(v.name, "d_status");
+                assert_eq!(v.display_column, "name");
+                assert_eq!(v.data_column, "code");
+                assert_eq!(v.percent_width, 120);
+                assert_eq!(v.case, TextCase::Upper);
+                assert!(v.required);
+                assert!(!v.allow_edit);
+                assert_eq!(v.others.get(&"useasborder".into_key()), Some(&Value::Literal("yes".into())));
+            },
+            _ => panic!("unexpected style: {style:?}")
+        }
+        assert_eq!(style.validate(None, None), Err("value is required".to_owned()));
+
+        item.set_edit_style(&EditStyle::Edit(Edit {
+            limit: 2,
+            code_table: true,
+            validate_code: true,
+            ..Default::default()
+        }));
+        assert!(!item.values.keys().any(|key| key.starts_with("dddw.")));
+        assert_eq!(item.values.get(&"edit.limit".into_key()), Some(&Value::Number(2.)));
+        let style = item.edit_style().unwrap();
+        assert_eq!(style.validate(Some("P"), Some(&code_table)), Ok(()));
+        assert!(style.validate(Some("X"), Some(&code_table)).is_err());
+        assert!(style.validate(Some("PPP"), None).is_err());
+
+        let style = dw.items[1].edit_style().unwrap();
+        assert_eq!(style, EditStyle::CheckBox(CheckBox {
+            text: "启用".into(),
+            on: "Y".into(),
+            off: "N".into(),
+            other: None,
+            left_text: false,
+            others: HashMap::from([("scale".into_key(), Value::Literal("no".into()))])
+        }));
+        assert!(style.validate(Some("X"), None).is_err());
+
+        let item = &dw.items[2];
+        let code_table = item.code_table().unwrap();
+        assert_eq!(code_table.data_value("A"), Some("X"));
+        assert_eq!(code_table.data_value("B"), Some("Y"));
+        let style = item.edit_style().unwrap();
+        assert!(matches!(style, EditStyle::DropDownListBox(_)));
+        assert!(style.validate(Some("Z"), Some(&code_table)).is_err());
+
+        let code_table = CodeTable {
+            entries: vec![CodeTableEntry {
+                display: "a~b\tc/d".into(),
+                data: "1/".into()
+            }]
+        };
+        assert_eq!(CodeTable::parse(&code_table.to_string()), code_table);
+
+        let mut item = dw.items[0].to_owned();
+        item.set_edit_style(&EditStyle::DropDownDataWindow(Default::default()));
+        assert!(!item.values.contains_key(&"dddw.name".into_key()));
+        let input = format!("release 19;\r\n{item}");
+        let dw = DWSyntax::parse(&input).unwrap();
+        assert!(matches!(dw.items[0].edit_style(), Some(EditStyle::DropDownDataWindow(_))));
+    }
+
+    #[test]
+    fn test_code_table_quote() {
+        let code_table = CodeTable {
+            entries: vec![CodeTableEntry {
+                display: "He said \"x\"".into(),
+                data: "\"q\"".into()
+            }]
+        };
+        let mut dw = DWSyntax::parse("release 19;\r\ncolumn(band=detail id=1 name=c1)").unwrap();
+        dw.items[0].set_code_table(&code_table);
+        let input = dw.to_string();
+        let dw = DWSyntax::parse(&input).unwrap();
+        assert_eq!(dw.items[0].code_table(), Some(code_table));
+    }
+}
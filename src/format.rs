@@ -0,0 +1,754 @@
+use crate::{ast::*, data::*, prelude::*};
+
+/// `[General]`日期时间格式
+const GENERAL_DATE: &str = "yyyy-mm-dd";
+const GENERAL_TIME: &str = "hh:mm:ss";
+const GENERAL_DATETIME: &str = "yyyy-mm-dd hh:mm:ss";
+/// `[ShortDate]`/`[LongDate]`/`[Time]`
+///
+/// NOTE 不读取操作系统区域设置
+const SHORT_DATE: &str = "yyyy-mm-dd";
+const LONG_DATE: &str = "dddd, mmmm d, yyyy";
+const TIME: &str = "hh:mm:ss";
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December"
+];
+const DAY_NAMES: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+
+/// 显示格式
+///
+/// 兼容PowerBuilder的`format`/`editmask.mask`参数
+///
+/// # Syntax
+///
+/// ```txt
+/// [color]positive;[color]negative;[color]zero;[color]null
+/// [color]date-format;[color]null
+/// [General] [Currency] [Currency(n)] [ShortDate] [LongDate] [Date] [Time]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayFormat {
+    sections: Vec<Section>
+}
+
+/// 格式化结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatted {
+    /// 显示文本
+    pub text: String,
+    /// 颜色前缀指定的颜色(`BGR`)
+    pub color: Option<u32>
+}
+
+/// 格式段
+#[derive(Debug, Clone, PartialEq)]
+struct Section {
+    color: Option<u32>,
+    kind: SectionKind,
+    tokens: Vec<Token>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SectionKind {
+    General,
+    Number,
+    DateTime,
+    String
+}
+
+/// 格式符
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// 原样输出的文本
+    Literal(String),
+    /// `#`
+    DigitOpt,
+    /// `0`
+    DigitZero,
+    /// `,`
+    Group,
+    /// `.`
+    Point,
+    /// `%`
+    Percent,
+    /// `E+00`/`e-0`
+    Exponent {
+        upper: bool,
+        sign: bool,
+        digits: usize
+    },
+    /// `@`
+    Char,
+    /// 日期时间符号(小写),如:`yyyy`/`mm`/`dd`/`hh`/`ampm`
+    DateTime(String)
+}
+
+impl DisplayFormat {
+    /// 解析显示格式
+    pub fn parse(format: &str) -> Self {
+        let sections = split_sections(format).into_iter().map(Section::parse).collect();
+        DisplayFormat {
+            sections
+        }
+    }
+
+    /// 格式化数据值
+    pub fn format(&self, value: &DataValue) -> Formatted {
+        let first = match self.sections.first() {
+            Some(v) => v,
+            None => {
+                return Formatted {
+                    text: value.to_string(),
+                    color: None
+                };
+            }
+        };
+        let is_number_format = matches!(first.kind, SectionKind::Number | SectionKind::General);
+        match value {
+            DataValue::Null => {
+                let index = if is_number_format { 3 } else { 1 };
+                match self.sections.get(index) {
+                    Some(section) => section.format_literal(),
+                    None => Formatted {
+                        text: String::new(),
+                        color: None
+                    }
+                }
+            },
+            DataValue::Number(v) => {
+                let v = *v;
+                let (section, negative) = if v < 0. {
+                    match self.sections.get(1) {
+                        Some(section) => (section, false),
+                        None => (first, true)
+                    }
+                } else if v == 0. {
+                    (self.sections.get(2).unwrap_or(first), false)
+                } else {
+                    (first, false)
+                };
+                let text = match section.kind {
+                    SectionKind::Number => format_number(&section.tokens, v.abs(), negative),
+                    SectionKind::String => section.format_literal().text,
+                    _ => general_number(if negative { v } else { v.abs() })
+                };
+                Formatted {
+                    text,
+                    color: section.color
+                }
+            },
+            DataValue::String(v) => {
+                let text = match first.kind {
+                    SectionKind::String => format_string(&first.tokens, v),
+                    _ => v.clone()
+                };
+                Formatted {
+                    text,
+                    color: first.color
+                }
+            },
+            DataValue::Date(_) | DataValue::Time(_) | DataValue::DateTime(..) => {
+                let (date, time) = match value {
+                    DataValue::Date(d) => (Some(*d), None),
+                    DataValue::Time(t) => (None, Some(*t)),
+                    DataValue::DateTime(d, t) => (Some(*d), Some(*t)),
+                    _ => unreachable!()
+                };
+                let text = match first.kind {
+                    SectionKind::DateTime => format_datetime(&first.tokens, date, time),
+                    _ => {
+                        let general = match (date, time) {
+                            (Some(_), Some(_)) => GENERAL_DATETIME,
+                            (Some(_), None) => GENERAL_DATE,
+                            _ => GENERAL_TIME
+                        };
+                        format_datetime(&tokenize(general, SectionKind::DateTime), date, time)
+                    }
+                };
+                Formatted {
+                    text,
+                    color: first.color
+                }
+            }
+        }
+    }
+}
+
+impl Section {
+    fn parse(input: &str) -> Self {
+        let mut input = input;
+        let mut color = None;
+        let mut kind = None;
+        let mut body = String::new();
+        //解析前缀关键字
+        while let Some(rest) = input.trim_start().strip_prefix('[') {
+            let (keyword, rest) = match rest.split_once(']') {
+                Some(v) => v,
+                None => break
+            };
+            input = rest;
+            let keyword = keyword.trim().to_ascii_lowercase();
+            if let Some(v) = color_by_name(&keyword) {
+                color = Some(v);
+            } else if let Ok(v) = keyword.parse::<u32>() {
+                color = Some(v);
+            } else if keyword == "general" {
+                kind = Some(SectionKind::General);
+            } else if let Some(decimals) = keyword.strip_prefix("currency") {
+                let decimals = decimals
+                    .trim()
+                    .strip_prefix('(')
+                    .and_then(|v| v.strip_suffix(')'))
+                    .and_then(|v| v.trim().parse::<usize>().ok())
+                    .unwrap_or(2);
+                body = "$#,##0".to_owned();
+                if decimals > 0 {
+                    body.push('.');
+                    body.push_str(&"0".repeat(decimals));
+                }
+            } else if keyword == "shortdate" || keyword == "date" {
+                body = SHORT_DATE.to_owned();
+            } else if keyword == "longdate" {
+                body = LONG_DATE.to_owned();
+            } else if keyword == "time" {
+                body = TIME.to_owned();
+            } else {
+                body.push('[');
+                body.push_str(&keyword);
+                body.push(']');
+            }
+        }
+        body.push_str(input);
+        let kind = kind.unwrap_or_else(|| detect_kind(&body));
+        let tokens = tokenize(&body, kind);
+        Section {
+            color,
+            kind,
+            tokens
+        }
+    }
+
+    /// 原样输出格式中的文本(用于`null`格式段)
+    fn format_literal(&self) -> Formatted {
+        let text = self
+            .tokens
+            .iter()
+            .map(|token| match token {
+                Token::Literal(v) | Token::DateTime(v) => v.as_str(),
+                _ => ""
+            })
+            .collect();
+        Formatted {
+            text,
+            color: self.color
+        }
+    }
+}
+
+/// 按`;`拆分格式段(忽略引号和转义中的`;`)
+fn split_sections(format: &str) -> Vec<&str> {
+    let mut sections = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in format.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                sections.push(&format[start..i]);
+                start = i + 1;
+            },
+            _ => {}
+        }
+    }
+    sections.push(&format[start..]);
+    sections
+}
+
+/// 识别格式段的类型
+fn detect_kind(body: &str) -> SectionKind {
+    let (mut has_digit, mut has_datetime) = (false, false);
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in body.chars() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        if quoted {
+            quoted = c != '"';
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '"' => quoted = true,
+            '@' => return SectionKind::String,
+            '#' | '0' => has_digit = true,
+            'y' | 'Y' | 'd' | 'D' | 'm' | 'M' | 'h' | 'H' | 's' | 'S' | 'f' | 'F' => has_datetime = true,
+            _ => {}
+        }
+    }
+    if has_digit {
+        SectionKind::Number
+    } else if has_datetime {
+        SectionKind::DateTime
+    } else {
+        SectionKind::String
+    }
+}
+
+/// 拆分格式符
+fn tokenize(body: &str, kind: SectionKind) -> Vec<Token> {
+    fn push_literal(tokens: &mut Vec<Token>, c: char) {
+        match tokens.last_mut() {
+            Some(Token::Literal(v)) => v.push(c),
+            _ => tokens.push(Token::Literal(c.to_string()))
+        }
+    }
+    let chars: Vec<char> = body.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '\\' => {
+                if i < chars.len() {
+                    push_literal(&mut tokens, chars[i]);
+                    i += 1;
+                }
+            },
+            '"' => {
+                while i < chars.len() && chars[i] != '"' {
+                    push_literal(&mut tokens, chars[i]);
+                    i += 1;
+                }
+                i += 1;
+            },
+            '@' if kind == SectionKind::String => tokens.push(Token::Char),
+            '#' if kind == SectionKind::Number => tokens.push(Token::DigitOpt),
+            '0' if kind == SectionKind::Number => tokens.push(Token::DigitZero),
+            ',' if kind == SectionKind::Number => tokens.push(Token::Group),
+            '.' if kind == SectionKind::Number => tokens.push(Token::Point),
+            '%' if kind == SectionKind::Number => tokens.push(Token::Percent),
+            'E' | 'e'
+                if kind == SectionKind::Number && i < chars.len() && (chars[i] == '+' || chars[i] == '-') =>
+            {
+                let sign = chars[i] == '+';
+                i += 1;
+                let mut digits = 0;
+                while i < chars.len() && chars[i] == '0' {
+                    digits += 1;
+                    i += 1;
+                }
+                tokens.push(Token::Exponent {
+                    upper: c == 'E',
+                    sign,
+                    digits: digits.max(1)
+                });
+            },
+            _ if kind == SectionKind::DateTime => {
+                let rest: String = chars[i - 1..].iter().collect::<String>().to_ascii_lowercase();
+                if let Some(ampm) = ["am/pm", "a/p"].iter().find(|v| rest.starts_with(*v)) {
+                    //保留大小写
+                    let upper = c.is_ascii_uppercase();
+                    tokens.push(Token::DateTime(format!("{}{}", if upper { "^" } else { "" }, ampm)));
+                    i += ampm.chars().count() - 1;
+                    continue;
+                }
+                let lower = c.to_ascii_lowercase();
+                if matches!(lower, 'y' | 'm' | 'd' | 'h' | 's' | 'f') {
+                    let mut n = 1;
+                    while i < chars.len() && chars[i].to_ascii_lowercase() == lower {
+                        n += 1;
+                        i += 1;
+                    }
+                    tokens.push(Token::DateTime(lower.to_string().repeat(n)));
+                } else {
+                    push_literal(&mut tokens, c);
+                }
+            },
+            _ => push_literal(&mut tokens, c)
+        }
+    }
+    tokens
+}
+
+/// 数值格式化
+fn format_number(tokens: &[Token], mut value: f64, negative: bool) -> String {
+    let point = tokens.iter().position(|v| *v == Token::Point);
+    let exp = tokens.iter().position(|v| matches!(v, Token::Exponent { .. }));
+    let int_end = point.or(exp).unwrap_or(tokens.len());
+    let frac_end = exp.unwrap_or(tokens.len());
+    let is_digit = |v: &Token| matches!(v, Token::DigitOpt | Token::DigitZero);
+    let int_slots: Vec<usize> = (0..int_end).filter(|&i| is_digit(&tokens[i])).collect();
+    let frac_slots: Vec<usize> = match point {
+        Some(point) => (point + 1..frac_end).filter(|&i| is_digit(&tokens[i])).collect(),
+        None => vec![]
+    };
+    let grouping = tokens[..int_end].contains(&Token::Group);
+    if tokens.contains(&Token::Percent) {
+        value *= 100.;
+    }
+    //科学计数法
+    let mut exponent = 0i32;
+    if exp.is_some() && value != 0. {
+        let int_digits = int_slots.len().max(1) as i32;
+        exponent = value.log10().floor() as i32 - (int_digits - 1);
+        value /= 10f64.powi(exponent);
+    }
+    //四舍五入
+    let decimals = frac_slots.len();
+    let text = format!("{:.*}", decimals, round(value, decimals));
+    let (int_text, frac_text) = match text.split_once('.') {
+        Some((i, f)) => (i.to_owned(), f.to_owned()),
+        None => (text, String::new())
+    };
+    //进位后科学计数法整数位溢出
+    let (int_text, exponent) = if exp.is_some() && int_text.len() > int_slots.len().max(1) && value != 0. {
+        let shift = (int_text.len() - int_slots.len().max(1)) as i32;
+        let v = value / 10f64.powi(shift);
+        let text = format!("{:.*}", decimals, round(v, decimals));
+        (text.split_once('.').map(|v| v.0.to_owned()).unwrap_or(text), exponent + shift)
+    } else {
+        (int_text, exponent)
+    };
+    //整数部分
+    let min_int = int_slots
+        .iter()
+        .position(|&i| tokens[i] == Token::DigitZero)
+        .map(|first_zero| int_slots.len() - first_zero)
+        .unwrap_or(0);
+    let mut digits = int_text.trim_start_matches('0').to_owned();
+    while digits.len() < min_int {
+        digits.insert(0, '0');
+    }
+    let is_zero = digits.chars().chain(frac_text.chars()).all(|c| c == '0');
+    //小数部分(去掉`#`位置的末尾0)
+    let mut frac: Vec<char> = frac_text.chars().collect();
+    while let Some(&last) = frac.last() {
+        if last == '0' && tokens[frac_slots[frac.len() - 1]] == Token::DigitOpt {
+            frac.pop();
+        } else {
+            break;
+        }
+    }
+
+    let mut rv = String::new();
+    if negative && !is_zero {
+        rv.push('-');
+    }
+    //整数位从右向左填充,多余的数字填充到第一个位置
+    let mut int_fill: Vec<String> = vec![String::new(); int_slots.len()];
+    if grouping {
+        if let Some(first) = int_fill.first_mut() {
+            *first = group_digits(&digits);
+        }
+    } else {
+        let mut chars: Vec<char> = digits.chars().collect();
+        for slot in (0..int_slots.len()).rev() {
+            if slot == 0 {
+                int_fill[0] = chars.iter().collect();
+            } else if let Some(c) = chars.pop() {
+                int_fill[slot] = c.to_string();
+            }
+        }
+    }
+    let mut int_slot = 0;
+    let mut frac_slot = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::DigitOpt | Token::DigitZero if i < int_end => {
+                rv.push_str(&int_fill[int_slot]);
+                int_slot += 1;
+            },
+            Token::DigitOpt | Token::DigitZero => {
+                if let Some(c) = frac.get(frac_slot) {
+                    rv.push(*c);
+                }
+                frac_slot += 1;
+            },
+            Token::Point => rv.push('.'),
+            Token::Group => {},
+            Token::Percent => rv.push('%'),
+            Token::Exponent {
+                upper,
+                sign,
+                digits
+            } => {
+                rv.push(if *upper { 'E' } else { 'e' });
+                if exponent < 0 {
+                    rv.push('-');
+                } else if *sign {
+                    rv.push('+');
+                }
+                rv.push_str(&format!("{:0width$}", exponent.abs(), width = *digits));
+            },
+            Token::Literal(v) | Token::DateTime(v) => rv.push_str(v),
+            Token::Char => {}
+        }
+    }
+    rv
+}
+
+/// 四舍五入(远离零)
+fn round(value: f64, decimals: usize) -> f64 {
+    let p = 10f64.powi(decimals as i32);
+    (value * p).round() / p
+}
+
+/// 千分位分组
+fn group_digits(digits: &str) -> String {
+    let len = digits.len();
+    let mut rv = String::with_capacity(len + len / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (len - i) % 3 == 0 {
+            rv.push(',');
+        }
+        rv.push(c);
+    }
+    rv
+}
+
+/// `[General]`数值格式
+pub(crate) fn general_number(value: f64) -> String {
+    if value.fract() == 0. && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        let text = format!("{:.10}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_owned()
+    }
+}
+
+/// 日期时间格式化
+fn format_datetime(tokens: &[Token], date: Option<Date>, time: Option<Time>) -> String {
+    let date = date.unwrap_or(Date::new(1900, 1, 1));
+    let time = time.unwrap_or_default();
+    let has_ampm =
+        tokens.iter().any(|v| matches!(v, Token::DateTime(v) if v.ends_with("/p") || v.ends_with("/pm")));
+    //`m`/`mm`在`h`之后或`s`之前表示分钟
+    let is_minute = |index: usize| {
+        let prev = tokens[..index].iter().rev().find_map(|v| match v {
+            Token::DateTime(v) => Some(v.as_str()),
+            _ => None
+        });
+        let next = tokens[index + 1..].iter().find_map(|v| match v {
+            Token::DateTime(v) => Some(v.as_str()),
+            _ => None
+        });
+        matches!(prev, Some(v) if v.starts_with('h')) || matches!(next, Some(v) if v.starts_with('s'))
+    };
+    let mut rv = String::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let symbol = match token {
+            Token::DateTime(v) => v.as_str(),
+            Token::Literal(v) => {
+                rv.push_str(v);
+                continue;
+            },
+            _ => continue
+        };
+        match symbol {
+            "yy" => rv.push_str(&format!("{:02}", date.year.rem_euclid(100))),
+            "yyy" | "yyyy" => rv.push_str(&format!("{:04}", date.year)),
+            "m" | "mm" if is_minute(index) => {
+                rv.push_str(&format!("{:0width$}", time.minute, width = symbol.len()));
+            },
+            "m" | "mm" => rv.push_str(&format!("{:0width$}", date.month, width = symbol.len())),
+            "mmm" => rv.push_str(&month_name(date.month)[..3]),
+            "mmmm" => rv.push_str(month_name(date.month)),
+            "d" | "dd" => rv.push_str(&format!("{:0width$}", date.day, width = symbol.len())),
+            "ddd" => rv.push_str(&DAY_NAMES[date.weekday() as usize][..3]),
+            "dddd" => rv.push_str(DAY_NAMES[date.weekday() as usize]),
+            "h" | "hh" => {
+                let hour = if has_ampm {
+                    match time.hour % 12 {
+                        0 => 12,
+                        v => v
+                    }
+                } else {
+                    time.hour
+                };
+                rv.push_str(&format!("{:0width$}", hour, width = symbol.len()));
+            },
+            "s" | "ss" => rv.push_str(&format!("{:0width$}", time.second, width = symbol.len())),
+            "am/pm" | "^am/pm" => {
+                let v = if time.hour < 12 { "am" } else { "pm" };
+                rv.push_str(&if symbol.starts_with('^') { v.to_ascii_uppercase() } else { v.to_owned() });
+            },
+            "a/p" | "^a/p" => {
+                let v = if time.hour < 12 { "a" } else { "p" };
+                rv.push_str(&if symbol.starts_with('^') { v.to_ascii_uppercase() } else { v.to_owned() });
+            },
+            _ if symbol.starts_with('f') => {
+                let digits = symbol.len().min(6);
+                rv.push_str(&format!("{:06}", time.microsecond)[..digits]);
+            },
+            _ => rv.push_str(symbol)
+        }
+    }
+    rv
+}
+
+fn month_name(month: u32) -> &'static str { MONTH_NAMES[(month.clamp(1, 12) - 1) as usize] }
+
+/// 字符串格式化(`@`)
+fn format_string(tokens: &[Token], value: &str) -> String {
+    let mut chars = value.chars();
+    let mut rv = String::new();
+    for token in tokens {
+        match token {
+            Token::Char => {
+                if let Some(c) = chars.next() {
+                    rv.push(c);
+                }
+            },
+            Token::Literal(v) => rv.push_str(v),
+            _ => {}
+        }
+    }
+    rv
+}
+
+/// 掩码编辑框格式化
+///
+/// 字符串掩码使用`!`(大写),`^`(小写),`#`(数字),`a`(字母数字),`x`(任意字符),
+/// 数值和日期时间掩码与显示格式相同
+pub fn format_edit_mask(mask: &str, value: &DataValue) -> String {
+    let value = match value {
+        DataValue::String(v) => v,
+        _ => return DisplayFormat::parse(mask).format(value).text
+    };
+    let mut chars = value.chars();
+    let mut rv = String::new();
+    let mut mask_chars = mask.chars();
+    while let Some(m) = mask_chars.next() {
+        match m {
+            '!' | '^' | '#' | 'a' | 'x' => {
+                if let Some(c) = chars.next() {
+                    match m {
+                        '!' => rv.extend(c.to_uppercase()),
+                        '^' => rv.extend(c.to_lowercase()),
+                        _ => rv.push(c)
+                    }
+                }
+            },
+            '\\' => {
+                if let Some(c) = mask_chars.next() {
+                    rv.push(c);
+                }
+            },
+            _ => rv.push(m)
+        }
+    }
+    rv
+}
+
+/// 颜色名称(`BGR`)
+fn color_by_name(name: &str) -> Option<u32> {
+    let color = match name {
+        "black" => 0,
+        "blue" => 16711680,
+        "cyan" => 16776960,
+        "green" => 65280,
+        "magenta" => 16711935,
+        "red" => 255,
+        "white" => 16777215,
+        "yellow" => 65535,
+        _ => return None
+    };
+    Some(color)
+}
+
+impl<'a> Item<'a> {
+    /// 获取控件的显示格式
+    ///
+    /// `format="#,##0.00"`
+    pub fn display_format(&self) -> Option<DisplayFormat> {
+        let format = self.values.get(&"format".into_key())?.as_str()?;
+        Some(DisplayFormat::parse(&unescape(format)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(format: &str, value: impl Into<DataValue>) -> String {
+        DisplayFormat::parse(format).format(&value.into()).text
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(fmt("#,##0.00", 1234567.891), "1,234,567.89");
+        assert_eq!(fmt("#,##0.00", -1234.5), "-1,234.50");
+        assert_eq!(fmt("#,##0.00", 0.), "0.00");
+        assert_eq!(fmt("#,##0.##", 12.), "12.");
+        assert_eq!(fmt("#,##0.##", 12.3), "12.3");
+        assert_eq!(fmt("0.00%", 0.1234), "12.34%");
+        assert_eq!(fmt("000-00", 1234.), "012-34");
+        assert_eq!(fmt("$#,##0;($#,##0);\"Zero\";\"N/A\"", -1500.), "($1,500)");
+        assert_eq!(fmt("$#,##0;($#,##0);\"Zero\";\"N/A\"", 0.), "Zero");
+        assert_eq!(fmt("$#,##0;($#,##0);\"Zero\";\"N/A\"", None::<f64>), "N/A");
+        assert_eq!(fmt("0.00E+00", 12345.), "1.23E+04");
+        assert_eq!(fmt("0.0e-0", 0.00123), "1.2e-3");
+        assert_eq!(fmt("[General]", 12.5), "12.5");
+        assert_eq!(fmt("[Currency]", 1234.5), "$1,234.50");
+        assert_eq!(fmt("[Currency(0)]", 1234.5), "$1,235");
+        assert_eq!(fmt("#", 0.), "");
+        assert_eq!(fmt("#,##0 days", 3.), "3 days");
+        assert_eq!(
+            DisplayFormat::parse("[RED]#,##0;[BLUE]-#,##0").format(&DataValue::Number(-5.)),
+            Formatted {
+                text: "-5".to_owned(),
+                color: Some(16711680)
+            }
+        );
+    }
+
+    #[test]
+    fn test_format_datetime() {
+        let date = Date::new(2024, 3, 5);
+        let time = Time::new(14, 7, 9, 123456);
+        assert_eq!(fmt("yyyy-mm-dd", date), "2024-03-05");
+        assert_eq!(fmt("yy/m/d", date), "24/3/5");
+        assert_eq!(fmt("dddd, mmmm d, yyyy", date), "Tuesday, March 5, 2024");
+        assert_eq!(fmt("ddd mmm", date), "Tue Mar");
+        assert_eq!(fmt("[ShortDate]", date), "2024-03-05");
+        assert_eq!(fmt("hh:mm:ss", time), "14:07:09");
+        assert_eq!(fmt("h:mm AM/PM", time), "2:07 PM");
+        assert_eq!(fmt("hh:mm:ss.fff", time), "14:07:09.123");
+        assert_eq!(
+            DisplayFormat::parse("yyyy年mm月dd日 hh:mm").format(&DataValue::DateTime(date, time)).text,
+            "2024年03月05日 14:07"
+        );
+        assert_eq!(fmt("yyyy-mm-dd;\"无\"", None::<Date>), "无");
+        assert_eq!(fmt("[General]", date), "2024-03-05");
+    }
+
+    #[test]
+    fn test_format_string() {
+        assert_eq!(fmt("@@@-@@@@", "5551234"), "555-1234");
+        assert_eq!(fmt("(@@@) @@@", "12"), "(12) ");
+        assert_eq!(fmt("[General]", "abc"), "abc");
+        assert_eq!(format_edit_mask("!!-###", &"ab123".into()), "AB-123");
+        assert_eq!(format_edit_mask("###,##0.00", &DataValue::Number(1234.)), "1,234.00");
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    ast::*, band::Band, builder::DWSyntaxBuilder, data::ColType, edit_style::*, parser::pbselect::PbSelect,
+    presentation::PresentationStyle
+};
+
+/// 控件左边距
+const MARGIN: u32 = 9;
+/// 控件间距
+const SPACING: u32 = 9;
+/// 标签高度
+const LABEL_HEIGHT: u32 = 64;
+/// 字段控件高度
+const COLUMN_HEIGHT: u32 = 76;
+
+/// 数据库表结构
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TableMeta {
+    /// 表名
+    pub name: String,
+    pub columns: Vec<ColumnMeta>
+}
+
+/// 数据库字段结构
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnMeta {
+    /// 字段名
+    pub name: String,
+    /// SQL类型,如`varchar(20)`/`integer`/`numeric(10,2)`
+    pub sql_type: String,
+    /// 是否允许为空
+    pub nullable: bool,
+    /// 是否为主键
+    pub primary_key: bool
+}
+
+impl ColType {
+    /// 根据SQL类型获取字段数据类型
+    ///
+    /// 按类型名中的关键字识别,无法识别的类型(如`uuid`/`json`)作为字符串
+    pub fn from_sql_type(sql_type: &str) -> ColType {
+        let ty = sql_type.trim().to_ascii_lowercase();
+        let (name, args) = match ty.split_once('(') {
+            Some((name, args)) => (name.trim(), args.trim_end_matches(')').trim()),
+            None => (ty.as_str(), "")
+        };
+        let mut args = args.split(',').map(|v| v.trim().parse::<u32>().ok());
+        let size = args.next().flatten();
+        let scale = args.next().flatten();
+        match name {
+            "bigint" | "int8" => ColType::LongLong,
+            "date" => ColType::Date,
+            "datetime" | "smalldatetime" | "datetime2" => ColType::DateTime,
+            "timestamp" => ColType::Timestamp,
+            "time" => ColType::Time,
+            "money" => ColType::Decimal(4),
+            "decimal" | "numeric" => ColType::Decimal(scale.unwrap_or_default()),
+            "real" | "float" | "double" | "double precision" => ColType::Number,
+            "bit" | "boolean" | "bool" => ColType::Int,
+            name if name.contains("int") => ColType::Long,
+            name if name.contains("char") || name.contains("clob") || name.contains("text") => {
+                ColType::Char(size.unwrap_or(32766))
+            },
+            name if ["real", "floa", "doub"].iter().any(|v| name.contains(v)) => ColType::Number,
+            name if name.is_empty() || name.contains("blob") || name.contains("binary") => ColType::Blob,
+            _ => ColType::Char(size.unwrap_or(32766))
+        }
+    }
+}
+
+impl TableMeta {
+    /// 生成DataWindow语法
+    ///
+    /// - `table`: 所有字段,`retrieve`为`PBSELECT`,存在主键时设置`update`/`updatewhere=1`及`key=yes`
+    /// - `header`带区: 字段标签`<column>_t`
+    /// - `detail`带区: 字段控件(`blob`字段除外),日期时间类型使用`editmask`,其余使用`edit`,
+    ///   不允许为空的字段设置为必填,按字段顺序设置`tabsequence`
+    ///
+    /// 仅支持`Tabular`/`Freeform`/`Grid`风格
+    pub fn to_syntax(&self, style: PresentationStyle) -> Result<DWSyntax<'static>, String> {
+        if self.columns.is_empty() {
+            return Err(format!("table has no column: {}", self.name));
+        }
+        let mut builder = DWSyntaxBuilder::new();
+        for column in &self.columns {
+            let ty = ColType::from_sql_type(&column.sql_type);
+            builder = builder.column_db(&column.name, ty, &format!("{}.{}", self.name, column.name));
+            if column.primary_key {
+                builder = builder.key(&column.name);
+            }
+        }
+        let select = PbSelect {
+            tables: vec![self.name.clone()],
+            columns: self.columns.iter().map(|col| format!("{}.{}", self.name, col.name)).collect(),
+            ..Default::default()
+        };
+        builder = builder.retrieve_sql(&select.to_pbselect());
+        if self.columns.iter().any(|col| col.primary_key) {
+            builder = builder.update_table(&self.name, 1);
+        }
+
+        let mut x = MARGIN;
+        for column in &self.columns {
+            let ty = ColType::from_sql_type(&column.sql_type);
+            if ty == ColType::Blob {
+                continue;
+            }
+            let width = width(ty);
+            builder = builder
+                .text(Band::Header, &format!("{}_t", column.name), &label(&column.name), (
+                    x,
+                    SPACING,
+                    width,
+                    LABEL_HEIGHT
+                ))
+                .column_control(Band::Detail, &column.name, (x, SPACING, width, COLUMN_HEIGHT));
+            //数值主键通常由数据库生成
+            let generated = column.primary_key && ty.is_numeric();
+            let required = !(column.nullable || generated);
+            let mask = match ty {
+                ColType::Date => Some("yyyy-mm-dd"),
+                ColType::DateTime | ColType::Timestamp => Some("yyyy-mm-dd hh:mm:ss"),
+                ColType::Time => Some("hh:mm:ss"),
+                _ => None
+            };
+            let style = match mask {
+                Some(mask) => {
+                    EditStyle::EditMask(EditMask {
+                        mask: mask.into(),
+                        required,
+                        ..Default::default()
+                    })
+                },
+                None => {
+                    EditStyle::Edit(Edit {
+                        limit: match ty {
+                            ColType::Char(size) if size < 32766 => size,
+                            _ => 0
+                        },
+                        required,
+                        auto_select: true,
+                        nil_is_null: column.nullable && ty.is_string(),
+                        ..Default::default()
+                    })
+                }
+            };
+            builder = builder.edit_style(&column.name, &style);
+            x += width + SPACING;
+        }
+        builder = builder
+            .band_header(LABEL_HEIGHT + SPACING * 2)
+            .band_detail(COLUMN_HEIGHT + SPACING * 2)
+            .name(&format!("d_{}", self.name.to_ascii_lowercase()));
+        let mut syntax = builder.build()?;
+        match style {
+            PresentationStyle::Tabular => {},
+            PresentationStyle::Freeform | PresentationStyle::Grid => syntax.set_presentation_style(style)?,
+            _ => return Err(format!("unsupported presentation style: {style}"))
+        }
+        Ok(syntax)
+    }
+
+    /// 读取SQLite数据库中的表结构
+    #[cfg(feature = "sqlite")]
+    pub fn from_sqlite(conn: &rusqlite::Connection, table: &str) -> Result<Self, String> {
+        let mut stmt = conn
+            .prepare("SELECT name, type, \"notnull\", pk FROM pragma_table_info(?1) ORDER BY cid")
+            .map_err(|e| e.to_string())?;
+        let columns = stmt
+            .query_map([table], |row| {
+                Ok(ColumnMeta {
+                    name: row.get(0)?,
+                    sql_type: row.get(1)?,
+                    //主键隐含非空
+                    nullable: row.get::<_, i64>(2)? == 0 && row.get::<_, i64>(3)? == 0,
+                    primary_key: row.get::<_, i64>(3)? > 0
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        if columns.is_empty() {
+            return Err(format!("table not found: {table}"));
+        }
+        Ok(TableMeta {
+            name: table.to_owned(),
+            columns
+        })
+    }
+}
+
+/// 根据字段名生成标签文本
+///
+/// `emp_name` => `Emp Name`
+fn label(name: &str) -> String {
+    name.split(|c: char| c == '_' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                None => String::new()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// 根据数据类型获取控件宽度
+fn width(ty: ColType) -> u32 {
+    match ty {
+        ColType::Char(size) => size.saturating_mul(32).clamp(200, 1200),
+        ColType::Date | ColType::Time => 300,
+        ColType::DateTime | ColType::Timestamp => 500,
+        _ => 329
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_from_sql_type() {
+        assert_eq!(ColType::from_sql_type("VARCHAR(20)"), ColType::Char(20));
+        assert_eq!(ColType::from_sql_type("numeric(10, 2)"), ColType::Decimal(2));
+        assert_eq!(ColType::from_sql_type("INTEGER"), ColType::Long);
+        assert_eq!(ColType::from_sql_type("datetime"), ColType::DateTime);
+        assert_eq!(ColType::from_sql_type("uuid"), ColType::Char(32766));
+        assert_eq!(ColType::from_sql_type("JSON"), ColType::Char(32766));
+        assert_eq!(ColType::from_sql_type("jsonb"), ColType::Char(32766));
+        assert_eq!(ColType::from_sql_type("BLOB"), ColType::Blob);
+    }
+
+    #[test]
+    fn test_generate() {
+        assert_eq!(label("emp_first_name"), "Emp First Name");
+        assert_eq!(width(ColType::Char(u32::MAX)), 1200);
+
+        let meta = TableMeta {
+            name: "emp".to_owned(),
+            columns: vec![
+                ColumnMeta {
+                    name: "id".to_owned(),
+                    sql_type: "INTEGER".to_owned(),
+                    nullable: false,
+                    primary_key: true
+                },
+                ColumnMeta {
+                    name: "name".to_owned(),
+                    sql_type: "VARCHAR(40)".to_owned(),
+                    nullable: false,
+                    primary_key: false
+                },
+                ColumnMeta {
+                    name: "hired".to_owned(),
+                    sql_type: "DATE".to_owned(),
+                    nullable: true,
+                    primary_key: false
+                },
+                ColumnMeta {
+                    name: "photo".to_owned(),
+                    sql_type: "BLOB".to_owned(),
+                    nullable: true,
+                    primary_key: false
+                },
+            ]
+        };
+        let dw = meta.to_syntax(PresentationStyle::Tabular).unwrap();
+        let syntax = dw.to_string();
+        let dw = DWSyntax::parse(&syntax).unwrap();
+        assert_eq!(dw.table.columns.len(), 4);
+        let retrieve = dw.table.values.get(&"retrieve".into_key()).and_then(|v| v.as_str()).unwrap();
+        let select = PbSelect::parse(retrieve).unwrap();
+        assert_eq!(select.to_sql(), "SELECT emp.id, emp.name, emp.hired, emp.photo FROM emp");
+        assert!(syntax.contains("update=\"emp\" updatewhere=1"));
+        assert!(syntax.contains("name=id dbname=\"emp.id\" key=yes"));
+        assert!(dw.table.update_spec().unwrap().validate().is_ok());
+        let column = |name: &str| {
+            dw.items.iter().find(|v| v.kind == "column" && matches!(&v.name, Some(v) if *v == name))
+        };
+        assert!(column("photo").is_none());
+        assert!(matches!(
+            column("hired").and_then(|v| v.edit_style()),
+            Some(EditStyle::EditMask(EditMask { required: false, .. }))
+        ));
+        assert!(matches!(
+            column("name").and_then(|v| v.edit_style()),
+            Some(EditStyle::Edit(Edit { limit: 40, required: true, .. }))
+        ));
+        let tabsequence = column("hired").and_then(|v| v.values.get(&"tabsequence".into_key())?.to_number());
+        assert_eq!(tabsequence, Some(30.));
+
+        let dw = meta.to_syntax(PresentationStyle::Freeform).unwrap();
+        assert_eq!(dw.presentation_style(), PresentationStyle::Freeform);
+        assert!(meta.to_syntax(PresentationStyle::Label).is_err());
+
+        #[cfg(feature = "sqlite")]
+        {
+            let conn = rusqlite::Connection::open_in_memory().unwrap();
+            conn.execute_batch(
+                "CREATE TABLE emp (id INTEGER PRIMARY KEY, name VARCHAR(40) NOT NULL, hired DATE, photo BLOB)"
+            )
+            .unwrap();
+            assert_eq!(TableMeta::from_sqlite(&conn, "emp").unwrap(), meta);
+            assert!(TableMeta::from_sqlite(&conn, "nosuch").is_err());
+        }
+    }
+}
@@ -0,0 +1,886 @@
+use crate::{ast::*, band::Band, data::*, format::general_number, prelude::*, rect::Rect};
+use std::fmt::Write;
+
+/// 系列颜色
+const PALETTE: [&str; 8] = ["#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7"];
+
+/// 图表类型
+///
+/// `graph(graphtype="2")`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphType {
+    Area,
+    Bar,
+    Bar3D,
+    Bar3DObj,
+    BarStacked,
+    BarStacked3DObj,
+    Col,
+    Col3D,
+    Col3DObj,
+    ColStacked,
+    ColStacked3DObj,
+    Line,
+    Pie,
+    Scatter,
+    Area3D,
+    Line3D,
+    Pie3D,
+    Other(u32)
+}
+
+impl GraphType {
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            1 => GraphType::Area,
+            2 => GraphType::Bar,
+            3 => GraphType::Bar3D,
+            4 => GraphType::Bar3DObj,
+            5 => GraphType::BarStacked,
+            6 => GraphType::BarStacked3DObj,
+            7 => GraphType::Col,
+            8 => GraphType::Col3D,
+            9 => GraphType::Col3DObj,
+            10 => GraphType::ColStacked,
+            11 => GraphType::ColStacked3DObj,
+            12 => GraphType::Line,
+            13 => GraphType::Pie,
+            14 => GraphType::Scatter,
+            15 => GraphType::Area3D,
+            16 => GraphType::Line3D,
+            17 => GraphType::Pie3D,
+            code => GraphType::Other(code)
+        }
+    }
+
+    pub fn code(&self) -> u32 {
+        match self {
+            GraphType::Area => 1,
+            GraphType::Bar => 2,
+            GraphType::Bar3D => 3,
+            GraphType::Bar3DObj => 4,
+            GraphType::BarStacked => 5,
+            GraphType::BarStacked3DObj => 6,
+            GraphType::Col => 7,
+            GraphType::Col3D => 8,
+            GraphType::Col3DObj => 9,
+            GraphType::ColStacked => 10,
+            GraphType::ColStacked3DObj => 11,
+            GraphType::Line => 12,
+            GraphType::Pie => 13,
+            GraphType::Scatter => 14,
+            GraphType::Area3D => 15,
+            GraphType::Line3D => 16,
+            GraphType::Pie3D => 17,
+            GraphType::Other(code) => *code
+        }
+    }
+
+    /// 是否为水平条形图
+    pub fn is_bar(&self) -> bool {
+        matches!(
+            self,
+            GraphType::Bar |
+                GraphType::Bar3D |
+                GraphType::Bar3DObj |
+                GraphType::BarStacked |
+                GraphType::BarStacked3DObj
+        )
+    }
+
+    /// 是否为垂直柱形图
+    pub fn is_col(&self) -> bool {
+        matches!(
+            self,
+            GraphType::Col |
+                GraphType::Col3D |
+                GraphType::Col3DObj |
+                GraphType::ColStacked |
+                GraphType::ColStacked3DObj
+        )
+    }
+
+    /// 是否为堆积图
+    pub fn is_stacked(&self) -> bool {
+        matches!(
+            self,
+            GraphType::BarStacked |
+                GraphType::BarStacked3DObj |
+                GraphType::ColStacked |
+                GraphType::ColStacked3DObj
+        )
+    }
+
+    pub fn is_pie(&self) -> bool { matches!(self, GraphType::Pie | GraphType::Pie3D) }
+
+    pub fn is_line(&self) -> bool { matches!(self, GraphType::Line | GraphType::Line3D) }
+
+    pub fn is_area(&self) -> bool { matches!(self, GraphType::Area | GraphType::Area3D) }
+}
+
+/// 图表的分类/值/系列表达式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphExpr {
+    /// 字段名
+    Column(String),
+    /// 聚合函数
+    ///
+    /// `sum(col for graph)`
+    Aggregate {
+        /// 函数名(小写)
+        func: String,
+        column: String
+    },
+    /// 无法识别的表达式
+    Other(String)
+}
+
+impl GraphExpr {
+    /// 解析单个表达式
+    pub fn parse(expr: &str) -> Self {
+        let expr = expr.trim();
+        if is_ident(expr) {
+            return GraphExpr::Column(expr.to_owned());
+        }
+        match parse_aggregate(expr, "graph") {
+            Some((func, column)) => GraphExpr::Aggregate {
+                func,
+                column
+            },
+            None => GraphExpr::Other(expr.to_owned())
+        }
+    }
+
+    /// 解析逗号分隔的表达式列表(忽略括号中的逗号)
+    pub fn parse_list(exprs: &str) -> Vec<Self> {
+        split_list(exprs).into_iter().map(GraphExpr::parse).collect()
+    }
+}
+
+/// 是否为标识符
+pub(crate) fn is_ident(s: &str) -> bool {
+    matches!(s.chars().next(), Some(c) if c.is_alphabetic() || c == '_') &&
+        s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// 解析聚合函数表达式,返回(小写函数名,字段名)
+///
+/// `sum(col for <scope>)`
+pub(crate) fn parse_aggregate(expr: &str, scope: &str) -> Option<(String, String)> {
+    let (func, rest) = expr.trim().split_once('(')?;
+    let func = func.trim();
+    let inner = rest.trim_end().strip_suffix(')')?.trim();
+    let column = match inner.rsplit_once(char::is_whitespace) {
+        Some((head, tail)) if tail.eq_ignore_ascii_case(scope) => {
+            let head = head.trim_end();
+            match head.len().checked_sub(3) {
+                Some(n) if head.is_char_boundary(n) && head[n..].eq_ignore_ascii_case("for") => {
+                    head[..n].trim_end()
+                },
+                _ => inner
+            }
+        },
+        _ => inner
+    };
+    if is_ident(func) && is_ident(column) {
+        Some((func.to_ascii_lowercase(), column.to_owned()))
+    } else {
+        None
+    }
+}
+
+/// 拆分逗号分隔的列表(忽略括号中的逗号)
+pub(crate) fn split_list(exprs: &str) -> Vec<&str> {
+    let mut rv = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in exprs.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                rv.push(&exprs[start..i]);
+                start = i + 1;
+            },
+            _ => {}
+        }
+    }
+    if !exprs[start..].trim().is_empty() {
+        rv.push(&exprs[start..]);
+    }
+    rv
+}
+
+impl std::fmt::Display for GraphExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphExpr::Column(v) | GraphExpr::Other(v) => write!(f, "{v}"),
+            GraphExpr::Aggregate {
+                func,
+                column
+            } => write!(f, "{func}({column} for graph)")
+        }
+    }
+}
+
+/// 坐标轴
+///
+/// `category.*`/`values.*`/`series.*`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GraphAxis {
+    /// `label`
+    pub label: Option<String>,
+    /// `autoscale`
+    pub auto_scale: bool,
+    /// `minimumvalue`
+    pub minimum: Option<f64>,
+    /// `maximumvalue`
+    pub maximum: Option<f64>,
+    /// `majordivisions`
+    pub major_divisions: u32,
+    /// `sort`(`0`不排序,`1`升序,`2`降序)
+    pub sort: u32
+}
+
+impl GraphAxis {
+    fn from_values(values: &HashMap<Key, Value>, prefix: &str) -> Self {
+        let get = |key: &str| values.get(&format!("{prefix}.{key}").into_key());
+        let label = get("label").and_then(|v| v.as_str()).map(unescape).filter(|v| v != "(None)");
+        GraphAxis {
+            label,
+            auto_scale: get("autoscale").and_then(|v| v.to_bool()).unwrap_or(true),
+            minimum: get("minimumvalue").and_then(|v| v.to_number()),
+            maximum: get("maximumvalue").and_then(|v| v.to_number()),
+            major_divisions: get("majordivisions").and_then(|v| v.to_number()).unwrap_or_default() as u32,
+            sort: get("sort").and_then(|v| v.to_number()).unwrap_or_default() as u32
+        }
+    }
+
+    fn write_to<'a>(&self, values: &mut HashMap<Key<'a>, Value<'a>>, prefix: &str) {
+        let mut put = |key: &str, value: Value<'a>| {
+            values.insert(format!("{prefix}.{key}").into_key(), value);
+        };
+        put("label", Value::DoubleQuotedString(escape(self.label.as_deref().unwrap_or("(None)")).into()));
+        put("autoscale", Value::Number(self.auto_scale as u32 as f64));
+        if let Some(v) = self.minimum {
+            put("minimumvalue", Value::Number(v));
+        }
+        if let Some(v) = self.maximum {
+            put("maximumvalue", Value::Number(v));
+        }
+        put("majordivisions", Value::Number(self.major_divisions as f64));
+        put("sort", Value::Number(self.sort as f64));
+    }
+}
+
+/// 图表控件
+///
+/// # Syntax
+///
+/// ```txt
+/// graph(band=background name=gr_1 graphtype="7" category="col1" values="sum(col2 for graph)" series="col3"
+///     title="Title" legend=2 overlappercent="0" spacing="100" category.label="(None)"
+///     values.autoscale=1 ...)
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphControl {
+    pub name: Option<String>,
+    pub band: Option<Band>,
+    pub graph_type: GraphType,
+    pub title: String,
+    /// 分类表达式
+    pub category: Option<GraphExpr>,
+    /// 值表达式
+    pub values: Vec<GraphExpr>,
+    /// 系列表达式
+    pub series: Option<GraphExpr>,
+    pub category_axis: GraphAxis,
+    pub value_axis: GraphAxis,
+    pub series_axis: GraphAxis,
+    /// 图例位置(`0`为不显示)
+    pub legend: u32,
+    /// 系列重叠百分比
+    pub overlap_percent: i32,
+    /// 分类间距百分比
+    pub spacing: u32,
+    /// 控件宽度
+    pub width: Option<f64>,
+    /// 控件高度
+    pub height: Option<f64>
+}
+
+impl GraphControl {
+    /// 从`graph`语法项读取
+    pub fn from_item(item: &Item) -> Option<Self> {
+        if item.kind != "graph" {
+            return None;
+        }
+        let values = &item.values;
+        let get = |key: &'static str| values.get(&key.into_key());
+        let string = |key: &'static str| get(key).and_then(|v| v.as_str()).map(unescape);
+        let number = |key: &'static str| get(key).and_then(|v| v.to_number());
+        Some(GraphControl {
+            name: item.name.as_ref().map(|v| v.to_string()),
+            band: item.band(),
+            graph_type: GraphType::from_code(number("graphtype").unwrap_or(7.) as u32),
+            title: string("title").unwrap_or_default(),
+            category: string("category").filter(|v| !v.trim().is_empty()).map(|v| GraphExpr::parse(&v)),
+            values: string("values").map(|v| GraphExpr::parse_list(&v)).unwrap_or_default(),
+            series: string("series").filter(|v| !v.trim().is_empty()).map(|v| GraphExpr::parse(&v)),
+            category_axis: GraphAxis::from_values(values, "category"),
+            value_axis: GraphAxis::from_values(values, "values"),
+            series_axis: GraphAxis::from_values(values, "series"),
+            legend: number("legend").unwrap_or_default() as u32,
+            overlap_percent: number("overlappercent").unwrap_or_default() as i32,
+            spacing: number("spacing").unwrap_or(100.) as u32,
+            width: number("width"),
+            height: number("height")
+        })
+    }
+
+    /// 写入`graph`语法项的参数列表
+    pub fn write_to<'a>(&self, values: &mut HashMap<Key<'a>, Value<'a>>) {
+        let string = |v: &str| Value::DoubleQuotedString(escape(v).into());
+        values.insert("graphtype".into_key(), string(&self.graph_type.code().to_string()));
+        values.insert("title".into_key(), string(&self.title));
+        let expr = |v: &Option<GraphExpr>| v.as_ref().map(|v| v.to_string()).unwrap_or_default();
+        let values_expr = self.values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+        values.insert("category".into_key(), string(&expr(&self.category)));
+        values.insert("values".into_key(), string(&values_expr));
+        values.insert("series".into_key(), string(&expr(&self.series)));
+        values.insert("legend".into_key(), Value::Number(self.legend as f64));
+        values.insert("overlappercent".into_key(), string(&self.overlap_percent.to_string()));
+        values.insert("spacing".into_key(), string(&self.spacing.to_string()));
+        self.category_axis.write_to(values, "category");
+        self.value_axis.write_to(values, "values");
+        self.series_axis.write_to(values, "series");
+    }
+
+    /// 按分类/系列汇总`data`项中的数据
+    pub fn evaluate(&self, syn: &DWSyntax) -> Result<GraphData, String> {
+        let column = |expr: &GraphExpr| -> Result<usize, String> {
+            let name = match expr {
+                GraphExpr::Column(v) => v,
+                GraphExpr::Aggregate {
+                    column, ..
+                } => column,
+                GraphExpr::Other(v) => return Err(format!("unsupported graph expression: {v}"))
+            };
+            syn.table_column_index(name).ok_or_else(|| format!("unknown column: {name}"))
+        };
+        let category = match &self.category {
+            Some(v) => Some(column(v)?),
+            None => None
+        };
+        let series = match &self.series {
+            Some(v) => Some(column(v)?),
+            None => None
+        };
+        let mut values = Vec::with_capacity(self.values.len());
+        for expr in &self.values {
+            let func = match expr {
+                GraphExpr::Aggregate {
+                    func, ..
+                } => func.as_str(),
+                _ => "sum"
+            };
+            if !matches!(func, "sum" | "count" | "avg" | "max" | "min") {
+                return Err(format!("unsupported graph function: {func}"));
+            }
+            values.push((column(expr)?, func, expr.to_string()));
+        }
+
+        let mut data = GraphData::default();
+        //每个系列对应的值表达式
+        let mut series_value = vec![];
+        //[category][series] -> (sum, count, min, max)
+        let mut acc: Vec<Vec<(f64, usize, f64, f64)>> = vec![];
+        for row in syn.data_rows() {
+            let cat = category.map(|i| row[i].to_string()).unwrap_or_default();
+            let cat = match data.categories.iter().position(|v| *v == cat) {
+                Some(i) => i,
+                None => {
+                    data.categories.push(cat);
+                    acc.push(vec![]);
+                    data.categories.len() - 1
+                }
+            };
+            for (vi, (col, _, label)) in values.iter().enumerate() {
+                let name = match series {
+                    Some(i) if values.len() > 1 => format!("{} {label}", row[i]),
+                    Some(i) => row[i].to_string(),
+                    None => label.clone()
+                };
+                let s = match data.series.iter().position(|v| *v == name) {
+                    Some(i) => i,
+                    None => {
+                        data.series.push(name);
+                        series_value.push(vi);
+                        data.series.len() - 1
+                    }
+                };
+                let slots = &mut acc[cat];
+                if slots.len() <= s {
+                    slots.resize(s + 1, (0., 0, f64::MAX, f64::MIN));
+                }
+                let slot = &mut slots[s];
+                if let Some(v) = row[*col].as_number() {
+                    slot.0 += v;
+                    slot.1 += 1;
+                    slot.2 = slot.2.min(v);
+                    slot.3 = slot.3.max(v);
+                } else if !row[*col].is_null() && values[vi].1 == "count" {
+                    slot.1 += 1;
+                }
+            }
+        }
+        data.values = acc
+            .into_iter()
+            .map(|slots| {
+                (0..data.series.len())
+                    .map(|s| match slots.get(s) {
+                        Some((sum, count, min, max)) if *count > 0 => match values[series_value[s]].1 {
+                            "count" => *count as f64,
+                            "avg" => sum / *count as f64,
+                            "min" => *min,
+                            "max" => *max,
+                            _ => *sum
+                        },
+                        _ => 0.
+                    })
+                    .collect()
+            })
+            .collect();
+        //分类排序
+        if self.category_axis.sort == 1 || self.category_axis.sort == 2 {
+            let mut order: Vec<usize> = (0..data.categories.len()).collect();
+            order.sort_by(|&a, &b| {
+                let (a, b) = (&data.categories[a], &data.categories[b]);
+                match (a.parse::<f64>(), b.parse::<f64>()) {
+                    (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal),
+                    _ => a.cmp(b)
+                }
+            });
+            if self.category_axis.sort == 2 {
+                order.reverse();
+            }
+            data.categories = order.iter().map(|&i| data.categories[i].clone()).collect();
+            data.values = order.iter().map(|&i| data.values[i].clone()).collect();
+        }
+        Ok(data)
+    }
+
+    /// 使用`data`项中的数据生成SVG图表
+    ///
+    /// `width`/`height`为像素尺寸
+    pub fn render_svg(&self, syn: &DWSyntax, width: u32, height: u32) -> Result<String, String> {
+        let data = self.evaluate(syn)?;
+        Ok(SvgRenderer {
+            graph: self,
+            data: &data,
+            width: width as f64,
+            height: height as f64
+        }
+        .render())
+    }
+}
+
+/// 图表汇总数据
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GraphData {
+    /// 分类
+    pub categories: Vec<String>,
+    /// 系列
+    pub series: Vec<String>,
+    /// `values[category][series]`
+    pub values: Vec<Vec<f64>>
+}
+
+impl GraphData {
+    /// 值的范围
+    fn range(&self, stacked: bool) -> (f64, f64) {
+        let mut min = 0f64;
+        let mut max = 0f64;
+        for row in &self.values {
+            if stacked {
+                max = max.max(row.iter().filter(|v| **v > 0.).sum());
+                min = min.min(row.iter().filter(|v| **v < 0.).sum());
+            } else {
+                for v in row {
+                    max = max.max(*v);
+                    min = min.min(*v);
+                }
+            }
+        }
+        (min, max)
+    }
+}
+
+impl<'a> Item<'a> {
+    /// 获取图表控件
+    pub fn graph(&self) -> Option<GraphControl> { GraphControl::from_item(self) }
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 获取所有图表控件
+    pub fn graphs(&self) -> Vec<GraphControl> {
+        self.items.iter().filter_map(GraphControl::from_item).collect()
+    }
+}
+
+/// SVG绘制
+struct SvgRenderer<'g> {
+    graph: &'g GraphControl,
+    data: &'g GraphData,
+    width: f64,
+    height: f64
+}
+
+impl<'g> SvgRenderer<'g> {
+    fn render(&self) -> String {
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">"#,
+            w = self.width,
+            h = self.height
+        );
+        let _ = write!(svg, r#"<rect width="{}" height="{}" fill="white"/>"#, self.width, self.height);
+        let top = if self.graph.title.is_empty() {
+            10.
+        } else {
+            let _ = write!(
+                svg,
+                r#"<text x="{}" y="20" text-anchor="middle" font-size="14" font-weight="bold">{}</text>"#,
+                self.width / 2.,
+                xml_escape(&self.graph.title)
+            );
+            30.
+        };
+        let legend_width = if self.graph.legend != 0 { 120. } else { 0. };
+        let pie = self.graph.graph_type.is_pie();
+        let plot = Rect {
+            x: if pie { 10. } else { 60. },
+            y: top,
+            width: (self.width - legend_width - if pie { 20. } else { 70. }).max(10.),
+            height: (self.height - top - if pie { 10. } else { 40. }).max(10.)
+        };
+        let ty = self.graph.graph_type;
+        if ty.is_pie() {
+            self.render_pie(&mut svg, &plot);
+        } else if ty == GraphType::Scatter {
+            self.render_scatter(&mut svg, &plot);
+        } else {
+            self.render_xy(&mut svg, &plot);
+        }
+        if legend_width > 0. {
+            let names: &[String] = if ty.is_pie() { &self.data.categories } else { &self.data.series };
+            let x = self.width - legend_width + 10.;
+            for (i, name) in names.iter().enumerate() {
+                let y = top + 10. + i as f64 * 16.;
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{x}" y="{}" width="10" height="10" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+                    y - 9.,
+                    color(i),
+                    x + 14.,
+                    y,
+                    xml_escape(name)
+                );
+            }
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// 柱形/条形/折线/面积图
+    fn render_xy(&self, svg: &mut String, plot: &Rect<f64>) {
+        let ty = self.graph.graph_type;
+        let horizontal = ty.is_bar();
+        let (min, max) = self.value_range(ty.is_stacked());
+        let cats = self.data.categories.len().max(1) as f64;
+        let series = self.data.series.len().max(1);
+        //值->坐标
+        let scale = |v: f64| {
+            let ratio = (v - min) / (max - min);
+            if horizontal {
+                plot.x + ratio * plot.width
+            } else {
+                plot.y + plot.height - ratio * plot.height
+            }
+        };
+        //坐标轴和刻度
+        let _ = write!(
+            svg,
+            r#"<path d="M{} {}V{}H{}" stroke="black" fill="none"/>"#,
+            plot.x,
+            plot.y,
+            plot.y + plot.height,
+            plot.x + plot.width
+        );
+        let step = nice_step(max - min, self.graph.value_axis.major_divisions);
+        let mut tick = (min / step).ceil() * step;
+        while tick <= max + step * 1e-9 {
+            let p = scale(tick);
+            let label = general_number((tick / step).round() * step);
+            if horizontal {
+                let _ = write!(
+                    svg,
+                    r##"<line x1="{p}" y1="{}" x2="{p}" y2="{}" stroke="#ddd"/><text x="{p}" y="{}" text-anchor="middle">{label}</text>"##,
+                    plot.y,
+                    plot.y + plot.height,
+                    plot.y + plot.height + 14.
+                );
+            } else {
+                let _ = write!(
+                    svg,
+                    r##"<line x1="{}" y1="{p}" x2="{}" y2="{p}" stroke="#ddd"/><text x="{}" y="{}" text-anchor="end">{label}</text>"##,
+                    plot.x,
+                    plot.x + plot.width,
+                    plot.x - 4.,
+                    p + 4.
+                );
+            }
+            tick += step;
+        }
+        let band = if horizontal { plot.height / cats } else { plot.width / cats };
+        for (ci, cat) in self.data.categories.iter().enumerate() {
+            let center = if horizontal {
+                plot.y + band * (ci as f64 + 0.5)
+            } else {
+                plot.x + band * (ci as f64 + 0.5)
+            };
+            if horizontal {
+                let _ = write!(
+                    svg,
+                    r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#,
+                    plot.x - 4.,
+                    center + 4.,
+                    xml_escape(cat)
+                );
+            } else {
+                let _ = write!(
+                    svg,
+                    r#"<text x="{center}" y="{}" text-anchor="middle">{}</text>"#,
+                    plot.y + plot.height + 14.,
+                    xml_escape(cat)
+                );
+            }
+        }
+        if ty.is_line() || ty.is_area() {
+            for s in 0..series {
+                let mut points = vec![];
+                for ci in 0..self.data.categories.len() {
+                    let v = self.data.values[ci].get(s).copied().unwrap_or(0.);
+                    let c = plot.x + band * (ci as f64 + 0.5);
+                    points.push(format!("{c:.2},{:.2}", scale(v)));
+                }
+                if points.is_empty() {
+                    continue;
+                }
+                if ty.is_area() {
+                    let base = scale(min.max(0.));
+                    let first = plot.x + band * 0.5;
+                    let last = plot.x + band * (self.data.categories.len() as f64 - 0.5);
+                    let _ = write!(
+                        svg,
+                        r#"<polygon points="{first:.2},{base:.2} {} {last:.2},{base:.2}" fill="{}" fill-opacity="0.6"/>"#,
+                        points.join(" "),
+                        color(s)
+                    );
+                } else {
+                    let _ = write!(
+                        svg,
+                        r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+                        points.join(" "),
+                        color(s)
+                    );
+                }
+            }
+            return;
+        }
+        //柱形/条形
+        let spacing = self.graph.spacing as f64 / 100.;
+        let group_width = band / (1. + spacing);
+        let bar_width = if ty.is_stacked() {
+            group_width
+        } else {
+            let overlap = (self.graph.overlap_percent as f64 / 100.).clamp(0., 0.99);
+            group_width / (series as f64 - (series as f64 - 1.) * overlap)
+        };
+        let step_width = if ty.is_stacked() {
+            0.
+        } else {
+            bar_width * (1. - (self.graph.overlap_percent as f64 / 100.).clamp(0., 0.99))
+        };
+        let zero = scale(0f64.clamp(min, max));
+        for (ci, row) in self.data.values.iter().enumerate() {
+            let start = band * ci as f64 + (band - group_width) / 2.;
+            let (mut pos, mut neg) = (0., 0.);
+            for (s, v) in row.iter().enumerate() {
+                let (from, to) = if ty.is_stacked() {
+                    let base = if *v >= 0. { &mut pos } else { &mut neg };
+                    let from = *base;
+                    *base += v;
+                    (scale(from), scale(*base))
+                } else {
+                    (zero, scale(*v))
+                };
+                let offset = start + step_width * s as f64;
+                let (x, y, w, h) = if horizontal {
+                    (from.min(to), plot.y + offset, (to - from).abs(), bar_width)
+                } else {
+                    (plot.x + offset, from.min(to), bar_width, (to - from).abs())
+                };
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{x:.2}" y="{y:.2}" width="{w:.2}" height="{h:.2}" fill="{}"/>"#,
+                    color(s)
+                );
+            }
+        }
+    }
+
+    /// 饼图(汇总所有系列)
+    fn render_pie(&self, svg: &mut String, plot: &Rect<f64>) {
+        let totals: Vec<f64> =
+            self.data.values.iter().map(|row| row.iter().filter(|v| **v > 0.).sum()).collect();
+        let total: f64 = totals.iter().sum();
+        if total <= 0. {
+            return;
+        }
+        let r = plot.width.min(plot.height) / 2.;
+        let (cx, cy) = (plot.x + plot.width / 2., plot.y + plot.height / 2.);
+        let mut angle = -std::f64::consts::FRAC_PI_2;
+        for (i, v) in totals.iter().enumerate() {
+            let sweep = v / total * std::f64::consts::TAU;
+            if sweep >= std::f64::consts::TAU - 1e-9 {
+                let _ = write!(svg, r#"<circle cx="{cx:.2}" cy="{cy:.2}" r="{r:.2}" fill="{}"/>"#, color(i));
+                break;
+            }
+            let (x1, y1) = (cx + r * angle.cos(), cy + r * angle.sin());
+            angle += sweep;
+            let (x2, y2) = (cx + r * angle.cos(), cy + r * angle.sin());
+            let large = if sweep > std::f64::consts::PI { 1 } else { 0 };
+            let _ = write!(
+                svg,
+                r#"<path d="M{cx:.2} {cy:.2}L{x1:.2} {y1:.2}A{r:.2} {r:.2} 0 {large} 1 {x2:.2} {y2:.2}Z" fill="{}"/>"#,
+                color(i)
+            );
+        }
+    }
+
+    /// 散点图(分类为X轴数值)
+    fn render_scatter(&self, svg: &mut String, plot: &Rect<f64>) {
+        let xs: Vec<f64> = self.data.categories.iter().map(|v| v.parse().unwrap_or(0.)).collect();
+        let (xmin, xmax) = xs.iter().fold((f64::MAX, f64::MIN), |(a, b), v| (a.min(*v), b.max(*v)));
+        let (xmin, xmax) = if xmin < xmax { (xmin, xmax) } else { (xmin - 1., xmin + 1.) };
+        let (ymin, ymax) = self.value_range(false);
+        let _ = write!(
+            svg,
+            r#"<path d="M{} {}V{}H{}" stroke="black" fill="none"/>"#,
+            plot.x,
+            plot.y,
+            plot.y + plot.height,
+            plot.x + plot.width
+        );
+        for (ci, row) in self.data.values.iter().enumerate() {
+            let x = plot.x + (xs[ci] - xmin) / (xmax - xmin) * plot.width;
+            for (s, v) in row.iter().enumerate() {
+                let y = plot.y + plot.height - (v - ymin) / (ymax - ymin) * plot.height;
+                let _ = write!(svg, r#"<circle cx="{x:.2}" cy="{y:.2}" r="3" fill="{}"/>"#, color(s));
+            }
+        }
+    }
+
+    /// 值坐标轴范围
+    fn value_range(&self, stacked: bool) -> (f64, f64) {
+        let axis = &self.graph.value_axis;
+        let (min, max) = self.data.range(stacked);
+        let min = if axis.auto_scale { min } else { axis.minimum.unwrap_or(min) };
+        let max = if axis.auto_scale { max } else { axis.maximum.unwrap_or(max) };
+        if max > min {
+            let step = nice_step(max - min, axis.major_divisions);
+            if axis.auto_scale {
+                return ((min / step).floor() * step, (max / step).ceil() * step);
+            }
+            (min, max)
+        } else {
+            (min, min + 1.)
+        }
+    }
+}
+
+/// 刻度间隔(1/2/5 * 10^n)
+fn nice_step(range: f64, divisions: u32) -> f64 {
+    let divisions = if divisions == 0 { 5. } else { divisions as f64 };
+    let raw = (range / divisions).max(f64::MIN_POSITIVE);
+    let base = 10f64.powf(raw.log10().floor());
+    let step = [1., 2., 5., 10.].iter().map(|v| v * base).find(|v| *v >= raw).unwrap_or(base * 10.);
+    if step > 0. {
+        step
+    } else {
+        1.
+    }
+}
+
+fn color(index: usize) -> &'static str { PALETTE[index % PALETTE.len()] }
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+table(column=(type=char(10) name=region dbname="region") column=(type=char(10) name=year dbname="year") column=(type=long name=amount dbname="amount"))
+graph(band=background name=gr_1 graphtype="10" title="Sales~"" category="region" values="sum(amount for graph)" series="year" legend=2 category.sort=1 category.label="(None)" values.label="Amount" values.autoscale=1 overlappercent="0" spacing="100" width="800" height="400")
+data("north", "2023", 10, "south", "2023", 5, "north", "2024", 20, "east", "2023", 7, "north", "2023", 1, )
+"#;
+
+    #[test]
+    fn test_graph() {
+        let dw = DWSyntax::parse(SYNTAX).unwrap();
+        let graphs = dw.graphs();
+        assert_eq!(graphs.len(), 1);
+        let graph = &graphs[0];
+        assert_eq!(graph.name.as_deref(), Some("gr_1"));
+        assert_eq!(graph.band, Some(Band::Background));
+        assert_eq!(graph.graph_type, GraphType::ColStacked);
+        assert!(graph.graph_type.is_stacked());
+        assert_eq!(graph.title, "Sales\"");
+        assert_eq!(graph.category, Some(GraphExpr::Column("region".to_owned())));
+        assert_eq!(graph.values, vec![GraphExpr::Aggregate {
+            func: "sum".to_owned(),
+            column: "amount".to_owned()
+        }]);
+        assert_eq!(graph.series, Some(GraphExpr::Column("year".to_owned())));
+        assert_eq!(graph.category_axis.label, None);
+        assert_eq!(graph.value_axis.label.as_deref(), Some("Amount"));
+        assert_eq!(graph.legend, 2);
+
+        let data = graph.evaluate(&dw).unwrap();
+        assert_eq!(data.categories, vec!["east", "north", "south"]);
+        assert_eq!(data.series, vec!["2023", "2024"]);
+        assert_eq!(data.values, vec![vec![7., 0.], vec![11., 20.], vec![5., 0.]]);
+
+        let svg = graph.render_svg(&dw, 400, 300).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("Sales&quot;"));
+        assert_eq!(svg.matches("<rect x=").count(), 3 * 2 + 2);
+
+        let mut item = dw.items[0].to_owned();
+        let mut graph = graph.clone();
+        graph.graph_type = GraphType::Pie;
+        graph.values = GraphExpr::parse_list("count(amount for graph), avg(amount)");
+        graph.write_to(&mut item.values);
+        let graph = item.graph().unwrap();
+        assert_eq!(graph.graph_type, GraphType::Pie);
+        assert_eq!(graph.values.len(), 2);
+        assert!(graph.render_svg(&dw, 400, 300).unwrap().contains("<path d=\"M"));
+        assert_eq!(GraphExpr::parse("col1 + col2"), GraphExpr::Other("col1 + col2".to_owned()));
+    }
+}
@@ -0,0 +1,418 @@
+use crate::{ast::*, band::Band, prelude::*};
+
+impl<'a> Item<'a> {
+    /// 是否为分组`group`语法项
+    pub fn is_group(&self) -> bool { self.kind == "group" }
+
+    /// 获取分组依据的字段列表
+    ///
+    /// # Syntax
+    ///
+    /// ```txt
+    /// group(level=1 by=("col1", "col2"))
+    /// ```
+    pub fn group_by(&self) -> Vec<&str> {
+        match self.values.get(&"by".into_key()) {
+            Some(Value::List(list)) => list.iter().filter_map(|v| v.as_str()).collect(),
+            Some(v) => v.as_str().map(|v| vec![v]).unwrap_or_default(),
+            None => vec![]
+        }
+    }
+
+    /// 获取分组头高度
+    pub fn group_header_height(&self) -> Option<f64> {
+        self.values.get(&"header.height".into_key()).and_then(|v| v.to_number())
+    }
+
+    /// 获取分组尾高度
+    pub fn group_trailer_height(&self) -> Option<f64> {
+        self.values.get(&"trailer.height".into_key()).and_then(|v| v.to_number())
+    }
+
+    /// 设置分组级别
+    fn set_group_level(&mut self, level: u32) {
+        self.level = Some(level);
+        self.values.insert("level".into_key(), Value::Number(level as f64));
+    }
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 按级别顺序获取所有分组`group`语法项
+    pub fn groups(&self) -> Vec<&Item<'a>> {
+        let mut groups: Vec<_> = self.items.iter().filter(|item| item.is_group()).collect();
+        groups.sort_by_key(|item| item.level.unwrap_or(u32::MAX));
+        groups
+    }
+
+    /// 获取指定级别的分组`group`语法项
+    pub fn group(&self, level: u32) -> Option<&Item<'a>> {
+        self.items.iter().find(|item| item.is_group() && item.level == Some(level))
+    }
+
+    /// 获取指定级别的分组`group`语法项
+    pub fn group_mut(&mut self, level: u32) -> Option<&mut Item<'a>> {
+        self.items.iter_mut().find(|item| item.is_group() && item.level == Some(level))
+    }
+
+    /// 在指定级别插入分组
+    ///
+    /// 原有级别大于等于`level`的分组依次后移,
+    /// 并同步刷新控件的`band=header.N`/`band=trailer.N`以及表达式中的`for group N`引用
+    pub fn insert_group<I, S>(&mut self, level: u32, by: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>
+    {
+        let count = self.groups().len() as u32;
+        if level == 0 || level > count + 1 {
+            return Err(format!("invalid group level: {level}"));
+        }
+        let by = self.group_by_value(by)?;
+        self.remap_groups(|v| if v >= level { v + 1 } else { v });
+        let mut values = HashMap::with_capacity(4);
+        values.insert("level".into_key(), Value::Number(level as f64));
+        values.insert("header.height".into_key(), Value::Number(0.));
+        values.insert("trailer.height".into_key(), Value::Number(0.));
+        values.insert("by".into_key(), by);
+        let group = Item {
+            kind: "group".into_key(),
+            name: None,
+            id: None,
+            level: Some(level),
+            values
+        };
+        //保持分组在普通语法项中的位置(紧随前一级分组)
+        let index = self
+            .items
+            .iter()
+            .rposition(|item| item.is_group() && matches!(item.level, Some(v) if v < level))
+            .map(|index| index + 1)
+            .or_else(|| self.items.iter().position(|item| item.is_group()))
+            .unwrap_or(0);
+        self.items.insert(index, group);
+        self.refresh_index();
+        Ok(())
+    }
+
+    /// 删除指定级别的分组
+    ///
+    /// 分组带区内的控件移动到上一级分组(第1级分组则移动到`header`/`summary`带区),
+    /// 引用该分组的`for group N`表达式同样指向上一级分组(第1级分组则为`for all`)
+    pub fn remove_group(&mut self, level: u32) -> Option<Item<'a>> {
+        let index = self.items.iter().position(|item| item.is_group() && item.level == Some(level))?;
+        let group = self.items.remove(index);
+        self.remap_groups(|v| if v >= level { v - 1 } else { v });
+        self.refresh_index();
+        Some(group)
+    }
+
+    /// 修改分组依据的字段列表
+    pub fn set_group_by<I, S>(&mut self, level: u32, by: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>
+    {
+        let by = self.group_by_value(by)?;
+        let group = self.group_mut(level).ok_or_else(|| format!("invalid group level: {level}"))?;
+        group.values.insert("by".into_key(), by);
+        Ok(())
+    }
+
+    /// 生成并检查分组依据的字段列表
+    fn group_by_value<I, S>(&self, by: I) -> Result<Value<'a>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'a, str>>
+    {
+        let by: Vec<Cow<'a, str>> = by.into_iter().map(Into::into).collect();
+        if !self.table.columns.is_empty() {
+            for name in &by {
+                if self.table_column_position(name).is_none() {
+                    return Err(format!("unknown column: {name}"));
+                }
+            }
+        }
+        Ok(Value::List(by.into_iter().map(Value::DoubleQuotedString).collect()))
+    }
+
+    /// 重新映射分组级别
+    ///
+    /// `remap`返回`0`表示引用移动到分组以外的带区
+    fn remap_groups(&mut self, remap: impl Fn(u32) -> u32) {
+        for item in &mut self.items {
+            if item.is_group() {
+                if let Some(level) = item.level {
+                    item.set_group_level(remap(level));
+                }
+                continue;
+            }
+            match item.band() {
+                Some(Band::GroupHeader(level)) if remap(level) != level => {
+                    item.set_band(match remap(level) {
+                        0 => Band::Header,
+                        level => Band::GroupHeader(level)
+                    });
+                },
+                Some(Band::GroupTrailer(level)) if remap(level) != level => {
+                    item.set_band(match remap(level) {
+                        0 => Band::Summary,
+                        level => Band::GroupTrailer(level)
+                    });
+                },
+                _ => {}
+            }
+            for (key, value) in item.values.iter_mut() {
+                remap_value_group_refs(key, value, &remap);
+            }
+        }
+    }
+}
+
+/// 刷新参数值中的`for group N`引用
+///
+/// 仅处理表达式:`expression`/`*.expression`参数的值,以及其它参数`默认值~t表达式`形式的属性表达式,
+/// 普通文本(如`text`)不做修改
+fn remap_value_group_refs(key: &str, value: &mut Value, remap: &impl Fn(u32) -> u32) {
+    let key = key.to_ascii_lowercase();
+    let is_expression = key == "expression" || key.ends_with(".expression");
+    match value {
+        Value::DoubleQuotedString(v) | Value::SingleQuotedString(v) => {
+            let start = match (is_expression, v.find("~t")) {
+                (true, _) => 0,
+                (false, Some(n)) => n + 2,
+                (false, None) => return
+            };
+            if let Some(new) = remap_group_refs(&v[start..], remap) {
+                *v = Cow::Owned(format!("{}{new}", &v[..start]));
+            }
+        },
+        Value::Map(map) => {
+            for (key, value) in map.iter_mut() {
+                remap_value_group_refs(key, value, remap);
+            }
+        },
+        Value::List(list) => {
+            for value in list {
+                remap_value_group_refs(&key, value, remap);
+            }
+        },
+        _ => {}
+    }
+}
+
+/// 标记表达式中字符串常量所在的字节
+///
+/// `expr`为语法中的原始值(`~X`为一个转义字符),常量以`"`或`'`包围,常量内的`~`转义下一个字符
+fn literal_mask(expr: &str) -> Vec<bool> {
+    let bytes = expr.as_bytes();
+    let mut mask = vec![false; bytes.len()];
+    let mut quote = None;
+    let mut escaped = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let len = if bytes[i] == b'~' && i + 1 < bytes.len() { 2 } else { 1 };
+        let c = bytes[i + len - 1];
+        let inside = quote.is_some();
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == b'~' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {},
+            None if c == b'"' || c == b'\'' => quote = Some(c),
+            None => {}
+        }
+        mask[i..i + len].fill(inside || quote.is_some());
+        i += len;
+    }
+    mask
+}
+
+/// 刷新表达式中的`for group N`引用
+///
+/// 未发生变化时返回`None`
+fn remap_group_refs(expr: &str, remap: &impl Fn(u32) -> u32) -> Option<String> {
+    fn is_ident(c: u8) -> bool { c.is_ascii_alphanumeric() || c == b'_' }
+    fn skip_ws(bytes: &[u8], mut i: usize) -> usize {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    }
+    let lower = expr.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let literal = literal_mask(expr);
+    let mut rv = String::new();
+    let mut last = 0;
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find("for") {
+        let start = pos + offset;
+        pos = start + 3;
+        if literal[start] || (start > 0 && is_ident(bytes[start - 1])) {
+            continue;
+        }
+        let i = skip_ws(bytes, start + 3);
+        if i == start + 3 || !lower[i..].starts_with("group") {
+            continue;
+        }
+        let num_start = skip_ws(bytes, i + 5);
+        if num_start == i + 5 {
+            continue;
+        }
+        let mut num_end = num_start;
+        while num_end < bytes.len() && bytes[num_end].is_ascii_digit() {
+            num_end += 1;
+        }
+        if num_end == num_start || (num_end < bytes.len() && is_ident(bytes[num_end])) {
+            continue;
+        }
+        pos = num_end;
+        let level: u32 = match expr[num_start..num_end].parse() {
+            Ok(v) => v,
+            Err(_) => continue
+        };
+        let new_level = remap(level);
+        if new_level == level {
+            continue;
+        }
+        rv.push_str(&expr[last..start]);
+        if new_level == 0 {
+            rv.push_str(&expr[start..start + 3]);
+            rv.push_str(" all");
+        } else {
+            rv.push_str(&expr[start..num_start]);
+            rv.push_str(&new_level.to_string());
+        }
+        last = num_end;
+    }
+    if last == 0 {
+        return None;
+    }
+    rv.push_str(&expr[last..]);
+    Some(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+table(column=(type=char(10) name=col1 dbname="col1") column=(type=char(10) name=col2 dbname="col2") column=(type=long name=col3 dbname="col3"))
+group(level=2 header.height=10 trailer.height=20 by=("col2"))
+group(level=1 header.height=30 trailer.height=40 by=("col1"))
+compute(band=trailer.2 name=sum_2 expression="sum(col3 for group 2)")
+compute(band=trailer.1 name=sum_1 expression="sum(col3 For Group 1) + count(col3 for group 2)")
+text(band=header.1 name=t_1 text="group1")
+column(band=detail id=3 name=col3)
+"#;
+
+    fn expression<'b>(dw: &'b DWSyntax, name: &str) -> (Option<Band>, &'b str) {
+        let item = dw.item_by_name(name).unwrap();
+        (item.band(), item.values.get(&"expression".into_key()).and_then(|v| v.as_str()).unwrap())
+    }
+
+    #[test]
+    fn test_groups() {
+        let dw = DWSyntax::parse(SYNTAX).unwrap();
+        let groups = dw.groups();
+        assert_eq!(groups.iter().map(|v| v.level).collect::<Vec<_>>(), vec![Some(1), Some(2)]);
+        assert_eq!(groups[0].group_by(), vec!["col1"]);
+        assert_eq!(groups[1].group_header_height(), Some(10.));
+        assert_eq!(groups[1].group_trailer_height(), Some(20.));
+        assert!(dw.group(3).is_none());
+    }
+
+    #[test]
+    fn test_insert_group() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.insert_group(1, ["col3"]).unwrap();
+        assert_eq!(dw.groups().iter().map(|v| v.group_by()).collect::<Vec<_>>(), vec![
+            vec!["col3"],
+            vec!["col1"],
+            vec!["col2"]
+        ]);
+        assert_eq!(
+            expression(&dw, "sum_1"),
+            (Some(Band::GroupTrailer(2)), "sum(col3 For Group 2) + count(col3 for group 3)")
+        );
+        assert_eq!(expression(&dw, "sum_2"), (Some(Band::GroupTrailer(3)), "sum(col3 for group 3)"));
+        //追加到最后一级
+        dw.insert_group(4, ["col2"]).unwrap();
+        assert_eq!(dw.group(4).unwrap().group_by(), vec!["col2"]);
+        assert_eq!(expression(&dw, "sum_2"), (Some(Band::GroupTrailer(3)), "sum(col3 for group 3)"));
+    }
+
+    #[test]
+    fn test_insert_group_error() {
+        //失败时不修改语法
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let syntax = dw.to_string();
+        assert_eq!(dw.insert_group(0, ["col1"]).unwrap_err(), "invalid group level: 0");
+        assert_eq!(dw.insert_group(4, ["col1"]).unwrap_err(), "invalid group level: 4");
+        assert_eq!(dw.insert_group(1, ["col1", "no_col"]).unwrap_err(), "unknown column: no_col");
+        assert_eq!(dw.to_string(), syntax);
+    }
+
+    #[test]
+    fn test_remove_group() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let group = dw.remove_group(1).unwrap();
+        assert_eq!(group.group_by(), vec!["col1"]);
+        assert_eq!(dw.group(1).unwrap().group_by(), vec!["col2"]);
+        assert_eq!(dw.item_by_name("t_1").unwrap().band(), Some(Band::Header));
+        assert_eq!(
+            expression(&dw, "sum_1"),
+            (Some(Band::Summary), "sum(col3 For all) + count(col3 for group 1)")
+        );
+        assert_eq!(expression(&dw, "sum_2"), (Some(Band::GroupTrailer(1)), "sum(col3 for group 1)"));
+
+        //删除中间级别时引用指向上一级分组
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.insert_group(1, ["col3"]).unwrap();
+        dw.remove_group(2).unwrap();
+        assert_eq!(dw.item_by_name("t_1").unwrap().band(), Some(Band::GroupHeader(1)));
+        assert_eq!(
+            expression(&dw, "sum_1"),
+            (Some(Band::GroupTrailer(1)), "sum(col3 For Group 1) + count(col3 for group 2)")
+        );
+    }
+
+    #[test]
+    fn test_remove_group_missing() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let syntax = dw.to_string();
+        assert!(dw.remove_group(0).is_none());
+        assert!(dw.remove_group(3).is_none());
+        assert_eq!(dw.to_string(), syntax);
+    }
+
+    #[test]
+    fn test_set_group_by() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.set_group_by(1, ["col1", "col2"]).unwrap();
+        assert_eq!(dw.group(1).unwrap().group_by(), vec!["col1", "col2"]);
+        let syntax = dw.to_string();
+        assert_eq!(dw.set_group_by(3, ["col1"]).unwrap_err(), "invalid group level: 3");
+        assert_eq!(dw.set_group_by(1, ["no_col"]).unwrap_err(), "unknown column: no_col");
+        assert_eq!(dw.to_string(), syntax);
+    }
+
+    #[test]
+    fn test_group_refs_in_text() {
+        let mut dw = DWSyntax::parse(concat!(
+            "release 19;\r\ntable(column=(type=long name=col1 dbname=\"col1\"))\r\n",
+            "group(level=1 header.height=30 trailer.height=40 by=(\"col1\"))\r\n",
+            "text(band=trailer.1 name=t_1 text=\"Totals for group 1\" color=\"0~tif(count(col1 for group 1) > 1, 255, 0)\")\r\n",
+            "compute(band=trailer.1 name=c_1 expression=\"'for group 1: ' + string(sum(col1 for group 1)) + ~\"for group 1~\"\")"
+        ))
+        .unwrap();
+        dw.insert_group(1, ["col1"]).unwrap();
+        let value = |name: &str, key: &str| {
+            let item = dw.item_by_name(name).unwrap();
+            item.values.get(&key.into_key()).and_then(|v| v.as_str()).unwrap().to_owned()
+        };
+        assert_eq!(value("t_1", "text"), "Totals for group 1");
+        assert_eq!(value("t_1", "color"), "0~tif(count(col1 for group 2) > 1, 255, 0)");
+        let expected = "'for group 1: ' + string(sum(col1 for group 2)) + ~\"for group 1~\"";
+        assert_eq!(value("c_1", "expression"), expected);
+    }
+}
@@ -0,0 +1,693 @@
+use crate::{ast::*, prelude::*};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// 编辑历史
+///
+/// 包装`DWSyntax`,将每次修改记录为可逆的操作,支持撤销/重做及将多个操作合并为一个编辑动作
+///
+/// 启用`serde_support`时可序列化(包括语法及撤销/重做栈,不包括未结束的分组)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EditHistory<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    syntax: DWSyntax<'a>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    undo: Vec<EditGroup<'a>>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    redo: Vec<EditGroup<'a>>,
+    /// 未结束的分组
+    #[cfg_attr(feature = "serde", serde(skip))]
+    group: Option<EditGroup<'a>>,
+    /// 分组嵌套层数
+    #[cfg_attr(feature = "serde", serde(skip))]
+    depth: usize
+}
+
+/// 编辑动作(撤销/重做的单位)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EditGroup<'a> {
+    /// 动作名称
+    pub label: Option<String>,
+    /// 按执行顺序排列的操作
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub ops: Vec<EditOp<'a>>
+}
+
+/// 参数列表所属的语法项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EditTarget {
+    DataWindow,
+    Header,
+    Summary,
+    Footer,
+    Detail,
+    Table,
+    /// `table`字段(索引)
+    TableColumn(usize),
+    /// 普通语法项(索引)
+    Item(usize)
+}
+
+/// 可逆的编辑操作
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EditOp<'a> {
+    /// 修改参数值,`old`为`None`时为新增参数
+    SetValue {
+        target: EditTarget,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        key: Key<'a>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        old: Option<Value<'a>>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        new: Value<'a>
+    },
+    /// 替换整个参数列表(删除参数或调整顺序时使用)
+    SetValues {
+        target: EditTarget,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        old: HashMap<Key<'a>, Value<'a>>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        new: HashMap<Key<'a>, Value<'a>>
+    },
+    /// 插入语法项
+    InsertItem {
+        index: usize,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        item: Item<'a>
+    },
+    /// 删除语法项
+    RemoveItem {
+        index: usize,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        item: Item<'a>
+    },
+    /// 替换语法项(类型/名称/ID/级别发生变化)
+    ReplaceItem {
+        index: usize,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        old: Item<'a>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        new: Item<'a>
+    },
+    /// 替换`table`字段列表
+    SetTableColumns {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        old: Vec<ItemTableColumn<'a>>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        new: Vec<ItemTableColumn<'a>>
+    },
+    /// 替换`data`项
+    SetData {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        old: Vec<Value<'a>>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        new: Vec<Value<'a>>
+    },
+    /// 修改对象名
+    SetName {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        old: Option<Cow<'a, str>>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        new: Option<Cow<'a, str>>
+    },
+    /// 修改备注
+    SetComment {
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        old: Option<Cow<'a, str>>,
+        #[cfg_attr(feature = "serde", serde(borrow))]
+        new: Option<Cow<'a, str>>
+    },
+    /// 修改语法版本
+    SetVersion {
+        old: f64,
+        new: f64
+    }
+}
+
+impl<'a> EditHistory<'a> {
+    pub fn new(syntax: DWSyntax<'a>) -> Self {
+        EditHistory {
+            syntax,
+            undo: vec![],
+            redo: vec![],
+            group: None,
+            depth: 0
+        }
+    }
+
+    /// 当前语法
+    pub fn syntax(&self) -> &DWSyntax<'a> { &self.syntax }
+
+    /// 取出语法并丢弃历史
+    pub fn into_syntax(self) -> DWSyntax<'a> { self.syntax }
+
+    /// 撤销栈(最近的动作在末尾)
+    pub fn undo_stack(&self) -> &[EditGroup<'a>] { &self.undo }
+
+    /// 重做栈(最近撤销的动作在末尾)
+    pub fn redo_stack(&self) -> &[EditGroup<'a>] { &self.redo }
+
+    pub fn can_undo(&self) -> bool { !self.undo.is_empty() || self.group.is_some() }
+
+    pub fn can_redo(&self) -> bool { !self.redo.is_empty() }
+
+    /// 通过闭包修改语法
+    ///
+    /// 比较修改前后的语法生成操作记录,适用于`set_presentation_style`等类型化接口
+    ///
+    /// 每次调用都会复制整个语法并逐项比较(O(n)),大语法的少量修改应使用`modify`、`insert_item`、
+    /// `remove_item`,这些接口直接记录执行的操作,不复制语法
+    pub fn edit<R>(&mut self, f: impl FnOnce(&mut DWSyntax<'a>) -> R) -> R {
+        let old = self.syntax.to_owned();
+        let rv = f(&mut self.syntax);
+        let mut ops = vec![];
+        diff(&old, &self.syntax, &mut ops);
+        self.syntax.refresh_index();
+        self.record(ops);
+        rv
+    }
+
+    /// 修改语法项的参数值
+    ///
+    /// 兼容`DataWindow::Modify`参数,失败时不产生记录
+    #[cfg(feature = "query")]
+    pub fn modify(&mut self, modifier: &str) -> Result<(), crate::parser::query::ModifyError> {
+        use crate::parser::query::{ModifyError, modify_ops};
+        let ops =
+            modify_ops(&mut self.syntax, modifier, None).map_err(|e| ModifyError::from_error(modifier, e))?;
+        self.record(ops);
+        Ok(())
+    }
+
+    /// 插入语法项
+    ///
+    /// `index`超出范围时追加到末尾
+    pub fn insert_item(&mut self, index: usize, item: Item<'a>) {
+        let index = index.min(self.syntax.items.len());
+        let op = EditOp::InsertItem {
+            index,
+            item
+        };
+        //SAFETY: 索引已限制在范围内
+        op.apply(&mut self.syntax, false).unwrap();
+        self.syntax.refresh_index();
+        self.record(vec![op]);
+    }
+
+    /// 删除语法项
+    pub fn remove_item(&mut self, index: usize) -> Option<Item<'a>> {
+        if index >= self.syntax.items.len() {
+            return None;
+        }
+        let item = self.syntax.items.remove(index);
+        self.syntax.refresh_index();
+        self.record(vec![EditOp::RemoveItem {
+            index,
+            item: item.to_owned()
+        }]);
+        Some(item)
+    }
+
+    /// 开始分组
+    ///
+    /// 到对应的`end_group`为止的所有操作作为一个动作撤销/重做,支持嵌套(以最外层的名称为准)
+    pub fn begin_group(&mut self, label: impl Into<String>) {
+        if self.depth == 0 {
+            self.group = Some(EditGroup {
+                label: Some(label.into()),
+                ops: vec![]
+            });
+        }
+        self.depth += 1;
+    }
+
+    /// 结束分组
+    pub fn end_group(&mut self) {
+        if self.depth == 0 {
+            return;
+        }
+        self.depth -= 1;
+        if self.depth == 0 {
+            if let Some(group) = self.group.take().filter(|v| !v.ops.is_empty()) {
+                self.undo.push(group);
+            }
+        }
+    }
+
+    /// 在分组中执行闭包
+    pub fn group<R>(&mut self, label: impl Into<String>, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.begin_group(label);
+        let rv = f(self);
+        self.end_group();
+        rv
+    }
+
+    /// 撤销最近的动作
+    ///
+    /// 存在未结束的分组时先结束分组,没有可撤销的动作时返回`false`
+    ///
+    /// 操作记录与语法不匹配(如反序列化的历史或直接修改语法后)时返回错误,语法及撤销栈保持不变
+    pub fn undo(&mut self) -> Result<bool, String> {
+        self.close_group();
+        let group = match self.undo.pop() {
+            Some(group) => group,
+            None => return Ok(false)
+        };
+        if let Err(e) = apply_ops(&mut self.syntax, &group.ops, true) {
+            self.undo.push(group);
+            return Err(e);
+        }
+        self.redo.push(group);
+        Ok(true)
+    }
+
+    /// 重做最近撤销的动作
+    ///
+    /// 失败时语法及重做栈保持不变
+    pub fn redo(&mut self) -> Result<bool, String> {
+        self.close_group();
+        let group = match self.redo.pop() {
+            Some(group) => group,
+            None => return Ok(false)
+        };
+        if let Err(e) = apply_ops(&mut self.syntax, &group.ops, false) {
+            self.redo.push(group);
+            return Err(e);
+        }
+        self.undo.push(group);
+        Ok(true)
+    }
+
+    /// 清空历史
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+        self.depth = 0;
+    }
+
+    fn close_group(&mut self) {
+        while self.depth > 0 {
+            self.end_group();
+        }
+    }
+
+    fn record(&mut self, ops: Vec<EditOp<'a>>) {
+        if ops.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        match &mut self.group {
+            Some(group) => group.ops.extend(ops),
+            None => {
+                self.undo.push(EditGroup {
+                    label: None,
+                    ops
+                })
+            }
+        }
+    }
+}
+
+/// 依次执行操作(`inverse`时逆序执行逆操作),失败时撤销已执行的操作并重建索引
+fn apply_ops<'a>(syn: &mut DWSyntax<'a>, ops: &[EditOp<'a>], inverse: bool) -> Result<(), String> {
+    let ops: Vec<&EditOp<'a>> = if inverse { ops.iter().rev().collect() } else { ops.iter().collect() };
+    let mut rv = Ok(());
+    for (i, op) in ops.iter().enumerate() {
+        if let Err(e) = op.apply(syn, inverse) {
+            for op in ops[..i].iter().rev() {
+                //SAFETY: 已执行的操作可以逆向执行
+                op.apply(syn, !inverse).unwrap();
+            }
+            rv = Err(e);
+            break;
+        }
+    }
+    syn.refresh_index();
+    rv
+}
+
+impl<'a> EditOp<'a> {
+    /// 执行操作,`inverse`为`true`时执行逆操作
+    ///
+    /// 语法项或`table`字段的索引超出范围时返回错误,不修改语法
+    pub(crate) fn apply(&self, syn: &mut DWSyntax<'a>, inverse: bool) -> Result<(), String> {
+        fn pick<T>(inverse: bool, old: T, new: T) -> T { if inverse { old } else { new } }
+        let len = syn.items.len();
+        let check = |index: usize, max: usize| {
+            if index < max {
+                Ok(())
+            } else {
+                Err(format!("item index out of range: {index}"))
+            }
+        };
+        match self {
+            EditOp::SetValue {
+                target,
+                key,
+                old,
+                new
+            } => {
+                let values =
+                    values_mut(syn, *target).ok_or_else(|| format!("invalid edit target: {target:?}"))?;
+                match (inverse, old) {
+                    (true, None) => {
+                        #[cfg(feature = "preserve_order")]
+                        values.shift_remove(key);
+                        #[cfg(not(feature = "preserve_order"))]
+                        values.remove(key);
+                    },
+                    (true, Some(old)) => {
+                        values.insert(Cow::clone(key).into_owned().into_key(), old.to_owned());
+                    },
+                    (false, _) => {
+                        values.insert(Cow::clone(key).into_owned().into_key(), new.to_owned());
+                    }
+                }
+            },
+            EditOp::SetValues {
+                target,
+                old,
+                new
+            } => {
+                let values =
+                    values_mut(syn, *target).ok_or_else(|| format!("invalid edit target: {target:?}"))?;
+                *values = map_to_owned(pick(inverse, old, new));
+            },
+            EditOp::InsertItem {
+                index,
+                item
+            } => {
+                if inverse {
+                    check(*index, len)?;
+                    syn.items.remove(*index);
+                } else {
+                    check(*index, len + 1)?;
+                    syn.items.insert(*index, item.to_owned());
+                }
+            },
+            EditOp::RemoveItem {
+                index,
+                item
+            } => {
+                if inverse {
+                    check(*index, len + 1)?;
+                    syn.items.insert(*index, item.to_owned());
+                } else {
+                    check(*index, len)?;
+                    syn.items.remove(*index);
+                }
+            },
+            EditOp::ReplaceItem {
+                index,
+                old,
+                new
+            } => {
+                check(*index, len)?;
+                syn.items[*index] = pick(inverse, old, new).to_owned();
+            },
+            EditOp::SetTableColumns {
+                old,
+                new
+            } => syn.table.columns = pick(inverse, old, new).iter().map(|v| v.to_owned()).collect(),
+            EditOp::SetData {
+                old,
+                new
+            } => syn.data = pick(inverse, old, new).iter().map(|v| v.to_owned()).collect(),
+            EditOp::SetName {
+                old,
+                new
+            } => syn.name = pick(inverse, old, new).as_ref().map(|v| Cow::Owned(v.clone().into_owned())),
+            EditOp::SetComment {
+                old,
+                new
+            } => syn.comment = pick(inverse, old, new).as_ref().map(|v| Cow::Owned(v.clone().into_owned())),
+            EditOp::SetVersion {
+                old,
+                new
+            } => syn.version = *pick(inverse, old, new)
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn values_mut<'s, 'a>(
+    syn: &'s mut DWSyntax<'a>,
+    target: EditTarget
+) -> Option<&'s mut HashMap<Key<'a>, Value<'a>>> {
+    match target {
+        EditTarget::DataWindow => Some(&mut syn.datawindow),
+        EditTarget::Header => Some(&mut syn.header),
+        EditTarget::Summary => Some(&mut syn.summary),
+        EditTarget::Footer => Some(&mut syn.footer),
+        EditTarget::Detail => Some(&mut syn.detail),
+        EditTarget::Table => Some(&mut syn.table.values),
+        EditTarget::TableColumn(index) => syn.table.columns.get_mut(index).map(|v| &mut v.values),
+        EditTarget::Item(index) => syn.items.get_mut(index).map(|v| &mut v.values)
+    }
+}
+
+/// 比较修改前后的语法生成操作记录
+fn diff<'a>(old: &DWSyntax, new: &DWSyntax, ops: &mut Vec<EditOp<'a>>) {
+    let cow = |v: &Option<Cow<str>>| v.as_ref().map(|v| Cow::Owned(v.clone().into_owned()));
+    if old.name != new.name {
+        ops.push(EditOp::SetName {
+            old: cow(&old.name),
+            new: cow(&new.name)
+        });
+    }
+    if old.comment != new.comment {
+        ops.push(EditOp::SetComment {
+            old: cow(&old.comment),
+            new: cow(&new.comment)
+        });
+    }
+    if old.version != new.version {
+        ops.push(EditOp::SetVersion {
+            old: old.version,
+            new: new.version
+        });
+    }
+    diff_values(EditTarget::DataWindow, &old.datawindow, &new.datawindow, ops);
+    diff_values(EditTarget::Header, &old.header, &new.header, ops);
+    diff_values(EditTarget::Summary, &old.summary, &new.summary, ops);
+    diff_values(EditTarget::Footer, &old.footer, &new.footer, ops);
+    diff_values(EditTarget::Detail, &old.detail, &new.detail, ops);
+    diff_values(EditTarget::Table, &old.table.values, &new.table.values, ops);
+    let (old_columns, new_columns) = (&old.table.columns, &new.table.columns);
+    if old_columns.len() == new_columns.len() &&
+        old_columns.iter().zip(new_columns).all(|(a, b)| a.name == b.name)
+    {
+        for (i, (a, b)) in old_columns.iter().zip(new_columns).enumerate() {
+            diff_values(EditTarget::TableColumn(i), &a.values, &b.values, ops);
+        }
+    } else {
+        ops.push(EditOp::SetTableColumns {
+            old: old_columns.iter().map(|v| v.to_owned()).collect(),
+            new: new_columns.iter().map(|v| v.to_owned()).collect()
+        });
+    }
+    if old.data != new.data {
+        ops.push(EditOp::SetData {
+            old: old.data.iter().map(|v| v.to_owned()).collect(),
+            new: new.data.iter().map(|v| v.to_owned()).collect()
+        });
+    }
+    diff_items(&old.items, &new.items, ops);
+}
+
+/// 比较语法项列表
+///
+/// 首尾相同(类型/名称/ID/级别)的语法项比较参数,中间不同的部分先删除后插入
+fn diff_items<'a>(old: &[Item], new: &[Item], ops: &mut Vec<EditOp<'a>>) {
+    let same =
+        |a: &Item, b: &Item| a.kind == b.kind && a.name == b.name && a.id == b.id && a.level == b.level;
+    let len = old.len().min(new.len());
+    let prefix = old.iter().zip(new).take_while(|(a, b)| same(a, b)).count();
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(len - prefix)
+        .take_while(|(a, b)| same(a, b))
+        .count();
+    for i in 0..prefix {
+        diff_values(EditTarget::Item(i), &old[i].values, &new[i].values, ops);
+    }
+    let (old_mid, new_mid) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    if old_mid.len() == new_mid.len() {
+        for (i, (a, b)) in old_mid.iter().zip(new_mid).enumerate() {
+            if same(a, b) {
+                diff_values(EditTarget::Item(prefix + i), &a.values, &b.values, ops);
+            } else {
+                ops.push(EditOp::ReplaceItem {
+                    index: prefix + i,
+                    old: a.to_owned(),
+                    new: b.to_owned()
+                });
+            }
+        }
+    } else {
+        for item in old_mid {
+            ops.push(EditOp::RemoveItem {
+                index: prefix,
+                item: item.to_owned()
+            });
+        }
+        for (i, item) in new_mid.iter().enumerate() {
+            ops.push(EditOp::InsertItem {
+                index: prefix + i,
+                item: item.to_owned()
+            });
+        }
+    }
+    for i in 0..suffix {
+        let (a, b) = (&old[old.len() - suffix + i], &new[new.len() - suffix + i]);
+        diff_values(EditTarget::Item(new.len() - suffix + i), &a.values, &b.values, ops);
+    }
+}
+
+/// 比较参数列表
+///
+/// 仅修改或追加参数时逐个记录,否则记录整个参数列表以保持参数顺序
+fn diff_values<'a>(
+    target: EditTarget,
+    old: &HashMap<Key, Value>,
+    new: &HashMap<Key, Value>,
+    ops: &mut Vec<EditOp<'a>>
+) {
+    if old == new {
+        return;
+    }
+    #[cfg(feature = "preserve_order")]
+    let appended = old.len() <= new.len() && old.keys().zip(new.keys()).all(|(a, b)| a == b);
+    #[cfg(not(feature = "preserve_order"))]
+    let appended = old.keys().all(|k| new.contains_key(k));
+    if !appended {
+        ops.push(EditOp::SetValues {
+            target,
+            old: map_to_owned(old),
+            new: map_to_owned(new)
+        });
+        return;
+    }
+    for (key, value) in new {
+        let old = old.get(key);
+        if old != Some(value) {
+            ops.push(EditOp::SetValue {
+                target,
+                key: Cow::clone(key).into_owned().into_key(),
+                old: old.map(|v| v.to_owned()),
+                new: value.to_owned()
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presentation::PresentationStyle;
+
+    const SYNTAX: &str = r#"release 19;
+datawindow(units=0 processing=0)
+header(height=96)
+detail(height=88)
+table(column=(type=long name=id dbname="id") column=(type=char(20) name=name dbname="name"))
+text(band=header alignment="2" text="Id" x="9" y="8" height="64" width="200" name=id_t)
+text(band=header alignment="2" text="Name" x="219" y="8" height="64" width="400" name=name_t)
+column(band=detail id=1 alignment="1" x="9" y="8" height="76" width="200" name=id)
+column(band=detail id=2 alignment="0" x="219" y="8" height="76" width="400" name=name)
+"#;
+
+    #[test]
+    fn test_edit_history() {
+        let mut history = EditHistory::new(DWSyntax::parse(SYNTAX).unwrap());
+        let original = history.syntax().to_string();
+        assert!(!history.can_undo());
+
+        history.edit(|syn| syn.set_presentation_style(PresentationStyle::Freeform)).unwrap();
+        let freeform = history.syntax().to_string();
+        assert_ne!(freeform, original);
+
+        //分组中的多个操作作为一个动作
+        history.group("remove labels", |history| {
+            let label = history.remove_item(0).unwrap();
+            history.remove_item(0);
+            history.insert_item(usize::MAX, label);
+            history.edit(|syn| syn.header.retain(|key, _| *key != "height"));
+        });
+        assert_eq!(history.undo_stack().len(), 2);
+        assert_eq!(history.undo_stack()[1].label.as_deref(), Some("remove labels"));
+        assert_eq!(history.undo_stack()[1].ops.len(), 4);
+        assert_eq!(history.syntax().items.len(), 3);
+        assert!(matches!(&history.syntax().items[2].name, Some(v) if *v == "id_t"));
+        let edited = history.syntax().to_string();
+
+        assert!(history.undo().unwrap());
+        assert_eq!(history.syntax().to_string(), freeform);
+        assert!(history.undo().unwrap());
+        assert_eq!(history.syntax().to_string(), original);
+        assert!(!history.undo().unwrap());
+        assert!(history.redo().unwrap());
+        assert!(history.redo().unwrap());
+        assert_eq!(history.syntax().to_string(), edited);
+        assert!(!history.can_redo());
+
+        #[cfg(feature = "query")]
+        {
+            assert!(history.modify("datawindow.units=1 nosuch.x=1").is_err());
+            assert_eq!(history.undo_stack().len(), 2);
+            let modifier = "destroy name create text(band=detail name=tip x=\"0\") datawindow.detail.height=200";
+            history.modify(modifier).unwrap();
+            assert_eq!(history.undo_stack().len(), 3);
+            history.undo().unwrap();
+            assert_eq!(history.syntax().to_string(), edited);
+        }
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&history).unwrap();
+            let mut restored: EditHistory = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.syntax().to_string(), history.syntax().to_string());
+            restored.undo().unwrap();
+            restored.undo().unwrap();
+            assert_eq!(restored.syntax().to_string(), original);
+        }
+    }
+
+    #[test]
+    fn test_apply_mismatched_ops() {
+        let mut history = EditHistory::new(DWSyntax::parse(SYNTAX).unwrap());
+        history.group("edit", |history| {
+            history.edit(|syn| syn.header.insert("color".into_key(), Value::Number(0.)));
+            history.remove_item(3);
+        });
+        //语法项比记录时少,执行失败时撤销已执行的操作
+        let mut other = DWSyntax::parse(SYNTAX).unwrap();
+        other.items.truncate(2);
+        let expected = other.to_string();
+        let ops = &history.undo_stack()[0].ops;
+        assert!(apply_ops(&mut other, ops, false).is_err());
+        assert_eq!(other.to_string(), expected);
+        assert!(apply_ops(&mut other, ops, true).is_err());
+        assert_eq!(other.to_string(), expected);
+        let op = EditOp::SetValue {
+            target: EditTarget::Item(5),
+            key: "x".into_key(),
+            old: None,
+            new: Value::Number(1.)
+        };
+        assert!(op.apply(&mut other, false).is_err());
+        let op = EditOp::ReplaceItem {
+            index: 2,
+            old: other.items[0].to_owned(),
+            new: other.items[1].to_owned()
+        };
+        assert!(op.apply(&mut other, true).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{ast::*, prelude::*};
+
+/// 语法项索引
+///
+/// 按名称、ID、分组级别定位`DWSyntax::items`中的语法项,按名称定位`table`字段,
+/// 由`DWSyntax::build_index`创建,`modify`、`insert_group`/`remove_group`及`EditHistory`的编辑、
+/// 撤销/重做修改语法项时自动重建
+///
+/// 直接修改`items`后索引可能过期,查找时会校验命中的语法项,未命中则回退到线性查找,
+/// 因此查找不存在的名称仍为O(n);`create`/`destroy`及修改名称、ID、分组级别时整体重建索引(O(n))
+#[derive(Debug, Clone, Default)]
+pub struct SyntaxIndex {
+    /// 是否已创建
+    enabled: bool,
+    names: HashMap<String, usize>,
+    ids: HashMap<u32, usize>,
+    groups: HashMap<u32, usize>,
+    columns: HashMap<String, usize>
+}
+
+impl SyntaxIndex {
+    pub fn new(syn: &DWSyntax) -> Self {
+        let mut index = SyntaxIndex {
+            enabled: true,
+            names: HashMap::with_capacity(syn.items.len()),
+            ids: HashMap::with_capacity(syn.items.len()),
+            groups: HashMap::new(),
+            columns: HashMap::with_capacity(syn.table.columns.len())
+        };
+        //重名时保留第一个,与线性查找一致
+        for (i, item) in syn.items.iter().enumerate() {
+            if let Some(name) = &item.name {
+                index.names.entry(key(name)).or_insert(i);
+            }
+            if let Some(id) = item.id {
+                index.ids.entry(id).or_insert(i);
+            }
+            if let (true, Some(level)) = (item.kind == "group", item.level) {
+                index.groups.entry(level).or_insert(i);
+            }
+        }
+        for (i, col) in syn.table.columns.iter().enumerate() {
+            if let Some(name) = &col.name {
+                index.columns.entry(key(name)).or_insert(i);
+            }
+        }
+        index
+    }
+
+    /// 是否已创建
+    pub fn is_enabled(&self) -> bool { self.enabled }
+
+    /// 按名称查找语法项的位置
+    pub fn name(&self, name: &str) -> Option<usize> { self.names.get(&key(name)).copied() }
+
+    /// 按ID查找语法项的位置
+    pub fn id(&self, id: u32) -> Option<usize> { self.ids.get(&id).copied() }
+
+    /// 按级别查找分组`group`语法项的位置
+    pub fn group(&self, level: u32) -> Option<usize> { self.groups.get(&level).copied() }
+
+    /// 按名称查找`table`字段的位置
+    pub fn table_column(&self, name: &str) -> Option<usize> {
+        self.columns.get(&key(name)).copied()
+    }
+}
+
+/// 索引键(统一转为小写,命中后按是否忽略大小写校验)
+fn key(name: &str) -> String { name.to_ascii_lowercase() }
+
+/// 校验索引命中的位置,未命中或已过期时线性查找
+fn lookup<T>(list: &[T], hit: Option<usize>, matched: impl Fn(&T) -> bool) -> Option<usize> {
+    match hit {
+        Some(i) if list.get(i).is_some_and(&matched) => Some(i),
+        _ => list.iter().position(matched)
+    }
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 创建语法项索引,使按名称、ID、分组级别及`table`字段名查找已存在的语法项为O(1)
+    pub fn build_index(&mut self) { self.index = SyntaxIndex::new(self); }
+
+    /// 删除语法项索引
+    pub fn clear_index(&mut self) { self.index = SyntaxIndex::default(); }
+
+    /// 已创建索引时重建
+    pub(crate) fn refresh_index(&mut self) {
+        if self.index.is_enabled() {
+            self.build_index();
+        }
+    }
+
+    /// 按名称查找语法项
+    pub fn item_by_name(&self, name: &str) -> Option<&Item<'a>> {
+        self.item_position(name).map(|i| &self.items[i])
+    }
+
+    /// 按ID查找字段控件`column`
+    pub fn column_control_by_id(&self, id: u32) -> Option<&Item<'a>> {
+        lookup(&self.items, self.index.id(id), |item| item.kind == "column" && item.id == Some(id))
+            .map(|i| &self.items[i])
+    }
+
+    /// 按级别查找分组`group`语法项
+    pub fn group_by_level(&self, level: u32) -> Option<&Item<'a>> {
+        self.group_position(level as f64).map(|i| &self.items[i])
+    }
+
+    /// 按名称查找`table`字段
+    pub fn table_column(&self, name: &str) -> Option<&ItemTableColumn<'a>> {
+        self.table_column_position(name).map(|i| &self.table.columns[i])
+    }
+
+    /// 按`describe`/`modify`的大小写匹配方式比较名称
+    pub(crate) fn name_eq(&self, key: &str, name: &str) -> bool {
+        if self.ignore_case() {
+            key.eq_ignore_ascii_case(name)
+        } else {
+            key == name
+        }
+    }
+
+    /// 按名称查找语法项的位置
+    pub(crate) fn item_position(&self, name: &str) -> Option<usize> {
+        lookup(&self.items, self.index.name(name), |item| {
+            matches!(&item.name, Some(v) if self.name_eq(v, name))
+        })
+    }
+
+    /// 按ID查找语法项的位置
+    pub(crate) fn id_position(&self, id: u32) -> Option<usize> {
+        lookup(&self.items, self.index.id(id), |item| item.id == Some(id))
+    }
+
+    /// 按级别查找分组`group`语法项的位置
+    pub(crate) fn group_position(&self, level: f64) -> Option<usize> {
+        let hit = if level >= 0. && level.fract() == 0. { self.index.group(level as u32) } else { None };
+        lookup(&self.items, hit, |item| {
+            item.kind == "group" &&
+                matches!(item.values.get(&"level".into_key()), Some(Value::Number(v)) if *v == level)
+        })
+    }
+
+    /// 按名称查找`table`字段的位置
+    pub(crate) fn table_column_position(&self, name: &str) -> Option<usize> {
+        lookup(&self.table.columns, self.index.table_column(name), |col| {
+            matches!(&col.name, Some(v) if self.name_eq(v, name))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::EditHistory;
+
+    const SYNTAX: &str = concat!(
+        "release 19;\r\ntable(column=(type=long name=id dbname=\"id\") column=(type=long name=dept dbname=\"dept\"))\r\n",
+        "group(level=1 by=(\"dept\"))\r\ntext(name=t1 x=1)\r\ncolumn(name=c1 id=1)\r\ntext(name=t1 x=2)\r\ncolumn(name=C2 id=2)"
+    );
+
+    #[test]
+    fn test_index() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let plain = dw.to_owned();
+        dw.build_index();
+        assert_eq!(dw, plain);
+        let index = dw.index();
+        assert!(index.is_enabled());
+        assert_eq!(index.name("C2"), Some(4));
+        assert_eq!(index.id(1), Some(2));
+        assert_eq!(index.group(1), Some(0));
+        assert_eq!(index.table_column("dept"), Some(1));
+        assert_eq!(index.name("none"), None);
+        assert_eq!(index.id(3), None);
+        assert_eq!(index.group(2), None);
+        let column = dw.column_control_by_id(2).and_then(|v| v.name.as_ref());
+        assert_eq!(column.map(|v| v.to_string()), Some("C2".into()));
+        assert_eq!(dw.group_by_level(1).map(|v| v.kind.to_string()), Some("group".into()));
+        assert!(dw.group_by_level(2).is_none());
+        assert!(dw.table_column("id").is_some());
+        assert!(dw.table_column("none").is_none());
+
+        dw.clear_index();
+        assert!(!dw.index().is_enabled());
+        assert_eq!(dw.index().name("C2"), None);
+        assert!(dw.item_by_name("C2").is_some());
+    }
+
+    #[test]
+    fn test_index_duplicate_name() {
+        //重名时与线性查找一致,返回第一个
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let x = |dw: &DWSyntax| dw.item_by_name("t1").and_then(|v| v.values.get(&"x".into_key())?.as_number());
+        assert_eq!(x(&dw), Some(1.));
+        dw.build_index();
+        assert_eq!(dw.index().name("t1"), Some(1));
+        assert_eq!(x(&dw), Some(1.));
+    }
+
+    #[test]
+    fn test_index_case() {
+        //索引键忽略大小写,命中后按运行时设置校验
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.build_index();
+        dw.set_case_insensitive(false);
+        assert_eq!(dw.index().name("c2"), Some(4));
+        assert!(dw.item_by_name("c2").is_none());
+        assert!(dw.table_column("DEPT").is_none());
+        dw.set_case_insensitive(true);
+        assert!(dw.item_by_name("c2").is_some());
+        assert!(dw.table_column("DEPT").is_some());
+    }
+
+    #[test]
+    fn test_index_stale() {
+        //直接修改`items`后索引过期,查找回退到线性查找
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.build_index();
+        dw.items.remove(0);
+        dw.table.columns.swap(0, 1);
+        assert_eq!(dw.index().name("c1"), Some(2));
+        assert_eq!(dw.item_by_name("c1").and_then(|v| v.id), Some(1));
+        assert_eq!(dw.column_control_by_id(2).and_then(|v| v.id), Some(2));
+        assert!(dw.group_by_level(1).is_none());
+        assert_eq!(dw.table_column_position("dept"), Some(0));
+        dw.items.clear();
+        assert!(dw.item_by_name("C2").is_none());
+        assert!(dw.column_control_by_id(1).is_none());
+    }
+
+    #[cfg(feature = "query")]
+    #[test]
+    fn test_index_modify() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.build_index();
+        assert_eq!(dw.modify("destroy t1"), "");
+        assert_eq!(dw.describe("t1.x"), "2");
+        assert_eq!(dw.describe("#2.name"), "C2");
+        assert_eq!(dw.modify("c1.name=c3 datawindow.table.column.dept.name=dept2"), "");
+        assert_eq!(dw.index().name("c3"), Some(1));
+        assert_eq!(dw.index().name("c1"), None);
+        assert_eq!(dw.index().table_column("dept2"), Some(1));
+        assert_eq!(dw.describe("c3.name"), "c3");
+        assert_eq!(dw.describe("c1.name"), "!");
+        assert_eq!(dw.describe("datawindow.group.1.by"), "(\"dept\")");
+        //失败的`modify`不修改索引
+        assert_ne!(dw.modify("c3.name=c4 nosuch.x=1"), "");
+        assert_eq!(dw.index().name("c3"), Some(1));
+        assert_eq!(dw.index().name("c4"), None);
+        //直接修改`items`后索引过期
+        dw.items.remove(0);
+        assert_eq!(dw.describe("C2.name"), "C2");
+        assert_eq!(dw.describe("datawindow.group.1.by"), "!");
+    }
+
+    #[test]
+    fn test_index_refresh() {
+        let mut dw = DWSyntax::parse(concat!(
+            "release 19;\r\ntable(column=(type=long name=id dbname=\"id\") column=(type=long name=dept dbname=\"dept\"))\r\n",
+            "group(level=1 by=(\"dept\"))\r\ntext(name=t1 x=1)\r\ncolumn(name=c1 id=1)"
+        ))
+        .unwrap();
+        dw.build_index();
+        //插入/删除分组后索引指向移动后的位置
+        dw.insert_group(1, ["id"]).unwrap();
+        assert_eq!(dw.index().group(1), Some(0));
+        assert_eq!(dw.index().group(2), Some(1));
+        assert_eq!(dw.index().name("t1"), Some(2));
+        dw.remove_group(1).unwrap();
+        assert_eq!(dw.index().group(1), Some(0));
+        assert_eq!(dw.index().group(2), None);
+        assert_eq!(dw.index().name("c1"), Some(2));
+
+        //撤销/重做后重建索引
+        let mut history = EditHistory::new(dw);
+        history.remove_item(1);
+        assert_eq!(history.syntax().index().name("t1"), None);
+        assert_eq!(history.syntax().index().name("c1"), Some(1));
+        history.undo().unwrap();
+        assert_eq!(history.syntax().index().name("t1"), Some(1));
+        assert_eq!(history.syntax().index().name("c1"), Some(2));
+        history.redo().unwrap();
+        assert_eq!(history.syntax().index().name("c1"), Some(1));
+        history.edit(|syn| syn.items.swap(0, 1));
+        assert_eq!(history.syntax().index().name("c1"), Some(0));
+        assert_eq!(history.syntax().index().group(1), Some(1));
+    }
+}
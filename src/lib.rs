@@ -4,9 +4,58 @@
 mod parser;
 mod ast;
 mod prelude;
+mod band;
+mod group;
+mod library;
+mod edit_style;
+mod data;
+mod format;
+mod graph;
+mod crosstab;
+mod presentation;
+mod tree;
+mod history;
+mod builder;
+mod generate;
+mod update;
+mod argument;
+mod datastore;
+mod saveas;
+mod index;
+mod property;
+mod rect;
+#[cfg(feature = "query")]
+mod describe;
+#[cfg(feature = "sqlite")]
+mod retrieve;
 
 pub use ast::*;
+pub use band::Band;
+pub use edit_style::{
+    CheckBox, CodeTable, CodeTableEntry, DropDownDataWindow, DropDownListBox, Edit, EditMask, EditStyle,
+    RadioButtons, TextCase
+};
+pub use data::{ColType, DataValue, Date, Time};
+pub use format::{DisplayFormat, Formatted, format_edit_mask};
+pub use graph::{GraphAxis, GraphControl, GraphData, GraphExpr, GraphType};
+pub use crosstab::{Crosstab, CrosstabCompute, CrosstabData, CrosstabRow, CrosstabValue};
+pub use presentation::PresentationStyle;
+pub use tree::{TreeLevel, TreeNode, TreeView};
+pub use history::{EditGroup, EditHistory, EditOp, EditTarget};
+pub use builder::DWSyntaxBuilder;
+pub use rect::Rect;
+pub use generate::{ColumnMeta, TableMeta};
+pub use update::{SqlStatement, UpdateColumn, UpdateSpec, UpdateWhere};
+pub use argument::{ArgumentType, ArgumentValue, Placeholder, RetrieveArgument, RetrieveSql};
+pub use datastore::{Buffer, DataColumn, DataRow, DataStore, ItemStatus};
+pub use saveas::SaveAsType;
+pub use index::SyntaxIndex;
+pub use property::PropertyMap;
+pub use library::{Library, LibraryError, Reference, ReferenceKind};
+pub use parser::stream::{SyntaxEvent, SyntaxReader};
+#[cfg(feature = "query")]
+pub use parser::query::{ModifyChange, ModifyError};
 pub use prelude::HashMap;
 
 /// 解析语法
-pub fn parse(input: &str) -> Result<DWSyntax, String> { DWSyntax::parse(input) }
+pub fn parse(input: &str) -> Result<DWSyntax<'_>, String> { DWSyntax::parse(input) }
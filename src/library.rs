@@ -0,0 +1,463 @@
+use crate::{ast::*, prelude::*};
+use std::{
+    collections::{BTreeMap, BTreeSet}, fmt::{self, Display}, fs, path::Path
+};
+
+/// 嵌套引用的最大深度
+const MAX_NESTED_DEPTH: usize = 32;
+
+/// 引用其它DataWindow对象的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    /// 嵌套报表
+    ///
+    /// `report(name=dw_child dataobject="d_child")`
+    Report,
+    /// 下拉DataWindow编辑风格
+    ///
+    /// `column(name=col dddw.name=d_lookup)`
+    DropDownDataWindow
+}
+
+/// 引用的DataWindow对象
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference<'b> {
+    /// 引用方式
+    pub kind: ReferenceKind,
+    /// 控件名
+    pub control: Option<&'b str>,
+    /// 被引用的DataWindow对象名
+    pub dataobject: &'b str
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 获取引用的其它DataWindow对象
+    pub fn references(&self) -> Vec<Reference<'_>> {
+        let mut refs = vec![];
+        for item in &self.items {
+            let (kind, key) = if item.kind == "report" {
+                (ReferenceKind::Report, "dataobject")
+            } else if item.kind == "column" {
+                (ReferenceKind::DropDownDataWindow, "dddw.name")
+            } else {
+                continue;
+            };
+            let dataobject = item.values.get(&key.into_key()).and_then(|v| v.as_str());
+            if let Some(dataobject) = dataobject.map(|v| v.trim()).filter(|v| !v.is_empty()) {
+                refs.push(Reference {
+                    kind,
+                    control: item.name.as_ref().map(|v| v.as_ref()),
+                    dataobject
+                });
+            }
+        }
+        refs
+    }
+}
+
+/// 依赖检查错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryError {
+    /// 被引用的对象不存在
+    Missing {
+        /// 引用方
+        from: String,
+        /// 控件名
+        control: Option<String>,
+        /// 被引用的对象名
+        dataobject: String
+    },
+    /// 循环引用
+    ///
+    /// 依次列出环上的对象名,首尾相同
+    Cycle(Vec<String>)
+}
+
+impl Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LibraryError::Missing {
+                from,
+                control: Some(control),
+                dataobject
+            } => write!(f, "{from}.{control}: dataobject `{dataobject}` not found"),
+            LibraryError::Missing {
+                from,
+                control: None,
+                dataobject
+            } => write!(f, "{from}: dataobject `{dataobject}` not found"),
+            LibraryError::Cycle(path) => write!(f, "cyclic reference: {}", path.join(" -> "))
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+/// DataWindow对象库
+///
+/// 加载多个`.srd`语法并解析对象之间的嵌套报表/下拉DataWindow引用
+///
+/// NOTE 与PowerBuilder一致,对象名不区分大小写
+#[derive(Debug, Default)]
+pub struct Library {
+    syntaxes: BTreeMap<String, DWSyntax<'static>>
+}
+
+impl Library {
+    pub fn new() -> Self { Library::default() }
+
+    /// 添加DataWindow对象
+    pub fn insert(&mut self, name: &str, syn: &DWSyntax) {
+        self.syntaxes.insert(normalize_name(name), syn.to_owned());
+    }
+
+    /// 解析语法并添加DataWindow对象
+    pub fn load_str(&mut self, name: &str, input: &str) -> Result<(), String> {
+        let syn = DWSyntax::parse(input)?;
+        self.insert(name, &syn);
+        Ok(())
+    }
+
+    /// 加载`.srd`文件,以文件名作为对象名
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<String, String> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .and_then(|v| v.to_str())
+            .ok_or_else(|| format!("{}: invalid file name", path.display()))?;
+        let input = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        //UTF-8 BOM
+        let input = input.strip_prefix('\u{feff}').unwrap_or(&input);
+        self.load_str(name, input).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(normalize_name(name))
+    }
+
+    /// 加载目录下所有`.srd`文件
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<Vec<String>, String> {
+        let dir = dir.as_ref();
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))? {
+            let path = entry.map_err(|e| format!("{}: {e}", dir.display()))?.path();
+            if matches!(path.extension(), Some(v) if v.eq_ignore_ascii_case("srd")) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        paths.into_iter().map(|path| self.load_file(path)).collect()
+    }
+
+    /// 删除DataWindow对象
+    pub fn remove(&mut self, name: &str) -> Option<DWSyntax<'static>> {
+        self.syntaxes.remove(&normalize_name(name))
+    }
+
+    /// 获取DataWindow对象
+    pub fn get(&self, name: &str) -> Option<&DWSyntax<'static>> { self.syntaxes.get(&normalize_name(name)) }
+
+    /// 获取DataWindow对象
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut DWSyntax<'static>> {
+        self.syntaxes.get_mut(&normalize_name(name))
+    }
+
+    /// 所有对象名
+    pub fn names(&self) -> impl Iterator<Item = &str> { self.syntaxes.keys().map(|v| v.as_str()) }
+
+    pub fn len(&self) -> usize { self.syntaxes.len() }
+
+    pub fn is_empty(&self) -> bool { self.syntaxes.is_empty() }
+
+    /// 获取对象的嵌套报表控件引用的子对象
+    pub fn resolve(&self, name: &str, control: &str) -> Option<&DWSyntax<'static>> {
+        let syn = self.get(name)?;
+        let dataobject = find_report(syn, control)?;
+        self.get(dataobject)
+    }
+
+    /// 依赖关系图
+    ///
+    /// 对象名 -> 直接引用的对象名
+    pub fn dependency_graph(&self) -> BTreeMap<&str, BTreeSet<String>> {
+        self.syntaxes
+            .iter()
+            .map(|(name, syn)| {
+                (name.as_str(), syn.references().into_iter().map(|v| normalize_name(v.dataobject)).collect())
+            })
+            .collect()
+    }
+
+    /// 引用指定对象的所有对象名
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        let name = normalize_name(name);
+        self.dependency_graph()
+            .into_iter()
+            .filter(|(_, deps)| deps.contains(&name))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// 检查缺失的对象和循环引用
+    pub fn check(&self) -> Vec<LibraryError> {
+        let mut errors = vec![];
+        for (name, syn) in &self.syntaxes {
+            for reference in syn.references() {
+                if !self.syntaxes.contains_key(&normalize_name(reference.dataobject)) {
+                    errors.push(LibraryError::Missing {
+                        from: name.clone(),
+                        control: reference.control.map(|v| v.to_owned()),
+                        dataobject: reference.dataobject.to_owned()
+                    });
+                }
+            }
+        }
+        if let Err(e) = self.sorted() {
+            errors.push(e);
+        }
+        errors
+    }
+
+    /// 按依赖顺序排列对象名(被引用的对象在前)
+    ///
+    /// 存在循环引用时返回`LibraryError::Cycle`
+    pub fn sorted(&self) -> Result<Vec<&str>, LibraryError> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            Visiting,
+            Done
+        }
+        fn visit<'g>(
+            name: &'g str,
+            graph: &BTreeMap<&'g str, BTreeSet<String>>,
+            marks: &mut BTreeMap<&'g str, Mark>,
+            stack: &mut Vec<&'g str>,
+            rv: &mut Vec<&'g str>
+        ) -> Result<(), LibraryError> {
+            match marks.get(name) {
+                Some(Mark::Done) => return Ok(()),
+                Some(Mark::Visiting) => {
+                    let start = stack.iter().position(|v| *v == name).unwrap_or(0);
+                    let mut path: Vec<String> = stack[start..].iter().map(|v| v.to_string()).collect();
+                    path.push(name.to_owned());
+                    return Err(LibraryError::Cycle(path));
+                },
+                None => {}
+            }
+            marks.insert(name, Mark::Visiting);
+            stack.push(name);
+            if let Some(deps) = graph.get(name) {
+                for dep in deps {
+                    //缺失的对象由`check`处理
+                    if let Some((dep, _)) = graph.get_key_value(dep.as_str()) {
+                        visit(dep, graph, marks, stack, rv)?;
+                    }
+                }
+            }
+            stack.pop();
+            marks.insert(name, Mark::Done);
+            rv.push(name);
+            Ok(())
+        }
+        let graph = self.dependency_graph();
+        let mut marks = BTreeMap::new();
+        let mut stack = vec![];
+        let mut rv = Vec::with_capacity(graph.len());
+        for name in graph.keys() {
+            visit(name, &graph, &mut marks, &mut stack, &mut rv)?;
+        }
+        Ok(rv)
+    }
+
+    /// 获取指定对象语法项的参数值
+    ///
+    /// 兼容`DataWindow::Describe`参数和返回值,支持通过`report.object.xxx`访问嵌套报表
+    #[cfg(feature = "query")]
+    pub fn describe(&self, name: &str, selector: &str) -> String {
+        match self.get(name) {
+            Some(syn) => self.describe_nested(syn, selector, 0),
+            None => "!".to_owned()
+        }
+    }
+
+    #[cfg(feature = "query")]
+    fn describe_nested(&self, syn: &DWSyntax, selector: &str, depth: usize) -> String {
+        if let Some((control, rest)) = selector.trim().split_once('.') {
+            if let Some((object, rest)) = rest.split_once('.') {
+                if object.eq_ignore_ascii_case("object") {
+                    if let Some(dataobject) = find_report(syn, control) {
+                        return match self.get(dataobject) {
+                            Some(child) if depth < MAX_NESTED_DEPTH => {
+                                self.describe_nested(child, rest, depth + 1)
+                            },
+                            _ => "!".to_owned()
+                        };
+                    }
+                }
+            }
+        }
+        syn.describe(selector)
+    }
+}
+
+/// 对象名统一为小写,并去掉`.srd`扩展名
+fn normalize_name(name: &str) -> String {
+    let name = name.trim();
+    let name = match name.len().checked_sub(4) {
+        Some(n) if name.is_char_boundary(n) && name[n..].eq_ignore_ascii_case(".srd") => &name[..n],
+        _ => name
+    };
+    name.to_lowercase()
+}
+
+/// 查找嵌套报表控件引用的对象名
+fn find_report<'b>(syn: &'b DWSyntax, control: &str) -> Option<&'b str> {
+    let item = syn.items.iter().find(|item| {
+        item.kind == "report" && matches!(&item.name, Some(v) if v.eq_ignore_ascii_case(control))
+    })?;
+    item.values.get(&"dataobject".into_key())?.as_str().map(|v| v.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 加载`(对象名,引用的嵌套报表)`
+    fn library(objects: &[(&str, &[&str])]) -> Library {
+        let mut lib = Library::new();
+        for (name, reports) in objects {
+            let mut input = "release 19;\r\ntext(band=detail name=t)\r\n".to_owned();
+            for (i, report) in reports.iter().enumerate() {
+                input += &format!("report(band=detail dataobject=\"{report}\" name=dw_{i})\r\n");
+            }
+            lib.load_str(name, &input).unwrap();
+        }
+        lib
+    }
+
+    fn cycle(path: &[&str]) -> LibraryError {
+        LibraryError::Cycle(path.iter().map(|v| v.to_string()).collect())
+    }
+
+    #[test]
+    fn test_references() {
+        let mut lib = Library::new();
+        lib.load_str(
+            "d_main.srd",
+            r#"release 19;
+table(column=(type=long name=id dbname="id"))
+column(band=detail id=1 name=id dddw.name=d_lookup)
+report(band=detail dataobject="D_Child" name=dw_child x="0")
+report(band=detail dataobject="" name=dw_empty)
+"#
+        )
+        .unwrap();
+        lib.load_str("d_child", "release 19;\r\ncolumn(band=detail id=1 name=code x=\"12\")").unwrap();
+
+        let syn = lib.get("D_MAIN").unwrap();
+        assert_eq!(syn.references(), vec![
+            Reference {
+                kind: ReferenceKind::DropDownDataWindow,
+                control: Some("id"),
+                dataobject: "d_lookup"
+            },
+            Reference {
+                kind: ReferenceKind::Report,
+                control: Some("dw_child"),
+                dataobject: "D_Child"
+            }
+        ]);
+        assert!(lib.resolve("d_main", "DW_Child").is_some());
+        assert!(lib.resolve("d_main", "id").is_none());
+        assert!(lib.resolve("d_main", "dw_empty").is_none());
+        assert!(lib.resolve("d_none", "dw_child").is_none());
+    }
+
+    #[test]
+    fn test_load_error() {
+        let mut lib = Library::new();
+        assert!(lib.load_str("d_bad", "release 19;\r\ntext(name=t").is_err());
+        assert!(lib.is_empty());
+        let err = lib.load_file("/nonexistent/d_none.srd").unwrap_err();
+        assert!(err.starts_with("/nonexistent/d_none.srd: "), "{err}");
+        assert!(lib.load_dir("/nonexistent").is_err());
+    }
+
+    #[test]
+    fn test_check_missing() {
+        let mut lib = library(&[("d_main", &["d_child"]), ("d_child", &["d_grand"])]);
+        lib.load_str("d_dddw", "release 19;\r\ncolumn(band=detail id=1 name=id dddw.name=d_lookup)").unwrap();
+        assert_eq!(lib.check(), vec![
+            LibraryError::Missing {
+                from: "d_child".to_owned(),
+                control: Some("dw_0".to_owned()),
+                dataobject: "d_grand".to_owned()
+            },
+            LibraryError::Missing {
+                from: "d_dddw".to_owned(),
+                control: Some("id".to_owned()),
+                dataobject: "d_lookup".to_owned()
+            }
+        ]);
+        assert_eq!(lib.check()[0].to_string(), "d_child.dw_0: dataobject `d_grand` not found");
+        //缺失的对象不影响排序
+        assert_eq!(lib.sorted().unwrap(), vec!["d_child", "d_dddw", "d_main"]);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let lib = library(&[("d_self", &["D_SELF.srd"])]);
+        assert_eq!(lib.sorted(), Err(cycle(&["d_self", "d_self"])));
+
+        let lib = library(&[("d_a", &["d_b"]), ("d_b", &["d_a"])]);
+        assert_eq!(lib.check(), vec![cycle(&["d_a", "d_b", "d_a"])]);
+        assert_eq!(lib.check()[0].to_string(), "cyclic reference: d_a -> d_b -> d_a");
+
+        //环之外的对象不出现在路径中
+        let lib = library(&[
+            ("d_main", &["d_child"]),
+            ("d_child", &["d_grand"]),
+            ("d_grand", &["d_leaf", "d_child"]),
+            ("d_leaf", &[])
+        ]);
+        assert_eq!(lib.sorted(), Err(cycle(&["d_child", "d_grand", "d_child"])));
+        assert_eq!(lib.dependents("d_child"), vec!["d_grand", "d_main"]);
+    }
+
+    #[test]
+    fn test_sorted() {
+        let mut lib = library(&[
+            ("d_main", &["d_child", "d_lookup"]),
+            ("d_child", &["d_grand"]),
+            ("d_grand", &["d_main"]),
+            ("d_lookup", &[])
+        ]);
+        assert!(lib.sorted().is_err());
+        lib.remove("d_grand.srd");
+        lib.get_mut("d_child").unwrap().items.pop();
+        assert_eq!(lib.sorted().unwrap(), vec!["d_child", "d_lookup", "d_main"]);
+        assert!(lib.check().is_empty());
+        assert_eq!(lib.dependents("D_Child"), vec!["d_main"]);
+        assert!(lib.dependents("d_main").is_empty());
+    }
+
+    #[cfg(feature = "query")]
+    #[test]
+    fn test_describe_nested() {
+        let mut lib = Library::new();
+        lib.load_str("d_main", "release 19;\r\nreport(band=detail dataobject=\"d_child\" name=dw_child)").unwrap();
+        lib.load_str("d_child", "release 19;\r\ncolumn(band=detail id=1 name=code x=\"12\")").unwrap();
+        assert_eq!(lib.describe("d_main", "dw_child.object.code.x"), "12");
+        assert_eq!(lib.describe("d_main", "DW_Child.Object.code.x"), "12");
+        assert_eq!(lib.describe("d_main", "dw_child.object.missing.x"), "!");
+        assert_eq!(lib.describe("d_main", "dw_child.band"), "detail");
+        assert_eq!(lib.describe("d_none", "dw_child.band"), "!");
+    }
+
+    #[cfg(feature = "query")]
+    #[test]
+    fn test_describe_cycle() {
+        //循环引用时超过最大深度返回`!`
+        let lib = library(&[("d_a", &["d_b"]), ("d_b", &["d_a"])]);
+        assert_eq!(lib.describe("d_a", "dw_0.object.dw_0.object.dw_0.band"), "detail");
+        let selector = "dw_0.object.".repeat(MAX_NESTED_DEPTH + 1) + "dw_0.band";
+        assert_eq!(lib.describe("d_a", &selector), "!");
+    }
+}
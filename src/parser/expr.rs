@@ -0,0 +1,474 @@
+use super::{value::string, *};
+use crate::data::{DataValue, unescape};
+use std::{cmp::Ordering, result::Result as StdResult};
+
+/// DataWindow表达式(`sort`/`filter`/分组依据)
+///
+/// 支持字段引用(`name`/`#1`)、数值、字符串、算术及比较运算、`like`、`in`、`and`/`or`/`not`及常用函数
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Value(DataValue),
+    /// 未解析的标识符
+    Name(String),
+    /// 字段索引(从0开始)
+    Column(usize),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// `expr [not] in (a, b)`
+    In(Box<Expr>, Vec<Expr>, bool),
+    /// 函数调用(函数名小写)
+    Call(String, Vec<Expr>)
+}
+
+/// 二元运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    NotLike,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow
+}
+
+/// 支持的函数(函数名,最少参数,最多参数)
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("isnull", 1, 1),
+    ("if", 3, 3),
+    ("upper", 1, 1),
+    ("lower", 1, 1),
+    ("trim", 1, 1),
+    ("len", 1, 1),
+    ("left", 2, 2),
+    ("right", 2, 2),
+    ("mid", 2, 3),
+    ("pos", 2, 2),
+    ("abs", 1, 1),
+    ("round", 2, 2),
+    ("string", 1, 1),
+    ("number", 1, 1),
+    ("year", 1, 1),
+    ("month", 1, 1),
+    ("day", 1, 1)
+];
+
+impl Expr {
+    /// 解析表达式,`column`返回字段名对应的索引
+    pub fn parse(input: &str, column: &dyn Fn(&str) -> Option<usize>) -> StdResult<Expr, String> {
+        let (_, mut expr) =
+            all_consuming(ws(or_expr))(input).map_err(|_| format!("invalid expression: {input}"))?;
+        expr.resolve(column)?;
+        Ok(expr)
+    }
+
+    /// 解析排序表达式`expr A, expr D`,返回(表达式,是否升序)
+    pub fn parse_sort(
+        input: &str,
+        column: &dyn Fn(&str) -> Option<usize>
+    ) -> StdResult<Vec<(Expr, bool)>, String> {
+        if input.trim().is_empty() {
+            return Ok(vec![]);
+        }
+        let order = alt((keyword("a").map(|_| true), keyword("d").map(|_| false)));
+        let term = pair(ws(or_expr), opt(ws(order)).map(|v| v.unwrap_or(true)));
+        let (_, mut terms) = all_consuming(separated_list1(char(','), term))(input)
+            .map_err(|_| format!("invalid sort expression: {input}"))?;
+        for (expr, _) in &mut terms {
+            expr.resolve(column)?;
+        }
+        Ok(terms)
+    }
+
+    /// 解析标识符并检查函数
+    fn resolve(&mut self, column: &dyn Fn(&str) -> Option<usize>) -> StdResult<(), String> {
+        match self {
+            Expr::Value(_) | Expr::Column(_) => {},
+            Expr::Name(name) => {
+                *self = match column(name) {
+                    Some(index) => Expr::Column(index),
+                    None if name.eq_ignore_ascii_case("true") => Expr::Value(DataValue::Number(1.)),
+                    None if name.eq_ignore_ascii_case("false") => Expr::Value(DataValue::Number(0.)),
+                    None => return Err(format!("column not found: {name}"))
+                };
+            },
+            Expr::Neg(expr) | Expr::Not(expr) => expr.resolve(column)?,
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.resolve(column)?;
+                rhs.resolve(column)?;
+            },
+            Expr::In(expr, list, _) => {
+                expr.resolve(column)?;
+                for expr in list {
+                    expr.resolve(column)?;
+                }
+            },
+            Expr::Call(name, args) => {
+                match FUNCTIONS.iter().find(|(func, ..)| func == name) {
+                    Some((_, min, max)) if (*min..=*max).contains(&args.len()) => {},
+                    Some(_) => return Err(format!("invalid argument count: {name}")),
+                    None => return Err(format!("unsupported function: {name}"))
+                }
+                for expr in args {
+                    expr.resolve(column)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 计算表达式的值
+    ///
+    /// 逻辑值以`1`/`0`表示,比较运算中任一方为`Null`时结果为`Null`
+    pub fn eval(&self, row: &[DataValue]) -> StdResult<DataValue, String> {
+        let rv = match self {
+            Expr::Value(v) => v.clone(),
+            Expr::Name(name) => return Err(format!("column not found: {name}")),
+            Expr::Column(index) => {
+                row.get(*index).cloned().ok_or_else(|| format!("column not found: #{}", index + 1))?
+            },
+            Expr::Neg(expr) => {
+                match expr.eval(row)? {
+                    DataValue::Null => DataValue::Null,
+                    DataValue::Number(v) => DataValue::Number(-v),
+                    v => return Err(format!("not a number: {v}"))
+                }
+            },
+            Expr::Not(expr) => logic(truth(&expr.eval(row)?)?.map(|v| !v)),
+            Expr::Binary(BinaryOp::And, lhs, rhs) => {
+                match (truth(&lhs.eval(row)?)?, truth(&rhs.eval(row)?)?) {
+                    (Some(false), _) | (_, Some(false)) => logic(Some(false)),
+                    (Some(true), Some(true)) => logic(Some(true)),
+                    _ => DataValue::Null
+                }
+            },
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => {
+                match (truth(&lhs.eval(row)?)?, truth(&rhs.eval(row)?)?) {
+                    (Some(true), _) | (_, Some(true)) => logic(Some(true)),
+                    (Some(false), Some(false)) => logic(Some(false)),
+                    _ => DataValue::Null
+                }
+            },
+            Expr::Binary(op, lhs, rhs) => binary(*op, lhs.eval(row)?, rhs.eval(row)?)?,
+            Expr::In(expr, list, negated) => {
+                let value = expr.eval(row)?;
+                if value.is_null() {
+                    return Ok(DataValue::Null);
+                }
+                let mut found = false;
+                for item in list {
+                    if compare(&value, &item.eval(row)?) == Some(Ordering::Equal) {
+                        found = true;
+                        break;
+                    }
+                }
+                logic(Some(found != *negated))
+            },
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|expr| expr.eval(row)).collect::<StdResult<Vec<_>, _>>()?;
+                call(name, args)?
+            }
+        };
+        Ok(rv)
+    }
+}
+
+/// 逻辑值
+fn logic(v: Option<bool>) -> DataValue {
+    match v {
+        Some(v) => DataValue::Number(if v { 1. } else { 0. }),
+        None => DataValue::Null
+    }
+}
+
+/// 转换为逻辑值
+fn truth(v: &DataValue) -> StdResult<Option<bool>, String> {
+    match v {
+        DataValue::Null => Ok(None),
+        DataValue::Number(v) => Ok(Some(*v != 0.)),
+        DataValue::String(v) if v.eq_ignore_ascii_case("true") => Ok(Some(true)),
+        DataValue::String(v) if v.eq_ignore_ascii_case("false") => Ok(Some(false)),
+        v => Err(format!("not a boolean: {v}"))
+    }
+}
+
+/// 比较两个值,日期与日期时间按日期时间比较,类型不同时返回`None`
+fn compare(a: &DataValue, b: &DataValue) -> Option<Ordering> {
+    match (a, b) {
+        (DataValue::Null, _) | (_, DataValue::Null) => None,
+        (DataValue::Number(_), DataValue::Number(_)) |
+        (DataValue::String(_), DataValue::String(_)) |
+        (DataValue::Date(_), DataValue::Date(_)) |
+        (DataValue::Time(_), DataValue::Time(_)) |
+        (DataValue::DateTime(..), DataValue::DateTime(..)) => a.partial_cmp(b),
+        (DataValue::Date(d), DataValue::DateTime(..)) => {
+            compare(&DataValue::DateTime(*d, Default::default()), b)
+        },
+        (DataValue::DateTime(..), DataValue::Date(d)) => {
+            compare(a, &DataValue::DateTime(*d, Default::default()))
+        },
+        _ => None
+    }
+}
+
+fn binary(op: BinaryOp, lhs: DataValue, rhs: DataValue) -> StdResult<DataValue, String> {
+    if lhs.is_null() || rhs.is_null() {
+        return Ok(DataValue::Null);
+    }
+    let mismatch = || format!("type mismatch: {lhs} {op:?} {rhs}");
+    let ordering = || compare(&lhs, &rhs).ok_or_else(mismatch);
+    let rv = match op {
+        BinaryOp::Eq => logic(Some(ordering()? == Ordering::Equal)),
+        BinaryOp::Ne => logic(Some(ordering()? != Ordering::Equal)),
+        BinaryOp::Lt => logic(Some(ordering()? == Ordering::Less)),
+        BinaryOp::Le => logic(Some(ordering()? != Ordering::Greater)),
+        BinaryOp::Gt => logic(Some(ordering()? == Ordering::Greater)),
+        BinaryOp::Ge => logic(Some(ordering()? != Ordering::Less)),
+        BinaryOp::Like | BinaryOp::NotLike => {
+            let (value, pattern) = (lhs.to_string(), rhs.to_string());
+            let matched = like(&value.chars().collect::<Vec<_>>(), &pattern.chars().collect::<Vec<_>>());
+            logic(Some(matched == (op == BinaryOp::Like)))
+        },
+        BinaryOp::Add => {
+            match (&lhs, &rhs) {
+                (DataValue::Number(a), DataValue::Number(b)) => DataValue::Number(a + b),
+                (DataValue::String(a), DataValue::String(b)) => DataValue::String(format!("{a}{b}")),
+                _ => return Err(mismatch())
+            }
+        },
+        _ => {
+            let (a, b) = lhs.as_number().zip(rhs.as_number()).ok_or_else(mismatch)?;
+            match op {
+                BinaryOp::Sub => DataValue::Number(a - b),
+                BinaryOp::Mul => DataValue::Number(a * b),
+                BinaryOp::Div if b == 0. => return Err("division by zero".to_owned()),
+                BinaryOp::Div => DataValue::Number(a / b),
+                _ => DataValue::Number(a.powf(b))
+            }
+        }
+    };
+    Ok(rv)
+}
+
+/// `like`匹配,`%`匹配任意字符串,`_`匹配单个字符
+fn like(value: &[char], pattern: &[char]) -> bool {
+    match pattern.split_first() {
+        None => value.is_empty(),
+        Some(('%', rest)) => (0..=value.len()).any(|i| like(&value[i..], rest)),
+        Some((c, rest)) => {
+            match value.split_first() {
+                Some((v, value)) if *c == '_' || v == c => like(value, rest),
+                _ => false
+            }
+        }
+    }
+}
+
+fn call(name: &str, args: Vec<DataValue>) -> StdResult<DataValue, String> {
+    let arg = |i: usize| &args[i];
+    let text = |i: usize| arg(i).to_string();
+    let number = |i: usize| arg(i).as_number().ok_or_else(|| format!("{name}: not a number: {}", arg(i)));
+    if name == "isnull" {
+        return Ok(logic(Some(arg(0).is_null())));
+    }
+    if name == "if" {
+        return Ok(if truth(arg(0))?.unwrap_or_default() { arg(1).clone() } else { arg(2).clone() });
+    }
+    if args.iter().any(|v| v.is_null()) {
+        return Ok(DataValue::Null);
+    }
+    let chars = |i: usize| text(i).chars().collect::<Vec<_>>();
+    let take = |chars: Vec<char>, start: usize, count: usize| {
+        DataValue::String(chars.into_iter().skip(start).take(count).collect())
+    };
+    let rv = match name {
+        "upper" => DataValue::String(text(0).to_uppercase()),
+        "lower" => DataValue::String(text(0).to_lowercase()),
+        "trim" => DataValue::String(text(0).trim().to_owned()),
+        "len" => DataValue::Number(text(0).chars().count() as f64),
+        "left" => take(chars(0), 0, number(1)?.max(0.) as usize),
+        "right" => {
+            let chars = chars(0);
+            let count = (number(1)?.max(0.) as usize).min(chars.len());
+            take(chars.clone(), chars.len() - count, count)
+        },
+        "mid" => {
+            let start = (number(1)?.max(1.) as usize) - 1;
+            let count = if args.len() > 2 { number(2)?.max(0.) as usize } else { usize::MAX };
+            take(chars(0), start, count)
+        },
+        "pos" => {
+            let (s, sub) = (text(0), text(1));
+            DataValue::Number(s.find(&sub).map(|i| s[..i].chars().count() + 1).unwrap_or_default() as f64)
+        },
+        "abs" => DataValue::Number(number(0)?.abs()),
+        "round" => {
+            let scale = 10f64.powi(number(1)? as i32);
+            DataValue::Number((number(0)? * scale).round() / scale)
+        },
+        "string" => DataValue::String(text(0)),
+        "number" => {
+            match arg(0) {
+                DataValue::Number(v) => DataValue::Number(*v),
+                v => {
+                    DataValue::Number(v.to_string().trim().parse().map_err(|_| format!("not a number: {v}"))?)
+                }
+            }
+        },
+        _ => {
+            let date = match arg(0) {
+                DataValue::Date(v) | DataValue::DateTime(v, _) => *v,
+                v => return Err(format!("{name}: not a date: {v}"))
+            };
+            DataValue::Number(match name {
+                "year" => date.year as f64,
+                "month" => date.month as f64,
+                _ => date.day as f64
+            })
+        }
+    };
+    Ok(rv)
+}
+
+/// 忽略前后空白
+fn ws<'a, O, F>(parser: F) -> impl FnMut(&'a str) -> ParseResult<'a, O>
+where
+    F: Parser<&'a str, O, VerboseError<&'a str>>
+{
+    delimited(multispace0, parser, multispace0)
+}
+
+/// 关键字(不区分大小写,后面不能紧跟标识符字符)
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> ParseResult<'a, &'a str> {
+    terminated(tag_no_case(word), not(satisfy(|c: char| c.is_alphanumeric() || c == '_')))
+}
+
+/// 左结合的二元运算
+fn fold_binary<'a>(
+    input: &'a str,
+    operand: fn(&'a str) -> ParseResult<'a, Expr>,
+    op: impl Parser<&'a str, BinaryOp, VerboseError<&'a str>>
+) -> ParseResult<'a, Expr> {
+    let (input, init) = operand(input)?;
+    fold_many0(pair(ws(op), operand), move || init.clone(), |lhs, (op, rhs)| {
+        Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+    })(input)
+}
+
+fn or_expr(input: &str) -> ParseResult<'_, Expr> {
+    fold_binary(input, and_expr, keyword("or").map(|_| BinaryOp::Or))
+}
+
+fn and_expr(input: &str) -> ParseResult<'_, Expr> {
+    fold_binary(input, not_expr, keyword("and").map(|_| BinaryOp::And))
+}
+
+fn not_expr(input: &str) -> ParseResult<'_, Expr> {
+    alt((preceded(ws(keyword("not")), not_expr).map(|v| Expr::Not(Box::new(v))), compare_expr))(input)
+}
+
+fn compare_expr(input: &str) -> ParseResult<'_, Expr> {
+    let (input, lhs) = add_expr(input)?;
+    let op = alt((
+        tag("<>").map(|_| BinaryOp::Ne),
+        tag("!=").map(|_| BinaryOp::Ne),
+        tag("<=").map(|_| BinaryOp::Le),
+        tag(">=").map(|_| BinaryOp::Ge),
+        tag("=").map(|_| BinaryOp::Eq),
+        tag("<").map(|_| BinaryOp::Lt),
+        tag(">").map(|_| BinaryOp::Gt),
+        keyword("like").map(|_| BinaryOp::Like),
+        pair(keyword("not"), ws(keyword("like"))).map(|_| BinaryOp::NotLike)
+    ));
+    let list = delimited(char('('), separated_list1(char(','), ws(or_expr)), char(')'));
+    let in_list =
+        pair(opt(terminated(keyword("not"), multispace0)), preceded(pair(keyword("in"), multispace0), list));
+    let (rest, rhs) = opt(ws(alt((
+        pair(op, ws(add_expr)).map(|(op, rhs)| (Some(op), vec![rhs], false)),
+        in_list.map(|(not, list)| (None, list, not.is_some()))
+    ))))(input)?;
+    let expr = match rhs {
+        Some((Some(op), mut rhs, _)) => Expr::Binary(op, Box::new(lhs), Box::new(rhs.remove(0))),
+        Some((None, list, negated)) => Expr::In(Box::new(lhs), list, negated),
+        None => lhs
+    };
+    Ok((rest, expr))
+}
+
+fn add_expr(input: &str) -> ParseResult<'_, Expr> {
+    fold_binary(input, mul_expr, alt((char('+').map(|_| BinaryOp::Add), char('-').map(|_| BinaryOp::Sub))))
+}
+
+fn mul_expr(input: &str) -> ParseResult<'_, Expr> {
+    fold_binary(input, pow_expr, alt((char('*').map(|_| BinaryOp::Mul), char('/').map(|_| BinaryOp::Div))))
+}
+
+fn pow_expr(input: &str) -> ParseResult<'_, Expr> {
+    fold_binary(input, unary, char('^').map(|_| BinaryOp::Pow))
+}
+
+fn unary(input: &str) -> ParseResult<'_, Expr> {
+    alt((preceded(ws(char('-')), unary).map(|v| Expr::Neg(Box::new(v))), primary))(input)
+}
+
+fn primary(input: &str) -> ParseResult<'_, Expr> {
+    let number = preceded(peek(satisfy(|c| c.is_ascii_digit() || c == '.')), double)
+        .map(|v| Expr::Value(DataValue::Number(v)));
+    let string = string.map(|v| Expr::Value(DataValue::String(v.as_str().map(unescape).unwrap_or_default())));
+    let column = map_res(preceded(char('#'), digit1), |v: &str| {
+        match v.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(Expr::Column(n - 1)),
+            _ => Err("invalid column id")
+        }
+    });
+    let ident = recognize(pair(
+        satisfy(|c| c.is_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_alphanumeric() || c == '_')
+    ));
+    let args = delimited(ws(char('(')), separated_list0(char(','), ws(or_expr)), char(')'));
+    let call_or_name = pair(ident, opt(args)).map(|(name, args): (&str, _)| {
+        match args {
+            Some(args) => Expr::Call(name.to_ascii_lowercase(), args),
+            None => Expr::Name(name.to_owned())
+        }
+    });
+    let paren = delimited(char('('), ws(or_expr), char(')'));
+    context("expression", ws(alt((number, string, column, call_or_name, paren))))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expr() {
+        let columns = ["id", "name", "amount"];
+        let column = |name: &str| columns.iter().position(|v| v.eq_ignore_ascii_case(name));
+        let row = [DataValue::Number(3.), "Tom".into(), DataValue::Null];
+        let eval = |input: &str| Expr::parse(input, &column).unwrap().eval(&row).unwrap();
+        assert_eq!(eval("id * 2 + 1 = 7 and NAME like 'T%'"), 1.0.into());
+        assert_eq!(eval("#1 - 1 ^ 2 * 2"), 1.0.into());
+        assert_eq!(eval("not (id in (1, 2)) and name not in ('a')"), 1.0.into());
+        assert_eq!(eval("amount > 0"), DataValue::Null);
+        assert_eq!(eval("isnull(amount) or amount > 0"), 1.0.into());
+        assert_eq!(eval("upper(left(name, 2)) + \"~\"x\""), "TO\"x".into());
+        assert_eq!(eval("if(id > 2, mid(name, 2), right(name, 1))"), "om".into());
+        assert_eq!(eval("-id + pos(name, 'm')"), 0.0.into());
+        assert!(Expr::parse("nosuch = 1", &column).is_err());
+        assert!(Expr::parse("foo(id)", &column).is_err());
+        assert!(Expr::parse("id = ", &column).is_err());
+
+        let sort = Expr::parse_sort("name A, #1 d, len(name)", &column).unwrap();
+        assert_eq!(sort.iter().map(|(_, asc)| *asc).collect::<Vec<_>>(), vec![true, false, true]);
+        assert_eq!(sort[1].0, Expr::Column(0));
+    }
+}
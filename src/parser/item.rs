@@ -25,8 +25,8 @@ impl<'a> SumItem<'a> {
 /// ```txt
 /// item(key=value key2=value)
 /// ```
-pub fn item(input: &str) -> ParseResult<SumItem> {
-    fn parse(input: &str) -> ParseResult<SumItem> {
+pub fn item(input: &str) -> ParseResult<'_, SumItem<'_>> {
+    fn parse(input: &str) -> ParseResult<'_, SumItem<'_>> {
         let (input, kind) = delimited(
             multispace0,
             take_while1(|c: char| c.is_alphabetic() || c == '.').map(IntoKey::into_key),
@@ -76,7 +76,7 @@ fn normal<'a>(kind: Key<'a>, input: &'a str) -> ParseResult<'a, SumItem<'a>> {
 /// (column=(type=type) column=(type=type) key=value key=value)
 /// ```
 #[inline]
-fn table(input: &str) -> ParseResult<SumItem> {
+fn table(input: &str) -> ParseResult<'_, SumItem<'_>> {
     let (mut input, _) = tag("(")(input)?;
     let mut columns = Vec::with_capacity(64);
     let mut values = HashMap::with_capacity(8);
@@ -128,9 +128,9 @@ fn table(input: &str) -> ParseResult<SumItem> {
 /// data(val, null val, val)
 /// ```
 #[inline]
-fn data(input: &str) -> ParseResult<SumItem> {
+pub(super) fn data(input: &str) -> ParseResult<'_, SumItem<'_>> {
     //yyyy-mm-dd
-    fn date(input: &str) -> ParseResult<Value> {
+    fn date(input: &str) -> ParseResult<'_, Value<'_>> {
         recognize(tuple((
             map_parser(digit1, take(4usize)),
             tag("-"),
@@ -142,7 +142,7 @@ fn data(input: &str) -> ParseResult<SumItem> {
         .parse(input)
     }
     //hh:mm:ss:ssss
-    fn time(input: &str) -> ParseResult<Value> {
+    fn time(input: &str) -> ParseResult<'_, Value<'_>> {
         recognize(tuple((
             map_parser(digit1, take(2usize)),
             tag(":"),
@@ -156,15 +156,15 @@ fn data(input: &str) -> ParseResult<SumItem> {
         .parse(input)
     }
     //yyyy-mm-dd hh:mm:ss:ssss
-    fn datetime(input: &str) -> ParseResult<Value> {
+    fn datetime(input: &str) -> ParseResult<'_, Value<'_>> {
         recognize(tuple((date, tag(" "), time))).map(|v: &str| Value::Literal(v.into())).parse(input)
     }
     //null
-    fn null(input: &str) -> ParseResult<Value> {
+    fn null(input: &str) -> ParseResult<'_, Value<'_>> {
         tag("null").map(|v: &str| Value::Literal(v.into())).parse(input)
     }
     //空格分隔的值列表
-    fn list(input: &str) -> ParseResult<Value> {
+    fn list(input: &str) -> ParseResult<'_, Value<'_>> {
         separated_list0(multispace1, alt((null, value::string, datetime, date, time, value::number)))
             .map(Value::List)
             .parse(input)
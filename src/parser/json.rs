@@ -0,0 +1,202 @@
+use super::*;
+use std::{fmt, result::Result as StdResult};
+
+/// JSON值
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// 保留键的顺序
+    Object(Vec<(String, Json)>)
+}
+
+impl Json {
+    /// 解析JSON
+    pub fn parse(input: &str) -> StdResult<Self, String> {
+        let input = input.trim_start_matches('\u{feff}');
+        let (_, json) =
+            all_consuming(delimited(multispace0, value, multispace0))(input).map_err(|e| {
+                format!("invalid json: {}", friendly_error(input, e))
+            })?;
+        Ok(json)
+    }
+
+    /// 获取对象的成员
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(v) => Some(v),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(v) => write!(f, "{v}"),
+            Json::Number(v) if v.is_finite() => write!(f, "{v}"),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(v) => write_string(f, v),
+            Json::Array(list) => {
+                write!(f, "[")?;
+                for (i, v) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                write!(f, "]")
+            },
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, v)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{v}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?
+        }
+    }
+    write!(f, "\"")
+}
+
+fn value(input: &str) -> ParseResult<'_, Json> {
+    let array = delimited(
+        char('['),
+        separated_list0(char(','), delimited(multispace0, value, multispace0)),
+        preceded(multispace0, char(']'))
+    )
+    .map(Json::Array);
+    let member = separated_pair(
+        delimited(multispace0, string, multispace0),
+        char(':'),
+        delimited(multispace0, value, multispace0)
+    );
+    let object = delimited(char('{'), separated_list0(char(','), member), preceded(multispace0, char('}')))
+        .map(Json::Object);
+    let number = recognize(tuple((
+        opt(char('-')),
+        digit1,
+        opt(pair(char('.'), digit1)),
+        opt(tuple((one_of("eE"), opt(one_of("+-")), digit1)))
+    )));
+    context(
+        "json value",
+        alt((
+            tag("null").map(|_| Json::Null),
+            tag("true").map(|_| Json::Bool(true)),
+            tag("false").map(|_| Json::Bool(false)),
+            map_res(number, |v: &str| v.parse().map(Json::Number)),
+            string.map(Json::String),
+            array,
+            object
+        ))
+    )(input)
+}
+
+fn string(input: &str) -> ParseResult<'_, String> {
+    let (mut input, _) = char('"')(input)?;
+    let mut rv = String::new();
+    loop {
+        let (rest, chunk) = take_till(|c| c == '"' || c == '\\')(input)?;
+        rv += chunk;
+        let (rest, c) = anychar(rest)?;
+        if c == '"' {
+            return Ok((rest, rv));
+        }
+        let (rest, c) = anychar(rest)?;
+        input = match c {
+            'n' => {
+                rv.push('\n');
+                rest
+            },
+            'r' => {
+                rv.push('\r');
+                rest
+            },
+            't' => {
+                rv.push('\t');
+                rest
+            },
+            'b' => {
+                rv.push('\u{8}');
+                rest
+            },
+            'f' => {
+                rv.push('\u{c}');
+                rest
+            },
+            'u' => {
+                let (rest, code) = hex4(rest)?;
+                //代理对
+                let (rest, code) = match code {
+                    0xd800..=0xdbff => {
+                        let (rest, low) = preceded(tag("\\u"), hex4)(rest)?;
+                        (rest, 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff))
+                    },
+                    _ => (rest, code)
+                };
+                rv.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                rest
+            },
+            c => {
+                rv.push(c);
+                rest
+            }
+        };
+    }
+}
+
+fn hex4(input: &str) -> ParseResult<'_, u32> {
+    map_res(take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()), |v| u32::from_str_radix(v, 16))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        let json = Json::parse(r#" {"a": [1, -2.5e1, true, null], "b" : "x\"中😀\n", "c": {}} "#).unwrap();
+        assert_eq!(json.get("a").and_then(|v| v.as_array()).map(|v| v.len()), Some(4));
+        assert_eq!(json.get("b").and_then(|v| v.as_str()), Some("x\"中😀\n"));
+        assert_eq!(json.to_string(), r#"{"a":[1,-25,true,null],"b":"x\"中😀\n","c":{}}"#);
+        assert!(Json::parse("[1,]").is_err());
+    }
+}
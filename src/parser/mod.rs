@@ -5,6 +5,13 @@ use nom::{
 
 mod item;
 mod value;
+pub mod pbselect;
+pub mod expr;
+pub mod json;
+pub mod xml;
+pub mod stream;
+#[cfg(feature = "rayon")]
+mod parallel;
 #[cfg(feature = "query")]
 pub mod query;
 
@@ -15,75 +22,53 @@ pub type Result<'a, T> = ::std::result::Result<T, Error<'a>>;
 type ParseResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
 
 /// 解析语法
-pub fn parse(input: &str) -> Result<DWSyntax> {
+pub fn parse(input: &str) -> Result<'_, DWSyntax<'_>> {
     let (input, (name, comment)) = srd_file_header(input)?;
     let (input, version) = version(input)?;
-    let (input, (datawindow, header, summary, footer, detail, table, data, items)) = fold_many1(
-        item,
-        || {
-            (
-                Default::default(),
-                Default::default(),
-                Default::default(),
-                Default::default(),
-                Default::default(),
-                Default::default(),
-                Default::default(),
-                Vec::with_capacity(2048)
-            )
-        },
-        |(
-            mut datawindow,
-            mut header,
-            mut summary,
-            mut footer,
-            mut detail,
-            mut table,
-            mut data,
-            mut items
-        ),
-         item| {
-            match item {
-                SumItem::Item(item) => {
-                    if item.kind == "datawindow" {
-                        datawindow = item.values;
-                    } else if item.kind == "header" {
-                        header = item.values;
-                    } else if item.kind == "summary" {
-                        summary = item.values;
-                    } else if item.kind == "footer" {
-                        footer = item.values;
-                    } else if item.kind == "detail" {
-                        detail = item.values;
-                    } else {
-                        items.push(item);
-                    }
-                },
-                SumItem::ItemTable(item) => {
-                    table = item;
-                },
-                SumItem::ItemData(item) => {
-                    data = item;
-                }
-            }
-            (datawindow, header, summary, footer, detail, table, data, items)
+    let mut syn = DWSyntax {
+        name,
+        comment,
+        items: Vec::with_capacity(2048),
+        ..DWSyntax::new(version)
+    };
+    //并行解析失败时回退到串行解析,保证错误信息一致
+    #[cfg(feature = "rayon")]
+    if let Some(chunks) = parallel::items(input) {
+        for item in chunks.into_iter().flatten() {
+            push_item(&mut syn, item);
         }
-    )(input)?;
+        return Ok(syn);
+    }
+    let (input, _) = fold_many1(item, || (), |_, item| push_item(&mut syn, item))(input)?;
     terminated(multispace0, eof)(input)?;
+    Ok(syn)
+}
 
-    Ok(DWSyntax {
-        name,
-        comment,
-        version,
-        datawindow,
-        header,
-        summary,
-        footer,
-        detail,
-        table,
-        data,
-        items
-    })
+/// 合并语法项
+fn push_item<'a>(syn: &mut DWSyntax<'a>, item: SumItem<'a>) {
+    match item {
+        SumItem::Item(item) => {
+            if item.kind == "datawindow" {
+                syn.datawindow = item.values;
+            } else if item.kind == "header" {
+                syn.header = item.values;
+            } else if item.kind == "summary" {
+                syn.summary = item.values;
+            } else if item.kind == "footer" {
+                syn.footer = item.values;
+            } else if item.kind == "detail" {
+                syn.detail = item.values;
+            } else {
+                syn.items.push(item);
+            }
+        },
+        SumItem::ItemTable(item) => {
+            syn.table = item;
+        },
+        SumItem::ItemData(item) => {
+            syn.data = item;
+        }
+    }
 }
 
 /// 转换友好错误信息
@@ -108,7 +93,8 @@ pub fn friendly_error(input: &str, err: Error) -> String {
 /// ```txt
 /// (dwo.srd,comment)
 /// ```
-fn srd_file_header(input: &str) -> ParseResult<(Option<Cow<str>>, Option<Cow<str>>)> {
+#[allow(clippy::type_complexity)]
+fn srd_file_header(input: &str) -> ParseResult<'_, (Option<Cow<'_, str>>, Option<Cow<'_, str>>)> {
     let (input, name) =
         context("header name", opt(delimited(tag("$PBExportHeader$"), is_not("\r"), crlf)))(input)?;
     let (input, comment) =
@@ -129,7 +115,7 @@ fn srd_file_header(input: &str) -> ParseResult<(Option<Cow<str>>, Option<Cow<str
 /// ```txt
 /// 19
 /// ```
-fn version(input: &str) -> ParseResult<f64> {
+fn version(input: &str) -> ParseResult<'_, f64> {
     context(
         "version",
         delimited(
@@ -153,7 +139,7 @@ fn version(input: &str) -> ParseResult<f64> {
 /// ```txt
 /// map<key,value>
 /// ```
-fn value_map(input: &str) -> ParseResult<HashMap<Key, Value>> {
+fn value_map(input: &str) -> ParseResult<'_, HashMap<Key<'_>, Value<'_>>> {
     let (mut input, _) = tag("(")(input)?;
     let mut values = HashMap::with_capacity(32);
     //手写循环替代`separated_list0`,以支持松散格式
@@ -188,8 +174,8 @@ fn value_map(input: &str) -> ParseResult<HashMap<Key, Value>> {
 /// ```txt
 /// (key,value)
 /// ```
-fn key_value(input: &str) -> ParseResult<(Key, Value)> {
-    fn key(input: &str) -> ParseResult<Key> {
+fn key_value(input: &str) -> ParseResult<'_, (Key<'_>, Value<'_>)> {
+    fn key(input: &str) -> ParseResult<'_, Key<'_>> {
         //必须是字母开头
         satisfy(|c| c.is_alphabetic())(input)?;
         context(
@@ -197,7 +183,7 @@ fn key_value(input: &str) -> ParseResult<(Key, Value)> {
             take_while1(|c: char| c.is_alphanumeric() || c == '.' || c == '_').map(IntoKey::into_key)
         )(input)
     }
-    fn value(input: &str) -> ParseResult<Value> {
+    fn value(input: &str) -> ParseResult<'_, Value<'_>> {
         context(
             "value",
             cut(alt((value::string, value::literal, value::number, value::map, value::list, fail)))
@@ -388,7 +374,8 @@ mod tests {
                         ("x1".into_key(), Value::DoubleQuotedString("0".into()))
                     ])
                 }
-            ]
+            ],
+            ..DWSyntax::new(12.5)
         });
         assert_eq!(
             dw.to_string(),
@@ -0,0 +1,75 @@
+use super::{
+    item::{SumItem, item},
+    stream::item_end,
+    *
+};
+use rayon::prelude::*;
+
+/// 启用并行解析的最小字节数
+const PARALLEL_MIN_SIZE: usize = 64 * 1024;
+/// 每块的最小字节数
+const CHUNK_MIN_SIZE: usize = 16 * 1024;
+
+/// 按顶层语法项边界拆分后并行解析
+///
+/// 块大小为`input.len() / (线程数 * 4)`且不小于`CHUNK_MIN_SIZE`,
+/// 以下情况返回`None`,由调用方回退到串行解析:
+/// - 内容少于`PARALLEL_MIN_SIZE`(64 KiB),拆分和调度的开销大于收益
+/// - 线程池只有一个线程
+/// - 只能拆分为一块
+/// - 任一块解析失败(如拆分位置不在语法项边界),由串行解析报告错误位置
+pub(super) fn items(input: &str) -> Option<Vec<Vec<SumItem<'_>>>> {
+    let threads = rayon::current_num_threads();
+    if input.len() < PARALLEL_MIN_SIZE || threads < 2 {
+        return None;
+    }
+    let chunks = split(input, (input.len() / (threads * 4)).max(CHUNK_MIN_SIZE));
+    if chunks.len() < 2 {
+        return None;
+    }
+    chunks
+        .into_par_iter()
+        .map(|chunk| all_consuming(terminated(many1(item), multispace0))(chunk).ok().map(|(_, items)| items))
+        .collect()
+}
+
+/// 在顶层语法项边界处拆分,每块至少`size`字节
+fn split(input: &str, size: usize) -> Vec<&str> {
+    let mut bounds = vec![0];
+    let mut pos = 0;
+    while let Some(end) = item_end(&input[pos..]) {
+        pos += end;
+        if pos - bounds[bounds.len() - 1] >= size {
+            bounds.push(pos);
+        }
+    }
+    //末尾的空白归入最后一块,不完整的语法项单独成块(解析失败后回退)
+    if bounds.len() > 1 && bounds[bounds.len() - 1] == pos && input[pos..].trim().is_empty() {
+        bounds.pop();
+    }
+    bounds.push(input.len());
+    bounds.windows(2).map(|v| &input[v[0]..v[1]]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel() {
+        let input = include_str!("../../assets/big_file.srd").trim_start_matches('\u{feff}');
+        let (input, _) = pair(srd_file_header, version)(input).unwrap();
+        let chunks = split(input, CHUNK_MIN_SIZE);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), input);
+        let (_, serial) = all_consuming(terminated(many1(item), multispace0))(input).unwrap();
+        //固定线程数,避免单核环境下不进行并行解析
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let parallel: Vec<_> = pool.install(|| items(input)).unwrap().into_iter().flatten().collect();
+        assert_eq!(parallel, serial);
+        //不完整的语法项回退到串行解析
+        let broken = format!("{input}\r\ntext(name=t");
+        assert!(pool.install(|| items(&broken)).is_none());
+        assert!(DWSyntax::parse(&format!("release 19;{broken}")).is_err());
+    }
+}
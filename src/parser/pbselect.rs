@@ -0,0 +1,342 @@
+use super::*;
+use crate::data::{escape, unescape};
+
+/// `PBSELECT`语法
+///
+/// # Syntax
+///
+/// ```txt
+/// PBSELECT( VERSION(400) DISTINCT = "yes" TABLE(NAME="emp" ) COLUMN(NAME="emp.id")
+///     JOIN (LEFT="emp.dept_id" OP ="=" RIGHT="dept.id" OUTER1 ="emp.dept_id" )
+///     WHERE( EXP1 ="emp.id" OP =">" EXP2 =":arg" LOGIC ="and" ) ORDER(NAME="emp.id" ASC=yes ) )
+///     ARG(NAME = "arg" TYPE = number)
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PbSelect {
+    pub distinct: bool,
+    pub tables: Vec<String>,
+    /// `COLUMN`和`COMPUTE`
+    pub columns: Vec<String>,
+    pub joins: Vec<PbSelectJoin>,
+    pub wheres: Vec<PbSelectCondition>,
+    pub groups: Vec<String>,
+    pub havings: Vec<PbSelectCondition>,
+    /// (字段,是否升序)
+    pub orders: Vec<(String, bool)>,
+    /// (参数名,类型)
+    pub arguments: Vec<(String, String)>
+}
+
+/// `JOIN`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PbSelectJoin {
+    pub left: String,
+    pub op: String,
+    pub right: String,
+    /// 外连接的一侧
+    pub outer: Option<String>
+}
+
+/// `WHERE`/`HAVING`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PbSelectCondition {
+    pub exp1: String,
+    pub op: String,
+    pub exp2: String,
+    /// 与下一个条件的连接方式
+    pub logic: Option<String>
+}
+
+impl PbSelect {
+    /// 解析`table.retrieve`参数
+    ///
+    /// 非`PBSELECT`语法时返回`None`
+    pub fn parse(retrieve: &str) -> Option<Self> {
+        let retrieve = unescape(retrieve);
+        pbselect(&retrieve).ok().map(|(_, v)| v)
+    }
+
+    /// 生成SQL语句
+    ///
+    /// `JOIN`生成ANSI连接语法(`INNER JOIN`/`LEFT OUTER JOIN`/`RIGHT OUTER JOIN`)
+    pub fn to_sql(&self) -> String {
+        let mut sql = String::from("SELECT ");
+        if self.distinct {
+            sql += "DISTINCT ";
+        }
+        sql += &self.columns.join(", ");
+        sql += " FROM ";
+        let (from, mut wheres) = self.from();
+        sql += &from;
+        if !self.wheres.is_empty() {
+            wheres.push(format!("( {} )", conditions(&self.wheres)));
+        }
+        if !wheres.is_empty() {
+            sql += " WHERE ";
+            sql += &wheres.join(" and ");
+        }
+        if !self.groups.is_empty() {
+            sql += " GROUP BY ";
+            sql += &self.groups.join(", ");
+        }
+        if !self.havings.is_empty() {
+            sql += " HAVING ";
+            sql += &conditions(&self.havings);
+        }
+        if !self.orders.is_empty() {
+            sql += " ORDER BY ";
+            let orders: Vec<String> = self
+                .orders
+                .iter()
+                .map(|(name, asc)| format!("{name} {}", if *asc { "ASC" } else { "DESC" }))
+                .collect();
+            sql += &orders.join(", ");
+        }
+        sql
+    }
+
+    /// 生成`PBSELECT`语法(用于`table.retrieve`)
+    pub fn to_pbselect(&self) -> String {
+        let mut rv = String::from("PBSELECT( VERSION(400)");
+        if self.distinct {
+            rv += " DISTINCT = \"yes\"";
+        }
+        for table in &self.tables {
+            rv += &format!(" TABLE(NAME=\"{}\" )", escape(table));
+        }
+        for column in &self.columns {
+            rv += &format!(" COLUMN(NAME=\"{}\")", escape(column));
+        }
+        for join in &self.joins {
+            rv += &format!(
+                " JOIN (LEFT=\"{}\" OP =\"{}\" RIGHT=\"{}\"",
+                escape(&join.left),
+                escape(&join.op),
+                escape(&join.right)
+            );
+            if let Some(outer) = &join.outer {
+                rv += &format!(" OUTER1 =\"{}\"", escape(outer));
+            }
+            rv += " )";
+        }
+        let condition = |name: &str, cond: &PbSelectCondition| {
+            let mut rv = format!(
+                " {name}( EXP1 =\"{}\" OP =\"{}\" EXP2 =\"{}\"",
+                escape(&cond.exp1),
+                escape(&cond.op),
+                escape(&cond.exp2)
+            );
+            if let Some(logic) = &cond.logic {
+                rv += &format!(" LOGIC =\"{}\"", escape(logic));
+            }
+            rv + " )"
+        };
+        for cond in &self.wheres {
+            rv += &condition("WHERE", cond);
+        }
+        for group in &self.groups {
+            rv += &format!(" GROUP(NAME=\"{}\")", escape(group));
+        }
+        for cond in &self.havings {
+            rv += &condition("HAVING", cond);
+        }
+        for (name, asc) in &self.orders {
+            rv += &format!(" ORDER(NAME=\"{}\" ASC={} )", escape(name), if *asc { "yes" } else { "no" });
+        }
+        rv += ")";
+        for (name, ty) in &self.arguments {
+            rv += &format!(" ARG(NAME = \"{}\" TYPE = {ty})", escape(name));
+        }
+        rv
+    }
+
+    /// 生成`FROM`子句,无法转换为连接的`JOIN`条件放到`WHERE`子句
+    fn from(&self) -> (String, Vec<String>) {
+        /// (基表, [(连接表, 连接方式, 条件)])
+        type Group<'a> = (&'a str, Vec<(&'a str, &'static str, Vec<String>)>);
+        fn table(column: &str) -> &str { column.rsplit_once('.').map(|(v, _)| v).unwrap_or_default() }
+        fn contains(group: &Group, table: &str) -> Option<usize> {
+            if group.0.eq_ignore_ascii_case(table) {
+                return Some(0);
+            }
+            group.1.iter().position(|v| v.0.eq_ignore_ascii_case(table)).map(|i| i + 1)
+        }
+        let mut groups: Vec<Group> = vec![];
+        let mut wheres = vec![];
+        for join in &self.joins {
+            let cond = format!("{} {} {}", join.left, join.op, join.right);
+            let kind = match &join.outer {
+                Some(v) if *v == join.left => "LEFT OUTER JOIN",
+                Some(v) if *v == join.right => "RIGHT OUTER JOIN",
+                _ => "INNER JOIN"
+            };
+            let (left, right) = (table(&join.left), table(&join.right));
+            let find =
+                |table: &str| groups.iter().enumerate().find_map(|(i, g)| Some((i, contains(g, table)?)));
+            match (find(left), find(right)) {
+                (None, None) => groups.push((left, vec![(right, kind, vec![cond])])),
+                (Some((g, _)), None) => groups[g].1.push((right, kind, vec![cond])),
+                (None, Some((g, _))) => {
+                    let kind = match kind {
+                        "LEFT OUTER JOIN" => "RIGHT OUTER JOIN",
+                        "RIGHT OUTER JOIN" => "LEFT OUTER JOIN",
+                        v => v
+                    };
+                    groups[g].1.push((left, kind, vec![cond]));
+                },
+                //条件放到后连接的表上
+                (Some((g1, i1)), Some((g2, i2))) if g1 == g2 && i1.max(i2) > 0 => {
+                    groups[g1].1[i1.max(i2) - 1].2.push(cond)
+                },
+                _ => wheres.push(format!("( {cond} )"))
+            }
+        }
+        let render = |(base, joins): Group| {
+            joins.into_iter().fold(base.to_owned(), |rv, (table, kind, conds)| {
+                format!("{rv} {kind} {table} ON {}", conds.join(" AND "))
+            })
+        };
+        let joined: Vec<&str> = groups.iter().flat_map(|g| g.1.iter().map(|v| v.0)).collect();
+        let mut from = vec![];
+        for table in &self.tables {
+            if let Some(i) = groups.iter().position(|g| g.0.eq_ignore_ascii_case(table)) {
+                from.push(render(groups.remove(i)));
+            } else if !joined.iter().any(|v| v.eq_ignore_ascii_case(table)) {
+                from.push(table.clone());
+            }
+        }
+        //基表不在`TABLE`中的连接
+        from.extend(groups.into_iter().map(render));
+        (from.join(", "), wheres)
+    }
+}
+
+fn conditions(conds: &[PbSelectCondition]) -> String {
+    let mut rv = String::new();
+    for (i, cond) in conds.iter().enumerate() {
+        rv += &format!("{} {} {}", cond.exp1, cond.op, cond.exp2);
+        if i + 1 < conds.len() {
+            rv += &format!(" {} ", cond.logic.as_deref().unwrap_or("and"));
+        }
+    }
+    rv
+}
+
+/// `PBSELECT`语法解析
+fn pbselect(input: &str) -> ParseResult<'_, PbSelect> {
+    /// `NAME(key=value ...)`/`NAME(400)`/`NAME = value`
+    fn clause(input: &str) -> ParseResult<'_, (&str, Option<HashMap<Key<'_>, Value<'_>>>)> {
+        let (input, name) = terminated(alpha1, multispace0)(input)?;
+        alt((
+            value_map.map(Some),
+            delimited(tag("("), delimited(multispace0, double, multispace0), tag(")")).map(|_| None),
+            preceded(
+                delimited(multispace0, tag("="), multispace0),
+                alt((value::string, value::literal, value::number))
+            )
+            .map(|v| {
+                let mut values = HashMap::default();
+                values.insert("VALUE".into_key(), v);
+                Some(values)
+            })
+        ))
+        .map(move |v| (name, v))
+        .parse(input)
+    }
+    let (input, clauses) = delimited(
+        tuple((multispace0, tag_no_case("pbselect"), multispace0, tag("("))),
+        many0(delimited(multispace0, clause, multispace0)),
+        tag(")")
+    )(input)?;
+    let (input, args) = terminated(many0(delimited(multispace0, clause, multispace0)), eof)(input)?;
+
+    let mut select = PbSelect::default();
+    for (name, values) in clauses.into_iter().chain(args) {
+        let values = values.unwrap_or_default();
+        let get = |key: &'static str| values.get(&key.into_key()).map(|v| unescape(&v.to_string()));
+        let string = |key: &'static str| get(key).unwrap_or_default();
+        let condition = || {
+            PbSelectCondition {
+                exp1: string("EXP1"),
+                op: string("OP"),
+                exp2: string("EXP2"),
+                logic: get("LOGIC")
+            }
+        };
+        match name.to_ascii_uppercase().as_str() {
+            "DISTINCT" => {
+                select.distinct =
+                    values.get(&"VALUE".into_key()).and_then(|v| v.to_bool()).unwrap_or_default()
+            },
+            "TABLE" => select.tables.push(string("NAME")),
+            "COLUMN" | "COMPUTE" => select.columns.push(string("NAME")),
+            "JOIN" => {
+                select.joins.push(PbSelectJoin {
+                    left: string("LEFT"),
+                    op: string("OP"),
+                    right: string("RIGHT"),
+                    outer: get("OUTER1").or_else(|| get("OUTER2"))
+                })
+            },
+            "WHERE" => select.wheres.push(condition()),
+            "HAVING" => select.havings.push(condition()),
+            "GROUP" => select.groups.push(string("NAME")),
+            "ORDER" => {
+                let asc = values.get(&"ASC".into_key()).and_then(|v| v.to_bool()).unwrap_or(true);
+                select.orders.push((string("NAME"), asc));
+            },
+            "ARG" => select.arguments.push((string("NAME"), string("TYPE"))),
+            _ => {}
+        }
+    }
+    Ok((input, select))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pbselect() {
+        let retrieve = r#"PBSELECT( VERSION(400) DISTINCT = "yes" TABLE(NAME="emp" ) TABLE(NAME="dept" ) COLUMN(NAME="emp.id") COLUMN(NAME="dept.name") COMPUTE(NAME="count(*)")JOIN (LEFT="emp.dept_id"    OP ="="RIGHT="dept.id"    OUTER1 ="emp.dept_id" )WHERE(    EXP1 ="emp.id"   OP =">"    EXP2 =":id"    LOGIC ="or" ) WHERE(    EXP1 ="dept.name"   OP ="like"    EXP2 ="~~'A%~~'" ) GROUP(NAME="emp.id") GROUP(NAME="dept.name") ORDER(NAME="emp.id" ASC=no ) ) ARG(NAME = "id" TYPE = number) "#;
+        let select = PbSelect::parse(retrieve).unwrap();
+        assert!(select.distinct);
+        assert_eq!(select.tables, vec!["emp", "dept"]);
+        assert_eq!(select.arguments, vec![("id".to_owned(), "number".to_owned())]);
+        assert_eq!(
+            select.to_sql(),
+            "SELECT DISTINCT emp.id, dept.name, count(*) FROM emp LEFT OUTER JOIN dept ON emp.dept_id = dept.id WHERE ( emp.id > :id or dept.name like 'A%' ) GROUP BY emp.id, dept.name ORDER BY emp.id DESC"
+        );
+        assert_eq!(PbSelect::parse(&select.to_pbselect()), Some(select));
+        let select = PbSelect {
+            tables: vec!["a".into(), "b".into(), "c".into(), "d".into()],
+            columns: vec!["a.id".into()],
+            joins: vec![
+                PbSelectJoin {
+                    left: "b.a_id".into(),
+                    op: "=".into(),
+                    right: "a.id".into(),
+                    outer: Some("b.a_id".into())
+                },
+                PbSelectJoin {
+                    left: "a.c_id".into(),
+                    op: "=".into(),
+                    right: "c.id".into(),
+                    outer: None
+                },
+                PbSelectJoin {
+                    left: "b.c_id".into(),
+                    op: "=".into(),
+                    right: "c.id".into(),
+                    outer: None
+                }
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            select.to_sql(),
+            "SELECT a.id FROM b LEFT OUTER JOIN a ON b.a_id = a.id INNER JOIN c ON a.c_id = c.id AND b.c_id = c.id, d"
+        );
+        assert_eq!(PbSelect::parse("SELECT 1"), None);
+    }
+}
@@ -1,4 +1,6 @@
 use super::*;
+use crate::history::{EditOp, EditTarget, values_mut};
+use nom::error::VerboseErrorKind;
 
 /// 查找指定语法项的参数值
 ///
@@ -21,176 +23,609 @@ pub fn find<'a, 'b: 'a, 'c>(syn: &'a DWSyntax<'b>, input: &'c str) -> Result<'c,
         SelectRoot::ItemTable => &syn.table.values,
         SelectRoot::ItemTableColumn(index) => &syn.table.columns[index].values
     };
-    Ok(values.get(&key))
+    Ok(find_key(values, &key, syn.ignore_case()).and_then(|key| values.get(key)))
 }
 
 /// 修改语法项的参数值
 ///
 /// 兼容`DataWindow::Modify`参数
+///
+/// 多条语句作为一个整体执行,任意一条失败时回滚已执行的修改
 pub fn modify<'a, 'b: 'a, 'c>(syn: &'a mut DWSyntax<'b>, input: &'c str) -> Result<'c, ()> {
-    enum ModifyKind<'a> {
-        Assign(&'a str, Value<'a>),
-        Create(SumItem<'a>),
-        Destroy(&'a str)
+    modify_ops(syn, input, None).map(|_| ())
+}
+
+/// 修改语法项的参数值,返回已执行的操作(用于撤销)
+///
+/// 失败时按操作记录回滚,不复制整个语法
+pub(crate) fn modify_ops<'b, 'c>(
+    syn: &mut DWSyntax<'b>,
+    input: &'c str,
+    mut changes: Option<&mut Vec<ModifyChange>>
+) -> Result<'c, Vec<EditOp<'b>>> {
+    let modifies = statements(input, syn.ignore_case())?;
+    let mut ops = Vec::with_capacity(modifies.len());
+    for kind in modifies {
+        if let Err(e) = apply(syn, kind, changes.as_deref_mut(), &mut ops) {
+            rollback(syn, &ops);
+            return Err(e);
+        }
+    }
+    Ok(ops)
+}
+
+/// 预演修改
+///
+/// 执行后按操作记录撤销,语法保持不变,返回执行`modify`将产生的变更
+pub fn modify_dry_run<'c>(syn: &mut DWSyntax, input: &'c str) -> Result<'c, Vec<ModifyChange>> {
+    let mut changes = vec![];
+    let ops = modify_ops(syn, input, Some(&mut changes))?;
+    rollback(syn, &ops);
+    Ok(changes)
+}
+
+/// 逆序撤销操作
+fn rollback<'b>(syn: &mut DWSyntax<'b>, ops: &[EditOp<'b>]) {
+    for op in ops.iter().rev() {
+        //SAFETY: 操作由当前语法生成
+        op.apply(syn, true).unwrap();
+    }
+    syn.refresh_index();
+}
+
+/// `Modify`产生的变更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModifyChange {
+    /// 修改参数值
+    Assign {
+        selector: String,
+        /// 原值(不存在时为`None`)
+        old: Option<String>,
+        new: String
+    },
+    /// 创建语法项
+    Create {
+        kind: String,
+        name: Option<String>,
+        /// 是否替换了已有的语法项
+        replaced: bool
+    },
+    /// 删除语法项
+    Destroy {
+        selector: String
     }
-    fn key(input: &str) -> ParseResult<&str> {
+}
+
+enum ModifyKind<'a> {
+    Assign(&'a str, Value<'a>),
+    Create(SumItem<'a>),
+    Destroy(&'a str)
+}
+
+/// 解析`Modify`语句列表,`ignore_case`时关键字忽略大小写
+fn statements<'a>(input: &'a str, ignore_case: bool) -> Result<'a, Vec<ModifyKind<'a>>> {
+    fn key(input: &str) -> ParseResult<'_, &str> {
         take_while1(|c: char| c.is_alphanumeric() || c == '#' || c == '.' || c == '_')(input)
     }
-    fn assign(input: &str) -> ParseResult<ModifyKind> {
-        fn value(input: &str) -> ParseResult<Value> {
-            cut(alt((value::string, value::literal, value::number, value::map, value::list, fail)))(input)
+    fn assign(input: &str) -> ParseResult<'_, ModifyKind<'_>> {
+        fn value(input: &str) -> ParseResult<'_, Value<'_>> {
+            context(
+                "value",
+                cut(alt((value::string, value::literal, value::number, value::map, value::list, fail)))
+            )(input)
         }
         separated_pair(key, delimited(multispace0, tag("="), multispace0), value)
             .map(|(key, val)| ModifyKind::Assign(key, val))
             .parse(input)
     }
-    fn create(input: &str) -> ParseResult<ModifyKind> {
-        #[cfg(feature = "case_insensitive")]
-        let (input, _) = tag_no_case("create")(input)?;
-        #[cfg(not(feature = "case_insensitive"))]
-        let (input, _) = tag("create")(input)?;
+    //关键字按运行时的大小写匹配方式比较
+    let keyword_tag = |name: &'static str| {
+        move |input: &'a str| -> ParseResult<'a, &'a str> {
+            if ignore_case {
+                tag_no_case(name)(input)
+            } else {
+                tag(name)(input)
+            }
+        }
+    };
+    let create = |input: &'a str| -> ParseResult<'a, ModifyKind<'a>> {
+        let (input, _) = keyword_tag("create")(input)?;
         let (input, _) = multispace1(input)?;
-        item.map(ModifyKind::Create).parse(input)
-    }
-    fn destroy(input: &str) -> ParseResult<ModifyKind> {
-        #[cfg(feature = "case_insensitive")]
-        let (input, _) = tag_no_case("destroy")(input)?;
-        #[cfg(not(feature = "case_insensitive"))]
-        let (input, _) = tag("create")(input)?;
+        cut(item.map(ModifyKind::Create)).parse(input)
+    };
+    let destroy = |input: &'a str| -> ParseResult<'a, ModifyKind<'a>> {
+        let (input, _) = keyword_tag("destroy")(input)?;
         let (input, _) = multispace1(input)?;
-        key.map(ModifyKind::Destroy).parse(input)
+        cut(key.map(ModifyKind::Destroy)).parse(input)
+    };
+    /// 不支持的关键字
+    fn keyword(input: &str) -> ParseResult<'_, ModifyKind<'_>> {
+        let (_, keyword) = terminated(alpha1, multispace1)(input)?;
+        Err(NomErr::Failure(VerboseError {
+            errors: vec![(keyword, VerboseErrorKind::Context("keyword"))]
+        }))
     }
     let (_, modifies) = terminated(
         preceded(
             multispace0,
             separated_list1(
                 alt((delimited(multispace0, tag(";"), multispace0), multispace1)),
-                alt((assign, create, destroy, fail))
+                alt((assign, create, destroy, keyword, fail))
             )
         ),
         terminated(delimited(multispace0, opt(tag(";")), multispace0), eof)
     )(input)?;
-    for kind in modifies {
-        match kind {
-            ModifyKind::Assign(selector, value) => {
-                let SelectResult {
-                    root,
-                    key
-                } = select(syn, selector)?;
-                if key.is_empty() {
-                    return Err(NomErr::Error(make_error(selector, ErrorKind::Fail)));
-                }
-                let values = match root {
-                    SelectRoot::DataWindow => &mut syn.datawindow,
-                    SelectRoot::Header => &mut syn.header,
-                    SelectRoot::Summary => &mut syn.summary,
-                    SelectRoot::Footer => &mut syn.footer,
-                    SelectRoot::Detail => &mut syn.detail,
-                    SelectRoot::Item(index) => {
-                        if key == "name" {
-                            syn.items[index].name =
-                                value.as_literal().map(|v| Cow::clone(v).into_owned().into_key());
-                        } else if key == "id" {
-                            syn.items[index].id = value.as_number().map(|v| v as u32);
-                        } else if key == "level" {
-                            syn.items[index].level = value.as_number().map(|v| v as u32);
-                        }
-                        &mut syn.items[index].values
-                    },
-                    SelectRoot::ItemTable => &mut syn.table.values,
-                    SelectRoot::ItemTableColumn(index) => {
-                        if key == "name" {
-                            syn.table.columns[index].name =
-                                value.as_literal().map(|v| Cow::clone(v).into_owned().into_key());
-                        }
-                        &mut syn.table.columns[index].values
+    Ok(modifies)
+}
+
+/// `create`时是否替换该语法项
+fn is_same_item(item: &Item, new_item: &Item) -> bool {
+    item.kind == new_item.kind &&
+        (item.name == new_item.name || item.id == new_item.id) &&
+        item.level == new_item.level
+}
+
+/// 执行单条`Modify`语句
+///
+/// 修改记录到`ops`,传入`changes`时记录变更
+fn apply<'b, 'c>(
+    syn: &mut DWSyntax<'b>,
+    kind: ModifyKind<'c>,
+    changes: Option<&mut Vec<ModifyChange>>,
+    ops: &mut Vec<EditOp<'b>>
+) -> Result<'c, ()> {
+    let mut exec = |syn: &mut DWSyntax<'b>, op: EditOp<'b>| {
+        //SAFETY: 操作由当前语法生成
+        op.apply(syn, false).unwrap();
+        ops.push(op);
+    };
+    match kind {
+        ModifyKind::Assign(selector, value) => {
+            let SelectResult {
+                root,
+                key
+            } = select(syn, selector)?;
+            if key.is_empty() {
+                return Err(NomErr::Error(make_error(selector, ErrorKind::Fail)));
+            }
+            let target = match root {
+                SelectRoot::DataWindow => EditTarget::DataWindow,
+                SelectRoot::Header => EditTarget::Header,
+                SelectRoot::Summary => EditTarget::Summary,
+                SelectRoot::Footer => EditTarget::Footer,
+                SelectRoot::Detail => EditTarget::Detail,
+                SelectRoot::Item(index) => EditTarget::Item(index),
+                SelectRoot::ItemTable => EditTarget::Table,
+                SelectRoot::ItemTableColumn(index) => EditTarget::TableColumn(index)
+            };
+            let values = match root {
+                SelectRoot::DataWindow => &syn.datawindow,
+                SelectRoot::Header => &syn.header,
+                SelectRoot::Summary => &syn.summary,
+                SelectRoot::Footer => &syn.footer,
+                SelectRoot::Detail => &syn.detail,
+                SelectRoot::Item(index) => &syn.items[index].values,
+                SelectRoot::ItemTable => &syn.table.values,
+                SelectRoot::ItemTableColumn(index) => &syn.table.columns[index].values
+            };
+            //保留已有参数名的大小写
+            let key = match find_key(values, &key, syn.ignore_case()) {
+                Some(key) => Cow::clone(key).into_owned().into_key(),
+                None => key.to_string().into_key()
+            };
+            let old = values.get(&key).map(|v| v.to_owned());
+            if let Some(changes) = changes {
+                changes.push(ModifyChange::Assign {
+                    selector: selector.trim().to_owned(),
+                    old: old.as_ref().map(|v| v.to_string()),
+                    new: value.to_string()
+                });
+            }
+            let literal = || value.as_literal().map(|v| Cow::clone(v).into_owned().into_key());
+            match root {
+                //修改名称/ID/级别时替换语法项并重建索引
+                SelectRoot::Item(index) if key == "name" || key == "id" || key == "level" => {
+                    let mut new = syn.items[index].to_owned();
+                    if key == "name" {
+                        new.name = literal();
+                    } else if key == "id" {
+                        new.id = value.as_number().map(|v| v as u32);
+                    } else {
+                        new.level = value.as_number().map(|v| v as u32);
                     }
-                };
-                values.insert(key, value.to_owned());
-            },
-            ModifyKind::Create(new_item) => {
-                let new_item = new_item.to_owned();
-                match new_item {
-                    SumItem::Item(new_item) => {
-                        if new_item.kind == "datawindow" {
-                            syn.datawindow = new_item.values;
-                        } else if new_item.kind == "header" {
-                            syn.header = new_item.values;
-                        } else if new_item.kind == "summary" {
-                            syn.summary = new_item.values;
-                        } else if new_item.kind == "footer" {
-                            syn.footer = new_item.values;
-                        } else if new_item.kind == "detail" {
-                            syn.detail = new_item.values;
-                        } else {
-                            for item in &mut syn.items {
-                                if item.kind == new_item.kind &&
-                                    (item.name == new_item.name || item.id == new_item.id) &&
-                                    item.level == new_item.level
-                                {
-                                    *item = new_item;
-                                    return Ok(());
-                                }
-                            }
-                            syn.items.push(new_item);
-                        }
-                    },
-                    SumItem::ItemData(new_item) => syn.data = new_item,
-                    SumItem::ItemTable(new_item) => syn.table = new_item
+                    new.values.insert(key, value.to_owned());
+                    let old = syn.items[index].to_owned();
+                    exec(syn, EditOp::ReplaceItem {
+                        index,
+                        old,
+                        new
+                    });
+                    syn.refresh_index();
+                },
+                SelectRoot::ItemTableColumn(index) if key == "name" => {
+                    let old = syn.table.columns.iter().map(|v| v.to_owned()).collect::<Vec<_>>();
+                    let mut new = old.iter().map(|v| v.to_owned()).collect::<Vec<_>>();
+                    new[index].name = literal();
+                    new[index].values.insert(key, value.to_owned());
+                    exec(syn, EditOp::SetTableColumns {
+                        old,
+                        new
+                    });
+                    syn.refresh_index();
+                },
+                _ => {
+                    exec(syn, EditOp::SetValue {
+                        target,
+                        key,
+                        old,
+                        new: value.to_owned()
+                    })
                 }
-            },
-            ModifyKind::Destroy(name) => {
-                let SelectResult {
-                    root,
-                    key
-                } = select(syn, name)?;
-                if !key.is_empty() {
-                    return Err(NomErr::Error(make_error(name, ErrorKind::Fail)));
+            }
+        },
+        ModifyKind::Create(new_item) => {
+            let new_item = new_item.to_owned();
+            let (kind, name, replaced) = match &new_item {
+                SumItem::Item(item) => {
+                    let replaced = match item.kind.as_ref() {
+                        "datawindow" | "header" | "summary" | "footer" | "detail" => true,
+                        _ => syn.items.iter().any(|v| is_same_item(v, item))
+                    };
+                    (item.kind.to_string(), item.name.as_ref().map(|v| v.to_string()), replaced)
+                },
+                SumItem::ItemData(_) => ("data".to_owned(), None, !syn.data.is_empty()),
+                SumItem::ItemTable(_) => {
+                    ("table".to_owned(), None, !syn.table.columns.is_empty() || !syn.table.values.is_empty())
                 }
-                match root {
-                    SelectRoot::DataWindow => syn.datawindow.clear(),
-                    SelectRoot::Header => syn.header.clear(),
-                    SelectRoot::Summary => syn.summary.clear(),
-                    SelectRoot::Footer => syn.footer.clear(),
-                    SelectRoot::Detail => syn.detail.clear(),
-                    SelectRoot::Item(index) => {
-                        syn.items.remove(index);
-                    },
-                    SelectRoot::ItemTable => {
-                        syn.table.columns.clear();
-                        syn.table.values.clear();
-                    },
-                    SelectRoot::ItemTableColumn(index) => {
-                        syn.table.columns.remove(index);
-                        //删除引用的字段控件并刷新ID
-                        let mut i = syn.items.len();
-                        while i > 0 {
-                            i -= 1;
-                            let item = &mut syn.items[i];
-                            if item.kind == "column" {
-                                if let Some(id) = item.id {
-                                    //删除控件
-                                    if id as usize == index + 1 {
-                                        #[allow(dropping_references)]
-                                        drop(item);
-                                        syn.items.remove(i);
+            };
+            if let Some(changes) = changes {
+                changes.push(ModifyChange::Create {
+                    kind,
+                    name,
+                    replaced
+                });
+            }
+            match new_item {
+                SumItem::Item(new_item) => {
+                    let target = match new_item.kind.as_ref() {
+                        "datawindow" => Some(EditTarget::DataWindow),
+                        "header" => Some(EditTarget::Header),
+                        "summary" => Some(EditTarget::Summary),
+                        "footer" => Some(EditTarget::Footer),
+                        "detail" => Some(EditTarget::Detail),
+                        _ => None
+                    };
+                    let op = match target {
+                        Some(target) => {
+                            EditOp::SetValues {
+                                target,
+                                old: std::mem::take(values_mut(syn, target).unwrap()),
+                                new: new_item.values
+                            }
+                        },
+                        //替换同名的语法项,不存在时追加
+                        None => {
+                            match syn.items.iter().position(|item| is_same_item(item, &new_item)) {
+                                Some(index) => {
+                                    EditOp::ReplaceItem {
+                                        index,
+                                        old: syn.items[index].to_owned(),
+                                        new: new_item
                                     }
-                                    //刷新ID
-                                    else if id as usize > index + 1 {
-                                        item.id = Some(id - 1);
-                                        item.values.insert("id".into_key(), Value::Number((id - 1) as f64));
+                                },
+                                None => {
+                                    EditOp::InsertItem {
+                                        index: syn.items.len(),
+                                        item: new_item
                                     }
                                 }
                             }
                         }
+                    };
+                    exec(syn, op);
+                },
+                SumItem::ItemData(new_item) => {
+                    let old = std::mem::take(&mut syn.data);
+                    exec(syn, EditOp::SetData {
+                        old,
+                        new: new_item
+                    })
+                },
+                SumItem::ItemTable(new_item) => {
+                    let old = std::mem::take(&mut syn.table.columns);
+                    exec(syn, EditOp::SetTableColumns {
+                        old,
+                        new: new_item.columns
+                    });
+                    let old = std::mem::take(&mut syn.table.values);
+                    exec(syn, EditOp::SetValues {
+                        target: EditTarget::Table,
+                        old,
+                        new: new_item.values
+                    });
+                }
+            }
+            syn.refresh_index();
+        },
+        ModifyKind::Destroy(name) => {
+            let SelectResult {
+                root,
+                key
+            } = select(syn, name)?;
+            if !key.is_empty() {
+                return Err(NomErr::Error(make_error(name, ErrorKind::Fail)));
+            }
+            if let Some(changes) = changes {
+                changes.push(ModifyChange::Destroy {
+                    selector: name.trim().to_owned()
+                });
+            }
+            let clear = |syn: &mut DWSyntax<'b>, target| {
+                let old = std::mem::take(values_mut(syn, target).unwrap());
+                EditOp::SetValues {
+                    target,
+                    old,
+                    new: HashMap::default()
+                }
+            };
+            match root {
+                SelectRoot::DataWindow => {
+                    let op = clear(syn, EditTarget::DataWindow);
+                    exec(syn, op)
+                },
+                SelectRoot::Header => {
+                    let op = clear(syn, EditTarget::Header);
+                    exec(syn, op)
+                },
+                SelectRoot::Summary => {
+                    let op = clear(syn, EditTarget::Summary);
+                    exec(syn, op)
+                },
+                SelectRoot::Footer => {
+                    let op = clear(syn, EditTarget::Footer);
+                    exec(syn, op)
+                },
+                SelectRoot::Detail => {
+                    let op = clear(syn, EditTarget::Detail);
+                    exec(syn, op)
+                },
+                SelectRoot::Item(index) => {
+                    let item = syn.items[index].to_owned();
+                    exec(syn, EditOp::RemoveItem {
+                        index,
+                        item
+                    })
+                },
+                SelectRoot::ItemTable => {
+                    let old = std::mem::take(&mut syn.table.columns);
+                    exec(syn, EditOp::SetTableColumns {
+                        old,
+                        new: vec![]
+                    });
+                    let op = clear(syn, EditTarget::Table);
+                    exec(syn, op);
+                },
+                SelectRoot::ItemTableColumn(index) => {
+                    let old = syn.table.columns.iter().map(|v| v.to_owned()).collect::<Vec<_>>();
+                    let mut new = old.iter().map(|v| v.to_owned()).collect::<Vec<_>>();
+                    new.remove(index);
+                    exec(syn, EditOp::SetTableColumns {
+                        old,
+                        new
+                    });
+                    //删除引用的字段控件并刷新ID
+                    let mut i = syn.items.len();
+                    while i > 0 {
+                        i -= 1;
+                        let item = &syn.items[i];
+                        if item.kind != "column" {
+                            continue;
+                        }
+                        let op = match item.id {
+                            //删除控件
+                            Some(id) if id as usize == index + 1 => {
+                                EditOp::RemoveItem {
+                                    index: i,
+                                    item: item.to_owned()
+                                }
+                            },
+                            //刷新ID
+                            Some(id) if id as usize > index + 1 => {
+                                let mut new = item.to_owned();
+                                new.id = Some(id - 1);
+                                new.values.insert("id".into_key(), Value::Number((id - 1) as f64));
+                                EditOp::ReplaceItem {
+                                    index: i,
+                                    old: item.to_owned(),
+                                    new
+                                }
+                            },
+                            _ => continue
+                        };
+                        exec(syn, op);
                     }
                 }
             }
+            syn.refresh_index();
         }
     }
     Ok(())
 }
 
+/// `Modify`错误
+///
+/// `Display`输出与PB的`DataWindow::Modify`返回值一致,如:`Line 1  Column 12: incorrect syntax.`
+///
+/// 行号和列号从1开始
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModifyError {
+    /// 语法错误
+    Syntax {
+        line: usize,
+        column: usize
+    },
+    /// 参数值解析失败
+    InvalidValue {
+        line: usize,
+        column: usize
+    },
+    /// 不支持的关键字
+    UnknownKeyword {
+        line: usize,
+        column: usize,
+        keyword: String
+    },
+    /// 控件/语法项不存在
+    UnknownObject {
+        line: usize,
+        column: usize,
+        name: String
+    },
+    /// `table`字段不存在
+    UnknownColumn {
+        line: usize,
+        column: usize,
+        name: String
+    },
+    /// 分组级别不存在
+    UnknownGroup {
+        line: usize,
+        column: usize,
+        level: String
+    }
+}
+
+impl ModifyError {
+    /// 从解析错误转换
+    ///
+    /// `input`为完整的`Modify`参数
+    pub fn from_error(input: &str, err: Error) -> Self {
+        let errors = match err {
+            NomErr::Error(e) | NomErr::Failure(e) => e.errors,
+            NomErr::Incomplete(_) => vec![]
+        };
+        let at = errors.first().map(|(v, _)| *v).unwrap_or_default();
+        let (line, column) = position(input, at);
+        let name = || at.chars().take_while(|c| c.is_alphanumeric() || *c == '#' || *c == '_').collect();
+        let kind = errors.iter().find_map(|(_, kind)| {
+            match kind {
+                VerboseErrorKind::Context(v) => Some(*v),
+                _ => None
+            }
+        });
+        match kind {
+            Some("object") => {
+                ModifyError::UnknownObject {
+                    line,
+                    column,
+                    name: name()
+                }
+            },
+            Some("column") => {
+                ModifyError::UnknownColumn {
+                    line,
+                    column,
+                    name: name()
+                }
+            },
+            Some("group") => {
+                ModifyError::UnknownGroup {
+                    line,
+                    column,
+                    level: name()
+                }
+            },
+            Some("keyword") => {
+                ModifyError::UnknownKeyword {
+                    line,
+                    column,
+                    keyword: name()
+                }
+            },
+            Some("value") => {
+                ModifyError::InvalidValue {
+                    line,
+                    column
+                }
+            },
+            _ => {
+                ModifyError::Syntax {
+                    line,
+                    column
+                }
+            }
+        }
+    }
+
+    /// 错误位置(行,列)
+    pub fn position(&self) -> (usize, usize) {
+        match self {
+            ModifyError::Syntax {
+                line,
+                column
+            } |
+            ModifyError::InvalidValue {
+                line,
+                column
+            } |
+            ModifyError::UnknownKeyword {
+                line,
+                column,
+                ..
+            } |
+            ModifyError::UnknownObject {
+                line,
+                column,
+                ..
+            } |
+            ModifyError::UnknownColumn {
+                line,
+                column,
+                ..
+            } |
+            ModifyError::UnknownGroup {
+                line,
+                column,
+                ..
+            } => (*line, *column)
+        }
+    }
+}
+
+impl std::fmt::Display for ModifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (line, column) = self.position();
+        match self {
+            ModifyError::UnknownObject {
+                ..
+            } |
+            ModifyError::UnknownColumn {
+                ..
+            } |
+            ModifyError::UnknownGroup {
+                ..
+            } => write!(f, "Line {line}  Column {column}: Unknown object name."),
+            _ => write!(f, "Line {line}  Column {column}: incorrect syntax.")
+        }
+    }
+}
+
+impl std::error::Error for ModifyError {}
+
+/// 计算`at`在`input`中的位置(行,列)
+///
+/// `at`不是`input`的子串时返回输入末尾的位置
+fn position(input: &str, at: &str) -> (usize, usize) {
+    let start = input.as_ptr() as usize;
+    let offset =
+        (at.as_ptr() as usize).checked_sub(start).filter(|v| *v <= input.len()).unwrap_or(input.len());
+    let offset = (0..=offset).rev().find(|v| input.is_char_boundary(*v)).unwrap_or_default();
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    (line, column)
+}
+
 /// 选取结果
 struct SelectResult<'a> {
     root: SelectRoot,
@@ -216,69 +651,70 @@ fn select<'a, 'b>(syn: &DWSyntax<'a>, input: &'b str) -> Result<'b, SelectResult
         preceded(multispace0, separated_list1(tag("."), alt((name, index)).map(IntoKey::into_key))),
         terminated(multispace0, eof)
     )(input)?;
-    if selector.len() == 0 {
+    if selector.is_empty() {
         return Err(NomErr::Error(make_error(input, ErrorKind::Eof)));
     }
     let mut selector = selector.into_iter();
     let mut root = None;
     let mut prefix = String::new();
+    //关键字比较,运行时忽略大小写时按忽略大小写匹配
+    let ignore_case = syn.ignore_case();
+    let is = |name: &Key, keyword: &str| {
+        if ignore_case {
+            name.eq_ignore_ascii_case(keyword)
+        } else {
+            key_str(name) == keyword
+        }
+    };
 
     //选取参数列表
     let name = selector.next().unwrap();
     //datawindow名称下的语法项
-    if name == "datawindow" {
-        //TODO
-        //- tree
+    if is(&name, "datawindow") {
         if selector.len() == 0 {
             return Err(NomErr::Error(make_error(input, ErrorKind::Eof)));
         }
         let name = selector.next().unwrap();
-        if name == "header" {
+        if is(&name, "header") {
             root = Some(SelectRoot::Header);
             //datawindow.header.<group #>
             if selector.len() >= 1 {
                 let name = selector.next().unwrap();
                 if let Ok(level) = name.parse() {
-                    if let Some((index, _)) = find_group(&syn.items, level) {
+                    if let Some((index, _)) = find_group(syn, level) {
                         root = Some(SelectRoot::Item(index));
                         prefix = "header".to_owned();
                     } else {
-                        return Err(NomErr::Error(make_error(
-                            //SAFETY
-                            name.borrowed().unwrap(),
-                            ErrorKind::Fail
-                        )));
+                        //SAFETY
+                        return Err(unknown(name.borrowed().unwrap(), "group"));
                     }
                 } else {
                     prefix = name.as_ref().to_owned();
                 }
             }
-        } else if name == "footer" {
+        } else if is(&name, "footer") {
             root = Some(SelectRoot::Footer);
             //datawindow.footer.<group #>
             if selector.len() >= 1 {
                 let name = selector.next().unwrap();
                 if let Ok(level) = name.parse() {
-                    if let Some((index, _)) = find_group(&syn.items, level) {
+                    if let Some((index, _)) = find_group(syn, level) {
                         root = Some(SelectRoot::Item(index));
                         prefix = "footer".to_owned();
                     } else {
-                        return Err(NomErr::Error(make_error(
-                            //SAFETY
-                            name.borrowed().unwrap(),
-                            ErrorKind::Fail
-                        )));
+                        //SAFETY
+                        return Err(unknown(name.borrowed().unwrap(), "group"));
                     }
                 } else {
                     prefix = name.as_ref().to_owned();
                 }
             }
-        } else if name == "trailer" {
+        } else if is(&name, "trailer") {
             let mut found = false;
             //datawindow.trailer.<group #>
             if selector.len() >= 1 {
                 if let Ok(level) = selector.next().unwrap().parse() {
-                    if let Some((index, _)) = find_group(&syn.items, level) {
+                    if let Some((index, _)) = find_group(syn, level) {
                         root = Some(SelectRoot::Item(index));
                         prefix = "trailer".to_owned();
                         found = true;
@@ -286,60 +722,55 @@ fn select<'a, 'b>(syn: &DWSyntax<'a>, input: &'b str) -> Result<'b, SelectResult
                 }
             }
             if !found {
-                return Err(NomErr::Error(make_error(
-                    //SAFETY
-                    name.borrowed().unwrap(),
-                    ErrorKind::Fail
-                )));
+                //SAFETY
+                return Err(unknown(name.borrowed().unwrap(), "group"));
             }
-        } else if name == "group" {
+        } else if is(&name, "group") {
             let mut found = false;
             //datawindow.group.<group #>
             if selector.len() >= 1 {
                 if let Ok(level) = selector.next().unwrap().parse() {
-                    if let Some((index, _)) = find_group(&syn.items, level) {
+                    if let Some((index, _)) = find_group(syn, level) {
                         root = Some(SelectRoot::Item(index));
                         found = true;
                     }
                 }
             }
             if !found {
-                return Err(NomErr::Error(make_error(
-                    //SAFETY
-                    name.borrowed().unwrap(),
-                    ErrorKind::Fail
-                )));
+                //SAFETY
+                return Err(unknown(name.borrowed().unwrap(), "group"));
             }
-        } else if name == "summary" {
+        } else if is(&name, "tree") {
+            //datawindow.tree.<property>
+            match syn.items.iter().position(|item| item.kind == "tree") {
+                Some(index) => root = Some(SelectRoot::Item(index)),
+                //SAFETY
+                None => return Err(unknown(name.borrowed().unwrap(), "object"))
+            }
+        } else if is(&name, "summary") {
             root = Some(SelectRoot::Summary);
-        } else if name == "detail" {
+        } else if is(&name, "detail") {
             root = Some(SelectRoot::Detail);
-        } else if name == "table" {
+        } else if is(&name, "table") {
             root = Some(SelectRoot::ItemTable);
             //- datawindow.table.column.<column #>
             //- datawindow.table.column.<column name>
             if selector.len() >= 2 {
                 let name = selector.next().unwrap();
-                if name == "column" {
+                if is(&name, "column") {
                     let name = selector.next().unwrap();
                     if let Ok(idx) = name.parse::<usize>() {
                         if idx > 0 && idx <= syn.table.columns.len() {
                             root = Some(SelectRoot::ItemTableColumn(idx - 1));
                         } else {
-                            return Err(NomErr::Error(make_error(
-                                //SAFETY
-                                name.borrowed().unwrap(),
-                                ErrorKind::Fail
-                            )));
+                            //SAFETY
+                            return Err(unknown(name.borrowed().unwrap(), "column"));
                         }
-                    } else if let Some((index, _)) = find_table_column(&syn.table.columns, name.as_ref()) {
+                    } else if let Some((index, _)) = find_table_column(syn, name.as_ref()) {
                         root = Some(SelectRoot::ItemTableColumn(index));
                     } else {
-                        return Err(NomErr::Error(make_error(
-                            //SAFETY
-                            name.borrowed().unwrap(),
-                            ErrorKind::Fail
-                        )));
+                        //SAFETY
+                        return Err(unknown(name.borrowed().unwrap(), "column"));
                     }
                 } else {
                     prefix = name.as_ref().to_owned();
@@ -352,40 +783,32 @@ fn select<'a, 'b>(syn: &DWSyntax<'a>, input: &'b str) -> Result<'b, SelectResult
     }
     //具名的普通语法项
     else {
-        match find_item(&syn.items, name.as_ref()) {
+        match find_item(syn, name.as_ref()) {
             Some((index, item)) => {
                 root = Some(SelectRoot::Item(index));
                 //特殊处理字段属性
                 //- col.coltype
                 //- col.dbname
-                if selector.len() == 1 && item.kind == "column" && item.id.is_some() {
+                if let (1, true, Some(id)) = (selector.len(), item.kind == "column", item.id) {
                     let name = selector.next().unwrap();
                     prefix = name.as_ref().to_owned();
-                    if name == "coltype" || name == "dbname" {
-                        let idx = item.id.unwrap() as usize;
+                    if is(&name, "coltype") || is(&name, "dbname") {
+                        let idx = id as usize;
                         if idx > 0 && idx <= syn.table.columns.len() {
                             root = Some(SelectRoot::ItemTableColumn(idx - 1));
                         } else {
-                            return Err(NomErr::Error(make_error(
-                                //SAFETY
-                                name.borrowed().unwrap(),
-                                ErrorKind::Fail
-                            )));
+                            //SAFETY
+                            return Err(unknown(name.borrowed().unwrap(), "column"));
                         }
                         //alias
-                        if name == "coltype" {
+                        if is(&name, "coltype") {
                             prefix = "type".to_owned();
                         }
                     }
                 }
             },
-            None => {
-                return Err(NomErr::Error(make_error(
-                    //SAFETY
-                    name.borrowed().unwrap(),
-                    ErrorKind::Fail
-                )));
-            }
+            //SAFETY
+            None => return Err(unknown(name.borrowed().unwrap(), "object"))
         }
     }
     let root = match root {
@@ -396,12 +819,12 @@ fn select<'a, 'b>(syn: &DWSyntax<'a>, input: &'b str) -> Result<'b, SelectResult
     let key = selector
         .fold(prefix, |mut result, item| {
             if !result.is_empty() {
-                result.push_str(".");
+                result.push('.');
             }
             result.push_str(item.as_ref());
             result
-        })
-        .into_key();
+        });
+    let key = if ignore_case { key.to_ascii_lowercase() } else { key }.into_key();
 
     Ok(SelectResult {
         root,
@@ -409,72 +832,69 @@ fn select<'a, 'b>(syn: &DWSyntax<'a>, input: &'b str) -> Result<'b, SelectResult
     })
 }
 
+/// 查找参数名
+///
+/// 运行时忽略大小写且未精确匹配时逐个比较
+fn find_key<'a, 'b>(
+    values: &'a HashMap<Key<'b>, Value<'b>>,
+    key: &Key<'b>,
+    ignore_case: bool
+) -> Option<&'a Key<'b>> {
+    match values.get_key_value(key) {
+        //启用`case_insensitive`特性时键本身忽略大小写,运行时区分大小写需比较原始键名
+        Some((found, _)) if ignore_case || key_str(found) == key_str(key) => Some(found),
+        Some(_) => None,
+        None if ignore_case => values.keys().find(|v| v.eq_ignore_ascii_case(key)),
+        None => None
+    }
+}
+
+/// 键的原始名称(不受`case_insensitive`特性的比较方式影响)
+fn key_str<'k>(key: &'k Key) -> &'k str { key }
+
+/// 选取失败的错误
+///
+/// `kind`为`object`/`column`/`group`
+fn unknown<'a>(name: &'a str, kind: &'static str) -> Error<'a> {
+    NomErr::Error(VerboseError {
+        errors: vec![(name, VerboseErrorKind::Context(kind))]
+    })
+}
+
 /// 解析参数名
-fn name(input: &str) -> ParseResult<&str> {
+fn name(input: &str) -> ParseResult<'_, &str> {
     //必须是字母或'#'开头
     satisfy(|c| c.is_alphabetic() || c == '#')(input)?;
     take_while1(|c: char| c.is_alphanumeric() || c == '#' || c == '_')(input)
 }
 
 /// 解析索引值
-fn index(input: &str) -> ParseResult<&str> { take_while1(|c: char| c.is_numeric())(input) }
+fn index(input: &str) -> ParseResult<'_, &str> { take_while1(|c: char| c.is_numeric())(input) }
 
 /// 查找指定`table`字段语法项
 fn find_table_column<'a, 'b: 'a>(
-    items: &'a Vec<ItemTableColumn<'b>>,
+    syn: &'a DWSyntax<'b>,
     name: &str
 ) -> Option<(usize, &'a ItemTableColumn<'b>)> {
-    for (index, item) in items.iter().enumerate() {
-        if let Some(v) = &item.name {
-            if *v == name {
-                return Some((index, item));
-            }
-        }
-    }
-    None
+    syn.table_column_position(name).map(|index| (index, &syn.table.columns[index]))
 }
 
 /// 查找指定`level`的分组`group`语法项
-fn find_group<'a, 'b: 'a>(items: &'a Vec<Item<'b>>, level: f64) -> Option<(usize, &'a Item<'b>)> {
-    for (index, item) in items.iter().enumerate() {
-        if item.kind == "group" {
-            if let Some(v) = item.values.get(&"level".into_key()) {
-                if let Value::Number(v) = v {
-                    if *v == level {
-                        return Some((index, item));
-                    }
-                }
-            }
-        }
-    }
-    None
+fn find_group<'a, 'b: 'a>(syn: &'a DWSyntax<'b>, level: f64) -> Option<(usize, &'a Item<'b>)> {
+    syn.group_position(level).map(|index| (index, &syn.items[index]))
 }
 
 /// 查找普通语法项
-fn find_item<'a, 'b: 'a>(items: &'a Vec<Item<'b>>, name: &str) -> Option<(usize, &'a Item<'b>)> {
-    //通过ID查找
-    if name.starts_with("#") {
-        if let Ok(id) = name[1..].parse() {
-            for (index, item) in items.iter().enumerate() {
-                if let Some(v) = item.id {
-                    if v == id {
-                        return Some((index, item));
-                    }
-                }
-            }
-        }
-    }
-    //通过名称查找
-    else {
-        for (index, item) in items.iter().enumerate() {
-            if let Some(v) = &item.name {
-                if *v == name {
-                    return Some((index, item));
-                }
-            }
-        }
-    }
-    None
+///
+/// 已创建索引时优先使用索引,未命中或索引过期时线性查找
+fn find_item<'a, 'b: 'a>(syn: &'a DWSyntax<'b>, name: &str) -> Option<(usize, &'a Item<'b>)> {
+    let index = match name.strip_prefix('#') {
+        //通过ID查找
+        Some(id) => syn.id_position(id.parse().ok()?),
+        //通过名称查找
+        None => syn.item_position(name)
+    }?;
+    Some((index, &syn.items[index]))
 }
 
 #[cfg(test)]
@@ -496,6 +916,7 @@ mod tests {
         group(level=2 trailer.height=76 by= (  "col1",   'col2' ))
         column(band=detail id=1 name=col1 alignment="1" tabsequence=32766 border="0" color="33554432")
         compute(band=trailer.5 alignment="2"name=compute_1 expression="count(jw_no for group 5 )+~"件~""x1="0"  )
+        tree(indent=64 showconnectlines=yes defaultexpandtolevel=1)
         "#;
         let dw = test_parser(dwsyn, parse);
 
@@ -588,6 +1009,13 @@ mod tests {
         let key = "compute_1.expression";
         let value = check_result(key, find(&dw, key));
         assert_eq!(value, Some(&Value::DoubleQuotedString("count(jw_no for group 5 )+~\"件~\"".into())));
+
+        #[cfg(feature = "case_insensitive")]
+        let key = "DataWindow.Tree.ShowConnectLines";
+        #[cfg(not(feature = "case_insensitive"))]
+        let key = "datawindow.tree.showconnectlines";
+        let value = check_result(key, find(&dw, key));
+        assert_eq!(value, Some(&Value::Literal("yes".into())));
     }
 
     #[test]
@@ -603,6 +1031,7 @@ mod tests {
         group(level=2 trailer.height=76 by= (  "col1",   'col2' ))
         column(band=detail id=1 name=col1 alignment="1" tabsequence=32766 border="0" color="33554432")
         compute(band=trailer.5 alignment="2"name=compute_1 expression="count(jw_no for group 5 )+~"件~""x1="0"  )
+        tree(indent=64 showconnectlines=yes defaultexpandtolevel=1)
         "#;
         let mut dw = test_parser(dwsyn, parse);
 
@@ -624,6 +1053,140 @@ mod tests {
         let value = check_result(key, find(&dw, key));
         assert_eq!(value, Some(&Value::Number(12345.0)));
 
+        #[cfg(feature = "case_insensitive")]
+        let modifier = "DataWindow.Tree.Indent=80";
+        #[cfg(not(feature = "case_insensitive"))]
+        let modifier = "datawindow.tree.indent=80";
+        check_result(modifier, modify(&mut dw, modifier));
+        assert_eq!(dw.tree_view().map(|v| v.indent), Some(80));
+
         //println!("{dw}");
     }
+
+    #[test]
+    fn test_modify_error() {
+        let dwsyn = r#"
+        release 12.5;datawindow(num=1)
+        table(column=(type=char(80) name=col1 dbname="col1"))
+        group(level=1 trailer.height=76 by=("col1"))
+        column(band=detail id=1 name=col1 x="0")
+        "#;
+        let mut dw = test_parser(dwsyn, parse);
+        let mut error =
+            |modifier: &str| ModifyError::from_error(modifier, modify(&mut dw, modifier).unwrap_err());
+
+        let err = error("datawindow.num=");
+        assert_eq!(err, ModifyError::InvalidValue {
+            line: 1,
+            column: 16
+        });
+        assert_eq!(err.to_string(), "Line 1  Column 16: incorrect syntax.");
+        let err = error("datawindow.num=1 nosuch.x=1");
+        assert_eq!(err, ModifyError::UnknownObject {
+            line: 1,
+            column: 18,
+            name: "nosuch".to_owned()
+        });
+        assert_eq!(err.to_string(), "Line 1  Column 18: Unknown object name.");
+        assert!(matches!(
+            error("datawindow.table.column.abc.type=long"),
+            ModifyError::UnknownColumn { column: 25, .. }
+        ));
+        assert!(matches!(
+            error("datawindow.header.9.height=1"),
+            ModifyError::UnknownGroup { column: 19, .. }
+        ));
+        assert!(matches!(
+            error("col1.x=1\nfoo col1"),
+            ModifyError::UnknownKeyword { line: 2, column: 1, .. }
+        ));
+        assert!(matches!(error("datawindow.num=1 ;;"), ModifyError::Syntax { line: 1, .. }));
+
+        check_result("destroy col1", modify(&mut dw, "destroy col1"));
+        assert!(dw.items.iter().all(|item| item.kind != "column"));
+    }
+
+    #[test]
+    fn test_modify_atomic() {
+        let dwsyn = r#"
+        release 12.5;datawindow(num=1)
+        table(column=(type=char(80) name=col1 dbname="col1"))
+        column(band=detail id=1 name=col1 x="0")
+        text(band=header name=col1_t x="0")
+        "#;
+        let mut dw = test_parser(dwsyn, parse);
+        let syntax = dw.to_string();
+        assert!(modify(&mut dw, "datawindow.num=2 col1.x='10' nosuch.x=1").is_err());
+        assert_eq!(dw.to_string(), syntax);
+        let modifier = "destroy datawindow.table.column.col1 create table(column=(type=long name=id)) destroy col1_t";
+        let mut copy = dw.to_owned();
+        check_result(modifier, modify(&mut copy, modifier));
+        assert_eq!(copy.items.len(), 0);
+        assert!(modify(&mut dw, &format!("{modifier} col1_t.x=1")).is_err());
+        assert_eq!(dw.to_string(), syntax);
+
+        //替换已有的语法项后继续执行后续语句
+        let modifier = "create text(band=header name=col1_t x=\"5\") col1.x='10'";
+        let changes = check_result(modifier, modify_dry_run(&mut dw, modifier));
+        assert_eq!(changes, vec![
+            ModifyChange::Create {
+                kind: "text".to_owned(),
+                name: Some("col1_t".to_owned()),
+                replaced: true
+            },
+            ModifyChange::Assign {
+                selector: "col1.x".to_owned(),
+                old: Some("0".to_owned()),
+                new: "10".to_owned()
+            },
+        ]);
+        assert_eq!(dw.to_string(), syntax);
+        check_result(modifier, modify(&mut dw, modifier));
+        assert_eq!(dw.items.iter().filter(|item| item.kind == "text").count(), 1);
+        let value = check_result("col1.x", find(&dw, "col1.x"));
+        assert_eq!(value.and_then(|v| v.as_str()), Some("10"));
+    }
+
+    #[test]
+    fn test_case_insensitive_keyword() {
+        let mut dw = test_parser("release 19;\r\ntext(name=t1 x=1)", parse);
+        dw.set_case_insensitive(false);
+        assert!(modify(&mut dw, "CREATE text(name=t2 x=2)").is_err());
+        assert!(modify(&mut dw, "DESTROY t1").is_err());
+        assert_eq!(dw.items.len(), 1);
+        dw.set_case_insensitive(true);
+        let modifier = "Destroy T1; CREATE text(name=t2 x=2)";
+        check_result(modifier, modify(&mut dw, modifier));
+        let names: Vec<_> = dw.items.iter().filter_map(|v| v.name.as_ref().map(|v| v.to_string())).collect();
+        assert_eq!(names, vec!["t2"]);
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let dwsyn = r#"
+        release 12.5;datawindow(num=1)
+        table(column=(type=char(80) name=Col1 dbname="col1"))
+        group(level=1 by=("col1"))
+        column(band=detail id=1 name=Col1 Text.X="0")
+        "#;
+        let mut dw = test_parser(dwsyn, parse);
+        assert_eq!(dw.is_case_insensitive(), cfg!(feature = "case_insensitive"));
+        //运行时设置优先于特性
+        dw.set_case_insensitive(false);
+        assert!(find(&dw, "DataWindow.Num").is_err());
+        assert!(find(&dw, "col1.text.x").is_err());
+        assert!(find(&dw, "Col1.Text.X").is_ok());
+        dw.set_case_insensitive(true);
+        let value = check_result("DataWindow.Num", find(&dw, "DataWindow.Num"));
+        assert_eq!(value.and_then(|v| v.as_number()), Some(1.));
+        let value = check_result("col1.text.x", find(&dw, "col1.text.x"));
+        assert_eq!(value.and_then(|v| v.as_str()), Some("0"));
+        check_result("col1.TEXT.X='5'", modify(&mut dw, "col1.TEXT.X='5' DataWindow.Group.1.Level=1"));
+        let modifier = "DataWindow.Table.Column.COL1.DbName='c'";
+        check_result(modifier, modify(&mut dw, modifier));
+        let item = dw.item_by_name("COL1").unwrap();
+        assert_eq!(item.values.get(&"Text.X".into_key()).and_then(|v| v.as_str()), Some("5"));
+        let column = dw.table_column("col1").unwrap();
+        assert_eq!(column.values.get(&"dbname".into_key()).and_then(|v| v.as_str()), Some("c"));
+    }
 }
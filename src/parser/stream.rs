@@ -0,0 +1,430 @@
+use super::{
+    item::{SumItem, data, item},
+    *
+};
+use std::{collections::VecDeque, io::BufRead, result::Result as StdResult};
+
+/// `data`项默认每批输出的最大字节数
+const DATA_CHUNK_SIZE: usize = 64 * 1024;
+/// 每次读取的最小字节数
+const READ_SIZE: usize = 8 * 1024;
+
+/// 增量解析事件
+#[derive(Debug, PartialEq)]
+pub enum SyntaxEvent {
+    /// `.srd`文件头
+    Header {
+        name: Option<String>,
+        comment: Option<String>
+    },
+    /// `release`版本号
+    Version(f64),
+    /// 语法项(包括`datawindow`/`header`/`summary`/`footer`/`detail`)
+    Item(Item<'static>),
+    /// `table`语法项
+    Table(ItemTable<'static>),
+    /// `data`项的一批值,一个`data`项可能分为多批输出
+    Data(Vec<Value<'static>>)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Header,
+    Items,
+    Data,
+    Done
+}
+
+/// 增量语法解析器
+///
+/// 从`BufRead`中逐个读取并解析语法项,内存中只保留当前语法项,
+/// `data`项按批输出,适用于扫描很大的`.srd`文件
+///
+/// # Example
+///
+/// ```
+/// use dwparser::{SyntaxEvent, SyntaxReader};
+///
+/// let input = "release 19;\r\ncompute(name=c1 expression=\"1+1\")\r\ndata(1, 2, )";
+/// let mut computes = vec![];
+/// for event in SyntaxReader::new(input.as_bytes()) {
+///     if let SyntaxEvent::Item(item) = event.unwrap() {
+///         if item.kind == "compute" {
+///             computes.push(item.name.unwrap().to_string());
+///         }
+///     }
+/// }
+/// assert_eq!(computes, vec!["c1"]);
+/// ```
+pub struct SyntaxReader<R> {
+    reader: R,
+    /// 已读取但未解析的内容
+    buf: String,
+    /// 不完整的UTF-8字节
+    pending: Vec<u8>,
+    events: VecDeque<SyntaxEvent>,
+    state: State,
+    eof: bool,
+    chunk_size: usize
+}
+
+impl<R: BufRead> SyntaxReader<R> {
+    pub fn new(reader: R) -> Self {
+        SyntaxReader {
+            reader,
+            buf: String::new(),
+            pending: vec![],
+            events: VecDeque::new(),
+            state: State::Header,
+            eof: false,
+            chunk_size: DATA_CHUNK_SIZE
+        }
+    }
+
+    /// 设置`data`项每批输出的最大字节数
+    pub fn with_chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    /// 读取一块数据(至少`READ_SIZE`字节或直到结束),返回是否读取到内容
+    fn fill(&mut self) -> StdResult<bool, String> {
+        let mut read = 0;
+        while !self.eof && read < READ_SIZE {
+            let chunk = self.reader.fill_buf().map_err(|e| e.to_string())?;
+            if chunk.is_empty() {
+                self.eof = true;
+                if !self.pending.is_empty() {
+                    return Err("invalid utf-8 sequence at end of input".to_owned());
+                }
+                break;
+            }
+            self.pending.extend_from_slice(chunk);
+            let len = chunk.len();
+            self.reader.consume(len);
+            read += len;
+            let valid = match std::str::from_utf8(&self.pending) {
+                Ok(s) => s.len(),
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(e) => return Err(format!("invalid utf-8 sequence: {e}"))
+            };
+            let text = std::str::from_utf8(&self.pending[..valid]).map_err(|e| e.to_string())?;
+            if self.buf.is_empty() && self.state == State::Header {
+                self.buf.push_str(text.trim_start_matches('\u{feff}'));
+            } else {
+                self.buf.push_str(text);
+            }
+            self.pending.drain(..valid);
+        }
+        Ok(read > 0)
+    }
+
+    /// 读取并解析下一个事件
+    fn advance(&mut self) -> StdResult<(), String> {
+        loop {
+            match self.state {
+                State::Header => {
+                    let parsed = match pair(srd_file_header, version)(&self.buf) {
+                        Ok((rest, ((name, comment), version))) => {
+                            let header = SyntaxEvent::Header {
+                                name: name.map(|v| v.into_owned()),
+                                comment: comment.map(|v| v.into_owned())
+                            };
+                            Ok((self.buf.len() - rest.len(), header, version))
+                        },
+                        Err(e) => Err(friendly_error(&self.buf, e))
+                    };
+                    match parsed {
+                        Ok((consumed, header, version)) => {
+                            self.buf.drain(..consumed);
+                            self.events.push_back(header);
+                            self.events.push_back(SyntaxEvent::Version(version));
+                            self.state = State::Items;
+                            return Ok(());
+                        },
+                        //内容不完整时继续读取
+                        Err(e) => {
+                            if !self.fill()? {
+                                return Err(e);
+                            }
+                        }
+                    }
+                },
+                State::Items => {
+                    let start = self.buf.len() - self.buf.trim_start().len();
+                    self.buf.drain(..start);
+                    if self.buf.is_empty() {
+                        if !self.fill()? {
+                            self.state = State::Done;
+                            return Ok(());
+                        }
+                        continue;
+                    }
+                    let kind_len =
+                        self.buf.find(|c: char| !(c.is_alphabetic() || c == '.')).unwrap_or(self.buf.len());
+                    let paren = self.buf[kind_len..].find(|c: char| !c.is_whitespace()).map(|i| kind_len + i);
+                    let paren = match paren {
+                        Some(i) if kind_len > 0 && self.buf[i..].starts_with('(') => i,
+                        Some(_) => return Err(format!("invalid item: {}", preview(&self.buf))),
+                        None => {
+                            if !self.fill()? {
+                                return Err(format!("unexpected end of input: {}", preview(&self.buf)));
+                            }
+                            continue;
+                        }
+                    };
+                    if &self.buf[..kind_len] == "data" {
+                        self.buf.drain(..paren + 1);
+                        self.state = State::Data;
+                        continue;
+                    }
+                    let end = match item_end(&self.buf[paren..]) {
+                        Some(end) => paren + end,
+                        None => {
+                            if !self.fill()? {
+                                return Err(format!("unexpected end of input: {}", preview(&self.buf)));
+                            }
+                            continue;
+                        }
+                    };
+                    let input = &self.buf[..end];
+                    let event = match all_consuming(item)(input) {
+                        Ok((_, SumItem::Item(item))) => SyntaxEvent::Item(item.to_owned()),
+                        Ok((_, SumItem::ItemTable(table))) => SyntaxEvent::Table(table.to_owned()),
+                        Ok((_, SumItem::ItemData(_))) => unreachable!(),
+                        Err(e) => return Err(friendly_error(input, e))
+                    };
+                    self.buf.drain(..end);
+                    self.events.push_back(event);
+                    return Ok(());
+                },
+                State::Data => {
+                    let (last_comma, close) = data_boundary(&self.buf, self.chunk_size);
+                    let end = match (close, last_comma) {
+                        (Some(close), _) => close,
+                        (None, Some(comma)) if self.buf.len() >= self.chunk_size => comma + 1,
+                        _ => {
+                            if !self.fill()? {
+                                return Err(format!("unterminated data: {}", preview(&self.buf)));
+                            }
+                            continue;
+                        }
+                    };
+                    let input = format!("({})", &self.buf[..end]);
+                    let values = match all_consuming(data)(&input) {
+                        Ok((_, SumItem::ItemData(values))) => values.iter().map(|v| v.to_owned()).collect(),
+                        Ok(_) => unreachable!(),
+                        Err(e) => return Err(friendly_error(&input, e))
+                    };
+                    if close.is_some() {
+                        self.buf.drain(..end + 1);
+                        self.state = State::Items;
+                    } else {
+                        self.buf.drain(..end);
+                    }
+                    self.events.push_back(SyntaxEvent::Data(values));
+                    return Ok(());
+                },
+                State::Done => return Ok(())
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SyntaxReader<R> {
+    type Item = StdResult<SyntaxEvent, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.events.is_empty() && self.state != State::Done {
+            if let Err(e) = self.advance() {
+                self.state = State::Done;
+                return Some(Err(e));
+            }
+        }
+        self.events.pop_front().map(Ok)
+    }
+}
+
+/// 查找与开头`(`匹配的`)`之后的位置,忽略字符串中的括号
+pub(super) fn item_end(input: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in input.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '~' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            },
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 查找`data`项中字符串之外的最后一个`,`及结束的`)`,超过`limit`字节且已找到`,`时停止
+fn data_boundary(input: &str, limit: usize) -> (Option<usize>, Option<usize>) {
+    let mut last_comma = None;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in input.char_indices() {
+        if i >= limit && last_comma.is_some() {
+            break;
+        }
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '~' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            ',' => last_comma = Some(i),
+            ')' => return (last_comma, Some(i)),
+            _ => {}
+        }
+    }
+    (last_comma, None)
+}
+
+/// 错误信息中的内容预览
+fn preview(input: &str) -> &str {
+    let end = input.char_indices().nth(64).map(|(i, _)| i).unwrap_or(input.len());
+    &input[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    #[test]
+    fn test_stream() {
+        let input = include_str!("../../assets/big_file.srd").replace(
+            "htmltable(",
+            "\r\ndata(1, \"a,)~\"\", null 2020-01-01, 'b' , 3, 4, 5, 6, 7,\r\n 8, )\r\nhtmltable("
+        );
+        let syn = DWSyntax::parse(input.trim_start_matches('\u{feff}')).unwrap();
+        //使用很小的缓冲区测试跨块读取(包括拆分UTF-8字符)
+        let reader = SyntaxReader::new(BufReader::with_capacity(7, input.as_bytes())).with_chunk_size(10);
+        let mut items = vec![];
+        let mut data = vec![];
+        let mut batches = 0;
+        let mut version = 0.;
+        for event in reader {
+            match event.unwrap() {
+                SyntaxEvent::Header {
+                    name, ..
+                } => assert_eq!(name.as_deref(), syn.name.as_deref()),
+                SyntaxEvent::Version(v) => version = v,
+                SyntaxEvent::Item(item) => items.push(item),
+                SyntaxEvent::Table(table) => assert_eq!(table, syn.table),
+                SyntaxEvent::Data(values) => {
+                    batches += 1;
+                    data.extend(values);
+                }
+            }
+        }
+        assert_eq!(version, syn.version);
+        let bands = ["datawindow", "header", "summary", "footer", "detail"];
+        items.retain(|item| !bands.iter().any(|v| item.kind == *v));
+        assert_eq!(items, syn.items);
+        assert_eq!(data, syn.data);
+        assert!(batches > 1);
+    }
+
+    /// 使用很小的缓冲区读取所有事件
+    fn read_events(input: &[u8]) -> Vec<StdResult<SyntaxEvent, String>> {
+        SyntaxReader::new(BufReader::with_capacity(3, input)).with_chunk_size(4).collect()
+    }
+
+    #[test]
+    fn test_stream_quote_across_reads() {
+        //字符串中的`)`/`,`/`~"`跨越多次读取
+        let events = read_events(b"release 19;\r\ntext(name=t text=\"a)(,~\"b\")\r\ndata(\"x),~\"y\", 'z)', )");
+        let events: Vec<SyntaxEvent> = events.into_iter().map(|v| v.unwrap()).collect();
+        match &events[2] {
+            SyntaxEvent::Item(item) => {
+                assert_eq!(item.values.get(&"text".into_key()).unwrap().as_str(), Some("a)(,~\"b"));
+            },
+            event => panic!("unexpected event: {event:?}")
+        }
+        let data: Vec<Value> = events
+            .into_iter()
+            .filter_map(|event| {
+                match event {
+                    SyntaxEvent::Data(values) => Some(values),
+                    _ => None
+                }
+            })
+            .flatten()
+            .collect();
+        assert_eq!(data, vec![
+            Value::DoubleQuotedString("x),~\"y".into()),
+            Value::SingleQuotedString("z)".into())
+        ]);
+    }
+
+    #[test]
+    fn test_stream_incomplete_item() {
+        let mut reader = SyntaxReader::new("release 19;\r\ntext(name=t".as_bytes());
+        assert!(matches!(reader.next(), Some(Ok(SyntaxEvent::Header { .. }))));
+        assert!(matches!(reader.next(), Some(Ok(SyntaxEvent::Version(_)))));
+        assert!(reader.next().unwrap().unwrap_err().contains("unexpected end of input"));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_stream_unbalanced_data() {
+        //缺少结束的`)`,已完整读取的批次正常输出
+        let events = read_events(b"release 19;\r\ndata(1, 2, 3, 4, 5");
+        let (last, events) = events.split_last().unwrap();
+        assert!(last.as_ref().unwrap_err().starts_with("unterminated data"));
+        assert!(events.iter().all(|event| event.is_ok()));
+        //字符串未结束时`)`不作为结束
+        let events = read_events(b"release 19;\r\ndata(1, \"a, ), 2)\r\ntext(name=t)");
+        assert!(events.last().unwrap().as_ref().unwrap_err().starts_with("unterminated data"));
+    }
+
+    #[test]
+    fn test_stream_unbalanced_quote() {
+        let events = read_events(b"release 19;\r\ntext(name=t text=\"a~\")\r\ncolumn(name=c)");
+        let err = events.last().unwrap().as_ref().unwrap_err();
+        assert!(err.starts_with("unexpected end of input"), "{err}");
+        let events = read_events(b"release 19;\r\ntext(name=t text='a)");
+        assert!(events.last().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_stream_invalid_input() {
+        let events = read_events(b"release 19;\r\n123(name=t)");
+        assert!(events.last().unwrap().as_ref().unwrap_err().starts_with("invalid item"));
+        let events = read_events(b"release;");
+        assert_eq!(events.len(), 1);
+        assert!(events[0].is_err());
+        //截断的UTF-8字符
+        let input = "release 19;\r\ntext(name=t text=\"中\")";
+        let events = read_events(&input.as_bytes()[..input.find('中').unwrap() + 1]);
+        assert_eq!(events.last().unwrap().as_ref().unwrap_err(), "invalid utf-8 sequence at end of input");
+        let events = read_events(b"release 19;\r\ntext(name=\xff)");
+        assert!(events.last().unwrap().as_ref().unwrap_err().starts_with("invalid utf-8 sequence"));
+    }
+}
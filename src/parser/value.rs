@@ -1,13 +1,13 @@
 use super::*;
 
 /// 字面值解析
-pub fn literal(input: &str) -> ParseResult<Value> {
+pub fn literal(input: &str) -> ParseResult<'_, Value<'_>> {
     /// 普通字面值
-    fn normal(input: &str) -> ParseResult<&str> {
+    fn normal(input: &str) -> ParseResult<'_, &str> {
         take_while1(|c: char| c.is_alphanumeric() || c == '.' || c == '_')(input)
     }
     /// 带括号的字面值
-    fn with_paren(input: &str) -> ParseResult<&str> {
+    fn with_paren(input: &str) -> ParseResult<'_, &str> {
         recognize(tuple((
             terminated(alpha1, multispace0),
             delimited(tag("("), delimited(multispace0, normal, multispace0), tag(")"))
@@ -20,7 +20,7 @@ pub fn literal(input: &str) -> ParseResult<Value> {
 }
 
 /// 字符串解析
-pub fn string(input: &str) -> ParseResult<Value> {
+pub fn string(input: &str) -> ParseResult<'_, Value<'_>> {
     /// 不同引号字符串(`""`/`''`)转义处理
     fn quoted(qot: char) -> impl Fn(&str) -> ParseResult<&str> {
         move |input: &str| {
@@ -47,10 +47,10 @@ pub fn string(input: &str) -> ParseResult<Value> {
 }
 
 /// 数值解析
-pub fn number(input: &str) -> ParseResult<Value> { context("number", double.map(Value::Number))(input) }
+pub fn number(input: &str) -> ParseResult<'_, Value<'_>> { context("number", double.map(Value::Number))(input) }
 
 /// 多值列表解析
-pub fn list(input: &str) -> ParseResult<Value> {
+pub fn list(input: &str) -> ParseResult<'_, Value<'_>> {
     let parser = delimited(
         tag("("),
         separated_list0(tag(","), delimited(multispace0, alt((string, literal, list, fail)), multispace0))
@@ -61,7 +61,7 @@ pub fn list(input: &str) -> ParseResult<Value> {
 }
 
 /// Key-Value值列表解析
-pub fn map(input: &str) -> ParseResult<Value> {
+pub fn map(input: &str) -> ParseResult<'_, Value<'_>> {
     let parser = value_map.map(Value::Map);
     context("map", parser)(input)
 }
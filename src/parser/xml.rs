@@ -0,0 +1,216 @@
+use super::*;
+use std::result::Result as StdResult;
+
+/// XML元素
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct XmlElement {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<XmlNode>
+}
+
+/// XML节点(注释和处理指令被忽略)
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(String)
+}
+
+/// XML文档
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct XmlDocument {
+    /// 根元素之前的原始内容(`<?xml ...?>`声明等)
+    pub prolog: String,
+    pub root: XmlElement
+}
+
+impl XmlDocument {
+    /// 解析XML文档
+    pub fn parse(input: &str) -> StdResult<Self, String> {
+        let input = input.trim_start_matches('\u{feff}');
+        let (_, (prolog, root)) = all_consuming(terminated(pair(recognize(misc), element), misc))(input)
+            .map_err(|e| format!("invalid xml: {}", friendly_error(input, e)))?;
+        Ok(XmlDocument {
+            prolog: prolog.to_owned(),
+            root
+        })
+    }
+}
+
+impl XmlElement {
+    /// 获取属性值
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(key, _)| key == name).map(|(_, v)| v.as_str())
+    }
+
+    /// 子元素
+    pub fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|node| {
+            match node {
+                XmlNode::Element(v) => Some(v),
+                XmlNode::Text(_) => None
+            }
+        })
+    }
+
+    /// 文本内容
+    pub fn text(&self) -> String {
+        self.children
+            .iter()
+            .map(|node| {
+                match node {
+                    XmlNode::Element(v) => v.text(),
+                    XmlNode::Text(v) => v.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for XmlElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}", self.name)?;
+        for (key, value) in &self.attrs {
+            write!(f, " {key}=\"{}\"", escape(value))?;
+        }
+        if self.children.is_empty() {
+            return write!(f, "/>");
+        }
+        write!(f, ">")?;
+        for node in &self.children {
+            match node {
+                XmlNode::Element(v) => write!(f, "{v}")?,
+                XmlNode::Text(v) => write!(f, "{}", escape(v))?
+            }
+        }
+        write!(f, "</{}>", self.name)
+    }
+}
+
+/// XML转义
+pub fn escape(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => rv += "&amp;",
+            '<' => rv += "&lt;",
+            '>' => rv += "&gt;",
+            '"' => rv += "&quot;",
+            '\'' => rv += "&apos;",
+            c => rv.push(c)
+        }
+    }
+    rv
+}
+
+/// XML实体还原
+fn unescape(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        rv += &rest[..start];
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break
+        };
+        let c = match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            v => {
+                match v.strip_prefix("#x").or_else(|| v.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => v.strip_prefix('#').and_then(|v| v.parse().ok())
+                }
+                .and_then(char::from_u32)
+            }
+        };
+        match c {
+            Some(c) => {
+                rv.push(c);
+                rest = &rest[end + 1..];
+            },
+            None => {
+                rv.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rv + rest
+}
+
+/// 空白、注释及处理指令
+fn misc(input: &str) -> ParseResult<'_, ()> {
+    let pi = delimited(tag("<?"), take_until("?>"), tag("?>"));
+    let doctype = delimited(tag("<!DOCTYPE"), is_not(">"), char('>'));
+    fold_many0(alt((multispace1, comment, pi, doctype)), || (), |_, _| ())(input)
+}
+
+fn comment(input: &str) -> ParseResult<'_, &str> {
+    delimited(tag("<!--"), take_until("-->"), tag("-->"))(input)
+}
+
+fn name(input: &str) -> ParseResult<'_, &str> {
+    take_while1(|c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))(input)
+}
+
+fn attr(input: &str) -> ParseResult<'_, (String, String)> {
+    let quoted = alt((
+        delimited(char('"'), take_till(|c| c == '"'), char('"')),
+        delimited(char('\''), take_till(|c| c == '\''), char('\''))
+    ));
+    let eq = delimited(multispace0, char('='), multispace0);
+    let (input, (key, value)) = pair(preceded(multispace1, name), preceded(eq, quoted))(input)?;
+    Ok((input, (key.to_owned(), unescape(value))))
+}
+
+fn element(input: &str) -> ParseResult<'_, XmlElement> {
+    let (input, (tag_name, attrs)) = preceded(char('<'), pair(name, many0(attr)))(input)?;
+    let (input, closed) =
+        preceded(multispace0, alt((tag("/>").map(|_| true), tag(">").map(|_| false))))(input)?;
+    let mut elem = XmlElement {
+        name: tag_name.to_owned(),
+        attrs,
+        children: vec![]
+    };
+    if closed {
+        return Ok((input, elem));
+    }
+    let cdata = delimited(tag("<![CDATA["), take_until("]]>"), tag("]]>"))
+        .map(|v: &str| Some(XmlNode::Text(v.to_owned())));
+    let text = take_till1(|c| c == '<').map(|v| Some(XmlNode::Text(unescape(v))));
+    let node = alt((element.map(|v| Some(XmlNode::Element(v))), comment.map(|_| None), cdata, text));
+    let (input, children) = many0(node)(input)?;
+    let end = delimited(tag("</"), tag(tag_name), pair(multispace0, char('>')));
+    let (input, _) = context("end tag", end)(input)?;
+    //合并相邻的文本节点
+    for node in children.into_iter().flatten() {
+        match (elem.children.last_mut(), node) {
+            (Some(XmlNode::Text(last)), XmlNode::Text(v)) => *last += &v,
+            (_, node) => elem.children.push(node)
+        }
+    }
+    Ok((input, elem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml() {
+        let doc = XmlDocument::parse(
+            "<?xml version=\"1.0\"?>\n<!-- c --><root a='1 &amp; 2'><row id=\"x\"><v>a&lt;b<![CDATA[<c>]]></v><e/></row> </root>\n"
+        )
+        .unwrap();
+        assert_eq!(doc.prolog, "<?xml version=\"1.0\"?>\n<!-- c -->");
+        assert_eq!(doc.root.attr("a"), Some("1 & 2"));
+        let row = doc.root.elements().next().unwrap();
+        assert_eq!(row.elements().next().unwrap().text(), "a<b<c>");
+        assert_eq!(row.to_string(), "<row id=\"x\"><v>a&lt;b&lt;c&gt;</v><e/></row>");
+        assert!(XmlDocument::parse("<a><b></a>").is_err());
+    }
+}
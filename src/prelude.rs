@@ -39,12 +39,12 @@ mod key_ci {
     impl<'a> std::ops::Deref for Key<'a> {
         type Target = Cow<'a, str>;
         #[inline]
-        fn deref<'b>(&'b self) -> &'b Self::Target { &self.0 }
+        fn deref(&self) -> &Self::Target { &self.0 }
     }
 
     impl<'a> std::ops::DerefMut for Key<'a> {
         #[inline]
-        fn deref_mut<'b>(&'b mut self) -> &'b mut Self::Target { &mut self.0 }
+        fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
     }
 
     impl AsRef<str> for Key<'_> {
@@ -0,0 +1,371 @@
+use crate::{ast::*, band::Band, prelude::*};
+use std::fmt::{self, Display};
+
+/// 控件左边距
+const MARGIN: f64 = 9.;
+/// 控件间距
+const SPACING: f64 = 9.;
+/// 默认控件高度
+const DEFAULT_HEIGHT: f64 = 76.;
+
+/// 显示风格
+///
+/// # Syntax
+///
+/// ```txt
+/// datawindow(processing=1 ...)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PresentationStyle {
+    /// `processing=0`,标签在`header`带区
+    Tabular,
+    /// `processing=0`,标签在`detail`带区
+    Freeform,
+    /// `processing=1`
+    Grid,
+    /// `processing=2`
+    Label,
+    /// `processing=3`
+    Graph,
+    /// `processing=4`
+    Crosstab,
+    /// `processing=5`
+    Composite,
+    /// `processing=7`
+    RichText,
+    /// `processing=8`
+    TreeView,
+    /// `processing=9`
+    Ole,
+    /// 其它`processing`值
+    Other(u32)
+}
+
+impl PresentationStyle {
+    /// 对应的`processing`值
+    pub fn processing(&self) -> u32 {
+        match self {
+            PresentationStyle::Tabular | PresentationStyle::Freeform => 0,
+            PresentationStyle::Grid => 1,
+            PresentationStyle::Label => 2,
+            PresentationStyle::Graph => 3,
+            PresentationStyle::Crosstab => 4,
+            PresentationStyle::Composite => 5,
+            PresentationStyle::RichText => 7,
+            PresentationStyle::TreeView => 8,
+            PresentationStyle::Ole => 9,
+            PresentationStyle::Other(v) => *v
+        }
+    }
+}
+
+impl Display for PresentationStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresentationStyle::Tabular => write!(f, "tabular"),
+            PresentationStyle::Freeform => write!(f, "freeform"),
+            PresentationStyle::Grid => write!(f, "grid"),
+            PresentationStyle::Label => write!(f, "label"),
+            PresentationStyle::Graph => write!(f, "graph"),
+            PresentationStyle::Crosstab => write!(f, "crosstab"),
+            PresentationStyle::Composite => write!(f, "composite"),
+            PresentationStyle::RichText => write!(f, "richtext"),
+            PresentationStyle::TreeView => write!(f, "treeview"),
+            PresentationStyle::Ole => write!(f, "ole"),
+            PresentationStyle::Other(v) => write!(f, "{v}")
+        }
+    }
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 获取显示风格
+    ///
+    /// 优先根据`tree`/`label`/`richtext`语法项判断,其次为`datawindow.processing`,
+    /// `processing=0`时根据字段标签(`<column>_t`)的位置区分:
+    /// 多数标签在`detail`带区且位于字段左侧则为`Freeform`,否则为`Tabular`
+    pub fn presentation_style(&self) -> PresentationStyle {
+        for item in &self.items {
+            if item.kind == "tree" {
+                return PresentationStyle::TreeView;
+            }
+            if item.kind == "label" {
+                return PresentationStyle::Label;
+            }
+            if item.kind == "richtext" {
+                return PresentationStyle::RichText;
+            }
+        }
+        let processing =
+            self.datawindow.get(&"processing".into_key()).and_then(|v| v.to_number()).unwrap_or_default();
+        match processing as u32 {
+            0 => {
+                if self.is_freeform() {
+                    PresentationStyle::Freeform
+                } else {
+                    PresentationStyle::Tabular
+                }
+            },
+            1 => PresentationStyle::Grid,
+            2 => PresentationStyle::Label,
+            3 => PresentationStyle::Graph,
+            4 => PresentationStyle::Crosstab,
+            5 => PresentationStyle::Composite,
+            7 => PresentationStyle::RichText,
+            8 => PresentationStyle::TreeView,
+            9 => PresentationStyle::Ole,
+            v => PresentationStyle::Other(v)
+        }
+    }
+
+    /// 转换显示风格并重新布局控件
+    ///
+    /// 仅支持`Tabular`/`Freeform`/`Grid`之间的转换:
+    ///
+    /// - `Tabular`/`Grid`: 字段标签(`<column>_t`)放到`header`带区,字段控件在`detail`带区横向排列
+    /// - `Freeform`: 字段标签与字段控件在`detail`带区纵向排列,标签位于字段左侧
+    pub fn set_presentation_style(&mut self, style: PresentationStyle) -> Result<(), String> {
+        let layoutable = |style: PresentationStyle| {
+            matches!(
+                style,
+                PresentationStyle::Tabular | PresentationStyle::Freeform | PresentationStyle::Grid
+            )
+        };
+        let current = self.presentation_style();
+        if !layoutable(current) || !layoutable(style) {
+            return Err(format!("unsupported presentation style conversion: {current} -> {style}"));
+        }
+        self.datawindow.insert("processing".into_key(), Value::Number(style.processing() as f64));
+        if style == PresentationStyle::Grid {
+            self.datawindow.entry("grid.lines".into_key()).or_insert(Value::Number(0.));
+            self.datawindow.entry("grid.columnmove".into_key()).or_insert(Value::Literal("no".into()));
+        } else {
+            self.datawindow.retain(|key, _| !key.to_ascii_lowercase().starts_with("grid."));
+        }
+        if current == style {
+            return Ok(());
+        }
+
+        //按(y, x)顺序获取detail带区的字段控件及其标签
+        let mut columns: Vec<usize> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.kind == "column" && item.band() == Some(Band::Detail))
+            .map(|(i, _)| i)
+            .collect();
+        columns.sort_by(|a, b| {
+            let (a, b) = (&self.items[*a], &self.items[*b]);
+            let pos = |item: &Item| (geometry(item, "y"), geometry(item, "x"));
+            pos(a).partial_cmp(&pos(b)).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let labels: Vec<Option<usize>> = columns.iter().map(|i| self.column_label(&self.items[*i])).collect();
+
+        if style == PresentationStyle::Freeform {
+            let label_width =
+                labels.iter().flatten().map(|i| geometry(&self.items[*i], "width")).fold(0., f64::max);
+            let mut y = SPACING;
+            for (column, label) in columns.iter().zip(&labels) {
+                let height = height(&self.items[*column]);
+                if let Some(label) = label {
+                    let label = &mut self.items[*label];
+                    label.set_band(Band::Detail);
+                    set_geometry(label, "x", MARGIN);
+                    set_geometry(label, "y", y);
+                    set_geometry(label, "width", label_width);
+                    label.values.insert("alignment".into_key(), Value::DoubleQuotedString("1".into()));
+                }
+                let column = &mut self.items[*column];
+                set_geometry(column, "x", MARGIN + label_width + SPACING);
+                set_geometry(column, "y", y);
+                y += height + SPACING;
+            }
+            self.detail.insert("height".into_key(), Value::Number(y));
+            if !self.items.iter().any(|item| item.band() == Some(Band::Header)) {
+                self.header.insert("height".into_key(), Value::Number(0.));
+            }
+        } else {
+            let label_height = labels
+                .iter()
+                .flatten()
+                .map(|i| height(&self.items[*i]))
+                .fold(0., f64::max)
+                .max(DEFAULT_HEIGHT);
+            let row_height = columns.iter().map(|i| height(&self.items[*i])).fold(0., f64::max);
+            //header带区中保留的其它控件位于标签上方
+            let top = self
+                .items
+                .iter()
+                .enumerate()
+                .filter(|(i, item)| item.band() == Some(Band::Header) && !labels.contains(&Some(*i)))
+                .map(|(_, item)| geometry(item, "y") + height(item))
+                .fold(0., f64::max);
+            let mut x = MARGIN;
+            for (column, label) in columns.iter().zip(&labels) {
+                let width = geometry(&self.items[*column], "width");
+                if let Some(label) = label {
+                    let label = &mut self.items[*label];
+                    label.set_band(Band::Header);
+                    set_geometry(label, "x", x);
+                    set_geometry(label, "y", top + SPACING);
+                    set_geometry(label, "width", width);
+                    label.values.insert("alignment".into_key(), Value::DoubleQuotedString("2".into()));
+                }
+                let column = &mut self.items[*column];
+                set_geometry(column, "x", x);
+                set_geometry(column, "y", SPACING);
+                x += width + SPACING;
+            }
+            if labels.iter().any(Option::is_some) {
+                self.header.insert("height".into_key(), Value::Number(top + SPACING * 2. + label_height));
+            }
+            self.detail.insert("height".into_key(), Value::Number(row_height + SPACING * 2.));
+        }
+        Ok(())
+    }
+
+    /// 是否为自由格式(字段标签在`detail`带区且与字段位于同一行的左侧)
+    fn is_freeform(&self) -> bool {
+        let (mut beside, mut other) = (0, 0);
+        let columns = self.items.iter().filter(|item| item.kind == "column" && item.band() == Some(Band::Detail));
+        for column in columns {
+            let label = match self.column_label(column) {
+                Some(i) => &self.items[i],
+                None => continue
+            };
+            let (x, y) = (geometry(column, "x"), geometry(column, "y"));
+            let (label_x, label_y) = (geometry(label, "x"), geometry(label, "y"));
+            if label.band() == Some(Band::Detail) &&
+                label_x < x &&
+                label_y < y + height(column) &&
+                y < label_y + height(label)
+            {
+                beside += 1;
+            } else {
+                other += 1;
+            }
+        }
+        beside > other
+    }
+
+    /// 查找字段控件的标签(`<column>_t`)
+    fn column_label(&self, column: &Item) -> Option<usize> {
+        let name = format!("{}_t", column.name.as_ref()?);
+        self.items.iter().position(|item| {
+            item.kind == "text" && matches!(&item.name, Some(v) if self.name_eq(v, &name))
+        })
+    }
+}
+
+/// 获取控件的坐标/尺寸
+fn geometry(item: &Item, key: &str) -> f64 {
+    item.values.get(&key.to_owned().into_key()).and_then(|v| v.to_number()).unwrap_or_default()
+}
+
+fn height(item: &Item) -> f64 {
+    match geometry(item, "height") {
+        v if v > 0. => v,
+        _ => DEFAULT_HEIGHT
+    }
+}
+
+fn set_geometry(item: &mut Item, key: &'static str, value: f64) {
+    item.values.insert(key.into_key(), Value::DoubleQuotedString(value.to_string().into()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+datawindow(units=0 processing=0 color=1073741824)
+header(height=96 color="536870912")
+detail(height=88 color="536870912")
+table(column=(type=long name=id dbname="id") column=(type=char(20) name=name dbname="name"))
+text(band=header alignment="2" text="Id" border="0" x="9" y="8" height="64" width="200" name=id_t)
+text(band=header alignment="2" text="Name" border="0" x="219" y="8" height="64" width="400" name=name_t)
+column(band=detail id=1 alignment="1" x="9" y="8" height="76" width="200" name=id)
+column(band=detail id=2 alignment="0" x="219" y="8" height="76" width="400" name=name)
+"#;
+
+    /// (带区, x, y, width)
+    fn layout(dw: &DWSyntax, name: &str) -> (Option<Band>, f64, f64, f64) {
+        let item = dw.item_by_name(name).unwrap();
+        (item.band(), geometry(item, "x"), geometry(item, "y"), geometry(item, "width"))
+    }
+
+    fn band_height(values: &HashMap<Key, Value>) -> Option<f64> {
+        values.get(&"height".into_key()).and_then(|v| v.to_number())
+    }
+
+    #[test]
+    fn test_presentation_style() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        assert_eq!(dw.presentation_style(), PresentationStyle::Tabular);
+        //detail带区中的其它文本控件不影响判断
+        let text = r#"release 19;
+text(band=detail text="*" x="700" y="8" height="64" width="20" name=t_1)"#;
+        dw.items.push(DWSyntax::parse(text).unwrap().items.remove(0));
+        assert_eq!(dw.presentation_style(), PresentationStyle::Tabular);
+
+        for (processing, style) in
+            [(1., PresentationStyle::Grid), (3., PresentationStyle::Graph), (6., PresentationStyle::Other(6))]
+        {
+            dw.datawindow.insert("processing".into_key(), Value::Number(processing));
+            assert_eq!(dw.presentation_style(), style);
+            assert_eq!(style.processing(), processing as u32);
+        }
+        //`tree`语法项优先于`processing`
+        dw.items.push(DWSyntax::parse("release 19;\ntree(indent=64)").unwrap().items.remove(0));
+        assert_eq!(dw.presentation_style(), PresentationStyle::TreeView);
+    }
+
+    #[test]
+    fn test_to_freeform() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.set_presentation_style(PresentationStyle::Freeform).unwrap();
+        assert_eq!(dw.presentation_style(), PresentationStyle::Freeform);
+        assert_eq!(layout(&dw, "id_t"), (Some(Band::Detail), 9., 9., 400.));
+        assert_eq!(layout(&dw, "name_t"), (Some(Band::Detail), 9., 94., 400.));
+        assert_eq!(layout(&dw, "id"), (Some(Band::Detail), 418., 9., 200.));
+        assert_eq!(layout(&dw, "name"), (Some(Band::Detail), 418., 94., 400.));
+        assert_eq!(band_height(&dw.detail), Some(179.));
+        assert_eq!(band_height(&dw.header), Some(0.));
+    }
+
+    #[test]
+    fn test_to_grid() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.set_presentation_style(PresentationStyle::Freeform).unwrap();
+        dw.set_presentation_style(PresentationStyle::Grid).unwrap();
+        assert_eq!(dw.presentation_style(), PresentationStyle::Grid);
+        assert_eq!(dw.datawindow.get(&"grid.lines".into_key()), Some(&Value::Number(0.)));
+        assert_eq!(dw.datawindow.get(&"grid.columnmove".into_key()), Some(&Value::Literal("no".into())));
+        assert_eq!(layout(&dw, "id_t"), (Some(Band::Header), 9., 9., 200.));
+        assert_eq!(layout(&dw, "name_t"), (Some(Band::Header), 218., 9., 400.));
+        assert_eq!(layout(&dw, "name"), (Some(Band::Detail), 218., 9., 400.));
+        assert_eq!(band_height(&dw.header), Some(94.));
+
+        //转回`Tabular`时删除`grid.*`属性
+        dw.set_presentation_style(PresentationStyle::Tabular).unwrap();
+        assert_eq!(dw.presentation_style(), PresentationStyle::Tabular);
+        assert!(!dw.datawindow.contains_key(&"grid.lines".into_key()));
+        assert_eq!(layout(&dw, "name_t"), (Some(Band::Header), 218., 9., 400.));
+    }
+
+    #[test]
+    fn test_set_presentation_style_error() {
+        //不支持的转换不修改语法
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let syntax = dw.to_string();
+        let err = dw.set_presentation_style(PresentationStyle::Graph).unwrap_err();
+        assert_eq!(err, "unsupported presentation style conversion: tabular -> graph");
+        assert!(dw.set_presentation_style(PresentationStyle::Other(6)).is_err());
+        assert_eq!(dw.to_string(), syntax);
+
+        dw.items.push(DWSyntax::parse("release 19;\ntree(indent=64)").unwrap().items.remove(0));
+        let syntax = dw.to_string();
+        let err = dw.set_presentation_style(PresentationStyle::Tabular).unwrap_err();
+        assert_eq!(err, "unsupported presentation style conversion: treeview -> tabular");
+        assert_eq!(dw.to_string(), syntax);
+    }
+}
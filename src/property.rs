@@ -0,0 +1,215 @@
+use crate::{ast::*, prelude::*};
+use std::collections::HashMap as StdHashMap;
+
+/// 属性表
+///
+/// 保留插入顺序,键的大小写匹配方式在运行时选择(默认区分大小写),
+/// 不受`preserve_order`/`case_insensitive`特性影响
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyMap<V> {
+    entries: Vec<(String, V)>,
+    /// 键(忽略大小写时为小写)到`entries`位置的索引
+    index: StdHashMap<String, usize>,
+    case_insensitive: bool
+}
+
+impl<V> Default for PropertyMap<V> {
+    fn default() -> Self { PropertyMap::new() }
+}
+
+impl<V> PropertyMap<V> {
+    pub fn new() -> Self { PropertyMap::with_case_insensitive(false) }
+
+    /// 指定是否忽略大小写
+    pub fn with_case_insensitive(case_insensitive: bool) -> Self {
+        PropertyMap {
+            entries: vec![],
+            index: StdHashMap::new(),
+            case_insensitive
+        }
+    }
+
+    pub fn is_case_insensitive(&self) -> bool { self.case_insensitive }
+
+    /// 切换大小写匹配方式
+    ///
+    /// 切换为忽略大小写后,仅大小写不同的键只能通过`get`查找到第一个
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+        self.reindex();
+    }
+
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    pub fn get(&self, key: &str) -> Option<&V> { self.position(key).map(|i| &self.entries[i].1) }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.position(key).map(move |i| &mut self.entries[i].1)
+    }
+
+    /// 忽略大小写查找,不受当前匹配方式影响
+    pub fn get_ignore_case(&self, key: &str) -> Option<&V> {
+        if self.case_insensitive {
+            return self.get(key);
+        }
+        self.get(key)
+            .or_else(|| self.entries.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v))
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool { self.position(key).is_some() }
+
+    /// 插入属性,键已存在时保留原位置及原键名,返回旧值
+    pub fn insert(&mut self, key: impl Into<String>, value: V) -> Option<V> {
+        let key = key.into();
+        match self.position(&key) {
+            Some(i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.index.insert(self.index_key(&key), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// 删除属性,保留其余属性的顺序
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let i = self.position(key)?;
+        let (_, value) = self.entries.remove(i);
+        self.reindex();
+        Some(value)
+    }
+
+    /// 按插入顺序遍历
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> { self.entries.iter().map(|(k, _)| k.as_str()) }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> { self.entries.iter().map(|(_, v)| v) }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        if self.case_insensitive {
+            self.index.get(&key.to_ascii_lowercase()).copied()
+        } else {
+            self.index.get(key).copied()
+        }
+    }
+
+    fn index_key(&self, key: &str) -> String {
+        if self.case_insensitive {
+            key.to_ascii_lowercase()
+        } else {
+            key.to_owned()
+        }
+    }
+
+    fn reindex(&mut self) {
+        let mut index = StdHashMap::with_capacity(self.entries.len());
+        for (i, (key, _)) in self.entries.iter().enumerate() {
+            index.entry(self.index_key(key)).or_insert(i);
+        }
+        self.index = index;
+    }
+}
+
+impl<K: Into<String>, V> FromIterator<(K, V)> for PropertyMap<V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = PropertyMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<V> IntoIterator for PropertyMap<V> {
+    type Item = (String, V);
+    type IntoIter = std::vec::IntoIter<(String, V)>;
+
+    fn into_iter(self) -> Self::IntoIter { self.entries.into_iter() }
+}
+
+/// 从语法项的参数列表转换
+///
+/// 按参数列表的遍历顺序插入:启用`preserve_order`特性时为语法中的顺序,否则顺序不确定
+impl<'a> From<&HashMap<Key<'a>, Value<'a>>> for PropertyMap<Value<'a>> {
+    fn from(values: &HashMap<Key<'a>, Value<'a>>) -> Self {
+        values.iter().map(|(k, v)| (k.to_string(), v.to_owned())).collect()
+    }
+}
+
+/// 转换为语法项的参数列表
+impl<'a> From<PropertyMap<Value<'a>>> for HashMap<Key<'a>, Value<'a>> {
+    fn from(map: PropertyMap<Value<'a>>) -> Self { map.into_iter().map(|(k, v)| (k.into_key(), v)).collect() }
+}
+
+impl<'a> Item<'a> {
+    /// 参数列表转换为属性表
+    pub fn properties(&self) -> PropertyMap<Value<'a>> { PropertyMap::from(&self.values) }
+
+    /// 使用属性表替换参数列表
+    pub fn set_properties(&mut self, properties: PropertyMap<Value<'a>>) { self.values = properties.into(); }
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 获取参数列表的属性表,大小写匹配方式与`describe`/`modify`一致
+    ///
+    /// `name`为`datawindow`/`header`/`summary`/`footer`/`detail`/`table`或语法项名称
+    pub fn properties(&self, name: &str) -> Option<PropertyMap<Value<'a>>> {
+        let values = match name.to_ascii_lowercase().as_str() {
+            "datawindow" => &self.datawindow,
+            "header" => &self.header,
+            "summary" => &self.summary,
+            "footer" => &self.footer,
+            "detail" => &self.detail,
+            "table" => &self.table.values,
+            _ => &self.item_by_name(name)?.values
+        };
+        let mut map = PropertyMap::from(values);
+        map.set_case_insensitive(self.ignore_case());
+        Some(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_property_map() {
+        let mut map: PropertyMap<i32> = [("b", 1), ("A", 2), ("c", 3)].into_iter().collect();
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.get_ignore_case("a"), Some(&2));
+        assert_eq!(map.insert("a", 4), None);
+        assert_eq!(map.len(), 4);
+        map.set_case_insensitive(true);
+        assert_eq!(map.get("a"), Some(&2));
+        assert_eq!(map.insert("B", 5), Some(1));
+        assert_eq!(map.remove("C"), Some(3));
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["b", "A", "a"]);
+        assert_eq!(map.values().collect::<Vec<_>>(), vec![&5, &2, &4]);
+
+        let dw = DWSyntax::parse("release 19;\r\ntext(name=t1 Text=\"x\" x=1)").unwrap();
+        let mut props = PropertyMap::from(&dw.items[0].values);
+        assert_eq!(props.keys().collect::<Vec<_>>().len(), 3);
+        assert!(props.get_ignore_case("text").is_some());
+        props.insert("y", Value::Number(2.));
+        let values: HashMap<Key, Value> = props.into();
+        assert_eq!(values.get(&"y".into_key()), Some(&Value::Number(2.)));
+
+        let mut dw = dw;
+        dw.set_case_insensitive(false);
+        assert!(dw.properties("t1").unwrap().get("text").is_none());
+        dw.set_case_insensitive(true);
+        assert!(dw.properties("T1").unwrap().get("text").is_some());
+        assert!(dw.properties("table").is_some_and(|v| v.is_empty()));
+        assert!(dw.properties("none").is_none());
+        let mut props = dw.items[0].properties();
+        props.remove("x");
+        dw.items[0].set_properties(props);
+        assert_eq!(dw.items[0].values.len(), 2);
+    }
+}
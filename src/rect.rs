@@ -0,0 +1,25 @@
+/// 矩形区域`(x, y, width, height)`
+///
+/// 控件使用PB单位(`Rect<u32>`),图表渲染使用像素(`Rect<f64>`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect<T = u32> {
+    pub x: T,
+    pub y: T,
+    pub width: T,
+    pub height: T
+}
+
+impl<T> Rect<T> {
+    pub fn new(x: T, y: T, width: T, height: T) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height
+        }
+    }
+}
+
+impl<T> From<(T, T, T, T)> for Rect<T> {
+    fn from((x, y, width, height): (T, T, T, T)) -> Self { Rect::new(x, y, width, height) }
+}
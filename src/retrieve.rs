@@ -0,0 +1,126 @@
+use crate::{
+    argument::{ArgumentValue, Placeholder},
+    ast::*,
+    data::*,
+    prelude::*
+};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+
+impl<'a> DWSyntax<'a> {
+    /// 在SQLite数据库中执行检索SQL,结果写入`data`项
+    ///
+    /// 结果集字段按`dbname`(或不含表名的字段名)对应`table`字段,
+    /// 并按字段类型转换,返回检索行数
+    pub fn retrieve_sqlite(
+        &mut self,
+        conn: &rusqlite::Connection,
+        values: &[ArgumentValue]
+    ) -> Result<usize, String> {
+        let args = self.table.arguments()?;
+        let stmt = self.table.retrieve()?.bind(&args, values, Placeholder::Question)?;
+        let mut query = conn.prepare(&stmt.sql).map_err(|e| e.to_string())?;
+        let names: Vec<String> = query.column_names().iter().map(|v| v.to_ascii_lowercase()).collect();
+        let mut mapping = Vec::with_capacity(self.table.columns.len());
+        for col in &self.table.columns {
+            let name = col.name.as_ref().map(|v| v.to_string()).unwrap_or_default();
+            let dbname = col
+                .values
+                .get(&"dbname".into_key())
+                .and_then(|v| v.as_str())
+                .map(unescape)
+                .unwrap_or_else(|| name.clone())
+                .to_ascii_lowercase();
+            let short = dbname.rsplit_once('.').map(|(_, v)| v).unwrap_or(&dbname);
+            let index = names
+                .iter()
+                .position(|v| *v == dbname)
+                .or_else(|| names.iter().position(|v| v == short))
+                .ok_or_else(|| format!("column not found in result set: {dbname}"))?;
+            mapping.push((index, col.col_type(), name));
+        }
+
+        let params = rusqlite::params_from_iter(stmt.params.iter().map(to_sql_value));
+        let mut rows = query.query(params).map_err(|e| e.to_string())?;
+        let mut data = vec![];
+        let mut count = 0;
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            for (index, ty, name) in &mapping {
+                let value = from_sql_value(row.get_ref(*index).map_err(|e| e.to_string())?);
+                let value = match ty {
+                    Some(ty) => {
+                        value.coerce(*ty).ok_or_else(|| format!("invalid value for column {name}: {value}"))?
+                    },
+                    None => value
+                };
+                data.push(value.to_value());
+            }
+            count += 1;
+        }
+        self.data = data;
+        Ok(count)
+    }
+}
+
+/// 转换为SQLite参数值,日期时间使用文本格式
+fn to_sql_value(value: &DataValue) -> SqlValue {
+    match value {
+        DataValue::Null => SqlValue::Null,
+        DataValue::Number(v) if v.fract() == 0. && v.abs() < i64::MAX as f64 => SqlValue::Integer(*v as i64),
+        DataValue::Number(v) => SqlValue::Real(*v),
+        DataValue::String(v) => SqlValue::Text(v.clone()),
+        _ => SqlValue::Text(value.to_string())
+    }
+}
+
+/// 从SQLite结果值转换,二进制数据按UTF-8文本处理
+fn from_sql_value(value: ValueRef) -> DataValue {
+    match value {
+        ValueRef::Null => DataValue::Null,
+        ValueRef::Integer(v) => DataValue::Number(v as f64),
+        ValueRef::Real(v) => DataValue::Number(v),
+        ValueRef::Text(v) | ValueRef::Blob(v) => DataValue::String(String::from_utf8_lossy(v).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+table(column=(type=long name=id dbname="emp.id") column=(type=char(20) name=emp_name dbname="emp.name")
+ column=(type=date name=hired dbname="emp.hired") column=(type=decimal(2) name=salary dbname="emp.salary")
+ retrieve="SELECT e.salary, e.hired, e.name, e.id FROM emp e WHERE e.dept IN (:depts) AND e.hired >= :since ORDER BY e.id"
+ arguments=(("depts", numberlist), ("since", date)))
+"#;
+
+    #[test]
+    fn test_retrieve_sqlite() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE emp (id INTEGER PRIMARY KEY, name TEXT, hired DATE, salary NUMERIC, dept INTEGER);
+             INSERT INTO emp VALUES (1, 'Tom', '2020-01-02', 1000.5, 10);
+             INSERT INTO emp VALUES (2, NULL, '2021-03-04', 2000, 20);
+             INSERT INTO emp VALUES (3, 'Jerry', '2019-05-06', 3000, 10);
+             INSERT INTO emp VALUES (4, 'Spike', '2022-07-08', 4000, 30);"
+        )
+        .unwrap();
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let values = [ArgumentValue::List(vec![10.0.into(), 20.0.into()]), Date::new(2020, 1, 1).into()];
+        assert_eq!(dw.retrieve_sqlite(&conn, &values).unwrap(), 2);
+        assert_eq!(dw.data_rows(), vec![
+            vec![1.0.into(), "Tom".into(), Date::new(2020, 1, 2).into(), 1000.5.into()],
+            vec![2.0.into(), DataValue::Null, Date::new(2021, 3, 4).into(), 2000.0.into()],
+        ]);
+        let syntax = dw.to_string();
+        assert!(syntax.contains("data(1, \"Tom\", 2020-01-02, 1000.5, 2, null 2021-03-04, 2000, "));
+        assert_eq!(DWSyntax::parse(&syntax).unwrap().data_rows(), dw.data_rows());
+
+        let values = [ArgumentValue::List(vec![]), Date::new(2020, 1, 1).into()];
+        assert_eq!(dw.retrieve_sqlite(&conn, &values).unwrap(), 0);
+        assert!(dw.data.is_empty());
+
+        dw.table.values.insert("retrieve".into_key(), Value::DoubleQuotedString("SELECT id FROM emp".into()));
+        dw.table.set_arguments(&[]);
+        assert_eq!(dw.retrieve_sqlite(&conn, &[]).unwrap_err(), "column not found in result set: emp.name");
+    }
+}
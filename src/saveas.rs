@@ -0,0 +1,746 @@
+use crate::{
+    ast::*,
+    data::*,
+    datastore::{Buffer, DataRow, DataStore, ItemStatus},
+    parser::{
+        json::Json,
+        xml::{self, XmlDocument, XmlElement, XmlNode}
+    },
+    prelude::*
+};
+
+/// 数据字段(字段名,类型)
+type Columns = [(String, ColType)];
+
+/// DataWindow JSON的标识
+const JSON_IDENTITY: &str = "70c86603-983b-4bd9-adbc-259436e43cbd";
+
+/// 数据导出/导入格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveAsType {
+    /// `Text!`: 制表符分隔
+    Text,
+    /// `CSV!`: 逗号分隔,字符串使用双引号
+    Csv,
+    /// `XML!`: 使用`export.xml`/`import.xml`的模板
+    Xml,
+    /// DataWindow JSON(`ExportJson`/`ImportJson`)
+    Json,
+    /// Plain JSON: `[{"column": value}]`
+    PlainJson
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 导出`data`项的数据(对应`SaveAs`/`ExportJson`)
+    ///
+    /// `headers`仅用于`Text`/`CSV`格式,首行输出字段名
+    ///
+    /// `data`项的数据作为已检索数据导出,DataWindow JSON的行状态均为`NotModified`且没有删除行,
+    /// 需要导出修改状态及过滤、删除缓冲区时使用`DataStore::export_json`
+    pub fn save_as(&self, ty: SaveAsType, headers: bool) -> Result<String, String> {
+        let columns = self.data_columns()?;
+        let rows = self.data_rows();
+        let rv = match ty {
+            SaveAsType::Text => write_delimited(&columns, &rows, '\t', headers),
+            SaveAsType::Csv => write_delimited(&columns, &rows, ',', headers),
+            SaveAsType::Xml => self.write_xml(&columns, &rows)?,
+            SaveAsType::Json => {
+                let status = ItemStatus::NotModified;
+                let rows = rows.iter().map(|row| json_dw_row(&columns, row, &[], status)).collect();
+                write_json(&self.dataobject(), &columns, [rows, vec![], vec![]])
+            },
+            SaveAsType::PlainJson => {
+                let rows = rows.iter().map(|row| json_row(&columns, row, json_value)).collect();
+                Json::Array(rows).to_string()
+            }
+        };
+        Ok(rv)
+    }
+
+    /// 导入数据追加到`data`项(对应`ImportString`/`ImportJson`),返回导入的行数
+    ///
+    /// - `Text`/`CSV`: 首行与字段名一致时作为标题行忽略,未加引号的空值导入为`Null`
+    /// - `XML`: 按`import.xml`模板或`<字段名>`元素对应字段
+    /// - `JSON`: 同时支持DataWindow JSON和Plain JSON,按字段名对应
+    pub fn import_string(&mut self, ty: SaveAsType, input: &str) -> Result<usize, String> {
+        let columns = self.data_columns()?;
+        let rows = match ty {
+            SaveAsType::Text => read_delimited(&columns, input, '\t')?,
+            SaveAsType::Csv => read_delimited(&columns, input, ',')?,
+            SaveAsType::Xml => self.read_xml(&columns, input)?,
+            SaveAsType::Json | SaveAsType::PlainJson => read_json(&columns, input)?
+        };
+        //丢弃不完整的行
+        let count = self.data.len() / columns.len() * columns.len();
+        self.data.truncate(count);
+        for row in &rows {
+            self.data.extend(row.iter().map(DataValue::to_value));
+        }
+        Ok(rows.len())
+    }
+
+    /// 数据字段(字段名,类型)
+    fn data_columns(&self) -> Result<Vec<(String, ColType)>, String> {
+        if self.table.columns.is_empty() {
+            return Err("table has no column".to_owned());
+        }
+        self.table
+            .columns
+            .iter()
+            .map(|col| {
+                let name = col.name.as_ref().map(|v| v.to_string()).unwrap_or_default();
+                let ty = col.col_type().ok_or_else(|| format!("invalid column type: {name}"))?;
+                Ok((name, ty))
+            })
+            .collect()
+    }
+
+    /// 数据窗口对象名(不含`.srd`)
+    fn dataobject(&self) -> String {
+        let name = self.name.as_deref().unwrap_or("datawindow");
+        name.strip_suffix(".srd").unwrap_or(name).to_owned()
+    }
+
+    /// 获取`export.xml`/`import.xml`的属性
+    fn xml_option(&self, kind: &str, key: &'static str) -> Option<&Value<'a>> {
+        self.items.iter().find(|item| item.kind == kind)?.values.get(&key.into_key())
+    }
+
+    /// 获取`export.xml`/`import.xml`的模板
+    fn xml_template(&self, kind: &str) -> Result<Option<XmlDocument>, String> {
+        let xml = match self.xml_option(kind, "template") {
+            Some(Value::Map(template)) => {
+                template.get(&"xml".into_key()).and_then(|v| v.as_str()).map(unescape)
+            },
+            _ => None
+        };
+        xml.map(|xml| XmlDocument::parse(&xml)).transpose()
+    }
+
+    fn write_xml(&self, columns: &Columns, rows: &[Vec<DataValue>]) -> Result<String, String> {
+        let whitespace =
+            self.xml_option("export.xml", "includewhitespace").and_then(|v| v.to_bool()).unwrap_or_default();
+        let (prolog, mut root) = match self.xml_template("export.xml")? {
+            Some(doc) => (doc.prolog, doc.root),
+            None => {
+                let name = self.dataobject();
+                let row = XmlElement {
+                    name: format!("{name}_row"),
+                    attrs: vec![("__pbband".to_owned(), "detail".to_owned())],
+                    children: columns
+                        .iter()
+                        .map(|(column, _)| {
+                            XmlNode::Element(XmlElement {
+                                name: column.clone(),
+                                attrs: vec![],
+                                children: vec![XmlNode::Text(column.clone())]
+                            })
+                        })
+                        .collect()
+                };
+                let root = XmlElement {
+                    name,
+                    attrs: vec![],
+                    children: vec![XmlNode::Element(row)]
+                };
+                ("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>".to_owned(), root)
+            }
+        };
+        if !expand_rows(&mut root, columns, rows) {
+            return Err("xml template has no detail band".to_owned());
+        }
+        let mut xml = prolog;
+        if whitespace {
+            if !xml.is_empty() && !xml.ends_with('\n') {
+                xml += "\r\n";
+            }
+            write_indented(&mut xml, &root, 0);
+        } else {
+            xml += &root.to_string();
+        }
+        Ok(xml)
+    }
+
+    fn read_xml(&self, columns: &Columns, input: &str) -> Result<Vec<Vec<DataValue>>, String> {
+        let doc = XmlDocument::parse(input)?;
+        //(行元素名,字段映射)
+        let (row_name, mapping) = match self.xml_template("import.xml")? {
+            Some(template) => {
+                let row = find_detail(&template.root).ok_or("xml template has no detail band")?;
+                let mut mapping = vec![];
+                collect_mapping(row, columns, &mut vec![], &mut mapping);
+                (Some(row.name.clone()), mapping)
+            },
+            None => {
+                let mapping =
+                    columns.iter().enumerate().map(|(i, (name, _))| (vec![name.clone()], None, i)).collect();
+                (None, mapping)
+            }
+        };
+        let mut elements = vec![];
+        match &row_name {
+            Some(name) => find_elements(&doc.root, name, &mut elements),
+            None => elements.extend(doc.root.elements())
+        }
+        let mut rows = vec![];
+        for elem in elements {
+            let mut row = vec![DataValue::Null; columns.len()];
+            for (path, attr, index) in &mapping {
+                let mut target = Some(elem);
+                for name in path {
+                    target = target.and_then(|v| v.elements().find(|v| v.name.eq_ignore_ascii_case(name)));
+                }
+                let text = match (target, attr) {
+                    (Some(target), Some(attr)) => target.attr(attr).map(|v| v.to_owned()),
+                    (Some(target), None) => Some(target.text()),
+                    (None, _) => None
+                };
+                let (name, ty) = &columns[*index];
+                row[*index] = match text {
+                    //空元素导入为`Null`
+                    Some(text) if !text.is_empty() => {
+                        DataValue::String(text)
+                            .coerce(*ty)
+                            .ok_or_else(|| format!("invalid value for column {name}: {}", elem))?
+                    },
+                    _ => DataValue::Null
+                };
+            }
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
+
+impl DataStore {
+    /// 导出DataWindow JSON(对应`ExportJson`),包含各缓冲区的数据及行状态
+    pub fn export_json(&self, name: &str) -> String {
+        let columns: Vec<(String, ColType)> =
+            self.columns().iter().map(|col| (col.name.clone(), col.ty)).collect();
+        let rows = |buffer: Buffer| {
+            self.rows(buffer).iter().map(|row| store_row(&columns, row)).collect::<Vec<_>>()
+        };
+        write_json(name, &columns, [rows(Buffer::Primary), rows(Buffer::Filter), rows(Buffer::Delete)])
+    }
+}
+
+/// `DataStore`的数据行,已修改的字段附带原始值
+fn store_row(columns: &Columns, row: &DataRow) -> Json {
+    let original: Vec<_> = (0..columns.len())
+        .map(|i| (row.column_status(i) == ItemStatus::DataModified).then(|| &row.original()[i]))
+        .collect();
+    json_dw_row(columns, row.values(), &original, row.status())
+}
+
+/// 按字段名查找字段索引
+fn column_index(columns: &Columns, name: &str) -> Option<usize> {
+    columns.iter().position(|(column, _)| column.eq_ignore_ascii_case(name.trim()))
+}
+
+/// 导入值转换
+fn import_value(columns: &Columns, index: usize, value: DataValue) -> Result<DataValue, String> {
+    let (name, ty) = &columns[index];
+    value.coerce(*ty).ok_or_else(|| format!("invalid value for column {name}: {value}"))
+}
+
+fn write_delimited(columns: &Columns, rows: &[Vec<DataValue>], sep: char, headers: bool) -> String {
+    //CSV格式的字符串总是使用引号,其它值仅在包含分隔符、引号或换行时使用引号
+    let field = |out: &mut String, value: &str, string: bool| {
+        if (string && sep == ',') || value.contains([sep, '"', '\r', '\n']) {
+            *out += &format!("\"{}\"", value.replace('"', "\"\""));
+        } else {
+            *out += value;
+        }
+    };
+    let mut out = String::new();
+    if headers {
+        for (i, (name, _)) in columns.iter().enumerate() {
+            if i > 0 {
+                out.push(sep);
+            }
+            field(&mut out, name, false);
+        }
+        out += "\r\n";
+    }
+    for row in rows {
+        for (i, value) in row.iter().enumerate() {
+            if i > 0 {
+                out.push(sep);
+            }
+            field(&mut out, &value.to_string(), matches!(value, DataValue::String(_)));
+        }
+        out += "\r\n";
+    }
+    out
+}
+
+fn read_delimited(columns: &Columns, input: &str, sep: char) -> Result<Vec<Vec<DataValue>>, String> {
+    let mut records = split_records(input.trim_start_matches('\u{feff}'), sep);
+    let is_header = |record: &Vec<(String, bool)>| {
+        record.len() == columns.len() &&
+            record.iter().zip(columns).all(|((field, _), (name, _))| field.trim().eq_ignore_ascii_case(name))
+    };
+    if records.first().is_some_and(is_header) {
+        records.remove(0);
+    }
+    let mut rows = Vec::with_capacity(records.len());
+    for (line, record) in records.into_iter().enumerate() {
+        if record.len() != columns.len() {
+            let count = record.len();
+            return Err(format!("expected {} fields, got {count} at row {}", columns.len(), line + 1));
+        }
+        let row = record
+            .into_iter()
+            .enumerate()
+            .map(|(i, (field, quoted))| {
+                if field.is_empty() && !quoted {
+                    Ok(DataValue::Null)
+                } else {
+                    import_value(columns, i, DataValue::String(field))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// 拆分记录,返回(字段值,是否使用引号),忽略空行
+fn split_records(input: &str, sep: char) -> Vec<Vec<(String, bool)>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() && !quoted => {
+                quoted = true;
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    field.push(c);
+                }
+            },
+            c if c == sep => record.push((std::mem::take(&mut field), std::mem::take(&mut quoted))),
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                if !record.is_empty() || !field.is_empty() || quoted {
+                    record.push((std::mem::take(&mut field), std::mem::take(&mut quoted)));
+                    records.push(std::mem::take(&mut record));
+                }
+            },
+            c => field.push(c)
+        }
+    }
+    if !record.is_empty() || !field.is_empty() || quoted {
+        record.push((field, quoted));
+        records.push(record);
+    }
+    records
+}
+
+/// 按数据行展开模板中`__pbband="detail"`的元素,替换与字段名相同的文本和属性值
+///
+/// 没有找到`detail`元素时返回`false`
+fn expand_rows(elem: &mut XmlElement, columns: &Columns, rows: &[Vec<DataValue>]) -> bool {
+    let index = elem.children.iter().position(|node| {
+        matches!(node, XmlNode::Element(v) if v.attr("__pbband") == Some("detail"))
+    });
+    let index = match index {
+        Some(index) => index,
+        None => {
+            return elem.children.iter_mut().any(|node| {
+                match node {
+                    XmlNode::Element(v) => expand_rows(v, columns, rows),
+                    XmlNode::Text(_) => false
+                }
+            });
+        },
+    };
+    let mut template = match elem.children.remove(index) {
+        XmlNode::Element(v) => v,
+        XmlNode::Text(_) => unreachable!()
+    };
+    template.attrs.retain(|(key, _)| key != "__pbband");
+    for (i, row) in rows.iter().enumerate() {
+        let mut row_elem = template.clone();
+        fill_row(&mut row_elem, columns, row);
+        elem.children.insert(index + i, XmlNode::Element(row_elem));
+    }
+    true
+}
+
+fn fill_row(elem: &mut XmlElement, columns: &Columns, row: &[DataValue]) {
+    for (_, value) in &mut elem.attrs {
+        if let Some(index) = column_index(columns, value) {
+            *value = row[index].to_string();
+        }
+    }
+    for node in &mut elem.children {
+        match node {
+            XmlNode::Element(v) => fill_row(v, columns, row),
+            XmlNode::Text(v) => {
+                if let Some(index) = column_index(columns, v) {
+                    *v = row[index].to_string();
+                }
+            }
+        }
+    }
+    //空值输出为空元素
+    elem.children.retain(|node| !matches!(node, XmlNode::Text(v) if v.is_empty()));
+}
+
+fn find_detail(elem: &XmlElement) -> Option<&XmlElement> {
+    if elem.attr("__pbband") == Some("detail") {
+        return Some(elem);
+    }
+    elem.elements().find_map(find_detail)
+}
+
+fn find_elements<'e>(elem: &'e XmlElement, name: &str, rv: &mut Vec<&'e XmlElement>) {
+    for child in elem.elements() {
+        if child.name == name {
+            rv.push(child);
+        } else {
+            find_elements(child, name, rv);
+        }
+    }
+}
+
+/// 收集模板中引用字段的位置(元素路径,属性名,字段索引)
+fn collect_mapping(
+    elem: &XmlElement,
+    columns: &Columns,
+    path: &mut Vec<String>,
+    mapping: &mut Vec<(Vec<String>, Option<String>, usize)>
+) {
+    for (key, value) in &elem.attrs {
+        if let Some(index) = column_index(columns, value) {
+            mapping.push((path.clone(), Some(key.clone()), index));
+        }
+    }
+    for node in &elem.children {
+        match node {
+            XmlNode::Element(child) => {
+                path.push(child.name.clone());
+                collect_mapping(child, columns, path, mapping);
+                path.pop();
+            },
+            XmlNode::Text(text) => {
+                if let Some(index) = column_index(columns, text) {
+                    mapping.push((path.clone(), None, index));
+                }
+            }
+        }
+    }
+}
+
+/// 缩进输出XML元素
+fn write_indented(out: &mut String, elem: &XmlElement, depth: usize) {
+    let indent = " ".repeat(depth);
+    let has_elements = elem.elements().next().is_some();
+    if !has_elements {
+        *out += &format!("{indent}{elem}\r\n");
+        return;
+    }
+    let mut open = format!("<{}", elem.name);
+    for (key, value) in &elem.attrs {
+        open += &format!(" {key}=\"{}\"", xml::escape(value));
+    }
+    *out += &format!("{indent}{open}>\r\n");
+    for node in &elem.children {
+        match node {
+            XmlNode::Element(child) => write_indented(out, child, depth + 1),
+            XmlNode::Text(text) if !text.trim().is_empty() => {
+                *out += &format!("{indent} {}\r\n", xml::escape(text.trim()));
+            },
+            XmlNode::Text(_) => {}
+        }
+    }
+    *out += &format!("{indent}</{}>\r\n", elem.name);
+}
+
+fn json_value(value: &DataValue) -> Json {
+    match value {
+        DataValue::Null => Json::Null,
+        DataValue::Number(v) => Json::Number(*v),
+        v => Json::String(v.to_string())
+    }
+}
+
+fn json_row(columns: &Columns, row: &[DataValue], f: impl Fn(&DataValue) -> Json) -> Json {
+    Json::Object(columns.iter().zip(row).map(|((name, _), value)| (name.clone(), f(value))).collect())
+}
+
+/// DataWindow JSON的行状态(`dwItemStatus`的值)
+fn json_status(status: ItemStatus) -> f64 {
+    match status {
+        ItemStatus::NotModified => 0.,
+        ItemStatus::DataModified => 1.,
+        ItemStatus::New => 2.,
+        ItemStatus::NewModified => 3.
+    }
+}
+
+/// DataWindow JSON的数据行,已修改的字段输出`[当前值,原始值]`
+fn json_dw_row(
+    columns: &Columns,
+    values: &[DataValue],
+    original: &[Option<&DataValue>],
+    status: ItemStatus
+) -> Json {
+    let members = columns
+        .iter()
+        .zip(values)
+        .enumerate()
+        .map(|(i, ((name, _), value))| {
+            let mut list = vec![json_value(value)];
+            if let Some(Some(original)) = original.get(i) {
+                list.push(json_value(original));
+            }
+            (name.clone(), Json::Array(list))
+        })
+        .collect();
+    Json::Object(vec![
+        ("row-status".to_owned(), Json::Number(json_status(status))),
+        ("columns".to_owned(), Json::Object(members))
+    ])
+}
+
+/// DataWindow JSON
+///
+/// ```json
+/// {"identity":"...","version":1,"platform":"PowerBuilder","mapping-method":0,
+///  "dataobject":{"name":"d_emp","meta-columns":[{"name":"id","index":0,"datatype":"long","nullable":1}],
+///  "primary-rows":[{"row-status":0,"columns":{"id":[1]}}],"filter-rows":[],"delete-rows":[]}}
+/// ```
+///
+/// `buffers`依次为主缓冲区、过滤缓冲区和删除缓冲区的数据行,
+/// 不输出`dw-columns`段(下拉数据窗口的子数据),语法中没有这部分数据,导入时也忽略该段
+fn write_json(name: &str, columns: &Columns, buffers: [Vec<Json>; 3]) -> String {
+    let meta = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (name, ty))| {
+            Json::Object(vec![
+                ("name".to_owned(), Json::String(name.clone())),
+                ("index".to_owned(), Json::Number(i as f64)),
+                ("datatype".to_owned(), Json::String(ty.to_string())),
+                ("nullable".to_owned(), Json::Number(1.)),
+            ])
+        })
+        .collect();
+    let [primary, filter, delete] = buffers;
+    Json::Object(vec![
+        ("identity".to_owned(), Json::String(JSON_IDENTITY.to_owned())),
+        ("version".to_owned(), Json::Number(1.)),
+        ("platform".to_owned(), Json::String("PowerBuilder".to_owned())),
+        ("mapping-method".to_owned(), Json::Number(0.)),
+        (
+            "dataobject".to_owned(),
+            Json::Object(vec![
+                ("name".to_owned(), Json::String(name.to_owned())),
+                ("meta-columns".to_owned(), Json::Array(meta)),
+                ("primary-rows".to_owned(), Json::Array(primary)),
+                ("filter-rows".to_owned(), Json::Array(filter)),
+                ("delete-rows".to_owned(), Json::Array(delete)),
+            ])
+        ),
+    ])
+    .to_string()
+}
+
+/// 读取JSON数据,DataWindow JSON读取主缓冲区和过滤缓冲区的当前值
+fn read_json(columns: &Columns, input: &str) -> Result<Vec<Vec<DataValue>>, String> {
+    let json = Json::parse(input)?;
+    let mut objects = vec![];
+    match &json {
+        Json::Array(list) => objects.extend(list.iter().map(|v| (v, false))),
+        Json::Object(_) if json.get("identity").is_some() => {
+            let dataobject = json.get("dataobject").unwrap_or(&json);
+            for key in ["primary-rows", "filter-rows"] {
+                let rows = dataobject.get(key).and_then(|v| v.as_array()).unwrap_or_default();
+                objects.extend(rows.iter().map(|row| (row.get("columns").unwrap_or(row), true)));
+            }
+        },
+        _ => return Err("invalid json data: expected an array or a DataWindow JSON object".to_owned())
+    }
+    let mut rows = Vec::with_capacity(objects.len());
+    for (object, dw) in objects {
+        let members = match object {
+            Json::Object(members) => members,
+            v => return Err(format!("invalid json row: {v}"))
+        };
+        let mut row = vec![DataValue::Null; columns.len()];
+        for (key, value) in members {
+            let index = match column_index(columns, key) {
+                Some(index) => index,
+                None => continue
+            };
+            //DataWindow JSON的字段值为[当前值,原始值]
+            let value = match (dw, value) {
+                (true, Json::Array(list)) => list.first().unwrap_or(&Json::Null),
+                _ => value
+            };
+            row[index] = match value {
+                Json::Null => DataValue::Null,
+                Json::Bool(v) => import_value(columns, index, DataValue::Number(if *v { 1. } else { 0. }))?,
+                Json::Number(v) => import_value(columns, index, DataValue::Number(*v))?,
+                Json::String(v) => import_value(columns, index, DataValue::String(v.clone()))?,
+                v => return Err(format!("invalid json value for column {}: {v}", columns[index].0))
+            };
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = concat!("$PBExportHeader$d_emp.srd\r\n", r#"release 19;
+table(column=(type=long name=id dbname="emp.id") column=(type=char(20) name=name dbname="emp.name")
+ column=(type=date name=hired dbname="emp.hired"))
+export.xml(headgroups="1" includewhitespace="0" metadatatype=0 savemetadata=0  template=(name="t_emp" comment="" encoding="UTF-8" xml="<?xml version=~"1.0~" encoding=~"UTF-8~"?><emps><emp __pbband=~"detail~" id=~"id~"><name>name</name><hired>hired</hired></emp></emps>" ) )
+import.xml()
+data(1, "Tom, ~"T~"", 2020-01-02, 2, null 2021-03-04, )
+"#);
+
+    #[test]
+    fn test_save_as() {
+        let dw = DWSyntax::parse(SYNTAX).unwrap();
+        let text = dw.save_as(SaveAsType::Text, true).unwrap();
+        assert_eq!(text, "id\tname\thired\r\n1\t\"Tom, \"\"T\"\"\"\t2020-01-02\r\n2\t\t2021-03-04\r\n");
+        let csv = dw.save_as(SaveAsType::Csv, false).unwrap();
+        assert_eq!(csv, "1,\"Tom, \"\"T\"\"\",2020-01-02\r\n2,,2021-03-04\r\n");
+        let xml = dw.save_as(SaveAsType::Xml, false).unwrap();
+        assert_eq!(
+            xml,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><emps><emp id=\"1\"><name>Tom, &quot;T&quot;</name>\
+             <hired>2020-01-02</hired></emp><emp id=\"2\"><name/><hired>2021-03-04</hired></emp></emps>"
+        );
+        let json = dw.save_as(SaveAsType::Json, false).unwrap();
+        assert!(json.contains(r#""mapping-method":0,"dataobject":{"name":"d_emp","meta-columns":[{"name":"id""#));
+        assert!(json.contains(r#"{"row-status":0,"columns":{"id":[2],"name":[null],"hired":["2021-03-04"]}}"#));
+        let plain = dw.save_as(SaveAsType::PlainJson, false).unwrap();
+        assert!(plain.starts_with(r#"[{"id":1,"name":"Tom, \"T\"","hired":"2020-01-02"}"#));
+    }
+
+    #[test]
+    fn test_import_round_trip() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let rows = dw.data_rows();
+        for ty in [SaveAsType::Text, SaveAsType::Csv, SaveAsType::Json, SaveAsType::PlainJson] {
+            let input = dw.save_as(ty, true).unwrap();
+            dw.data.clear();
+            assert_eq!(dw.import_string(ty, &input).unwrap(), 2, "{ty:?}");
+            assert_eq!(dw.data_rows(), rows, "{ty:?}");
+        }
+    }
+
+    #[test]
+    fn test_import_xml() {
+        //`import.xml`没有模板时按`<字段名>`元素导入
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let xml = dw.save_as(SaveAsType::Xml, false).unwrap();
+        let rows = dw.data_rows();
+        dw.data.clear();
+        let xml_rows = "<d_emp><r><ID>3</ID><hired>2022-01-01</hired></r></d_emp>";
+        assert_eq!(dw.import_string(SaveAsType::Xml, xml_rows).unwrap(), 1);
+        assert_eq!(dw.data_rows(), vec![vec![3.0.into(), DataValue::Null, Date::new(2022, 1, 1).into()]]);
+
+        //使用模板导入
+        let template = r#"import.xml(template=(name="t" xml="<emps><emp __pbband=~"detail~" id=~"id~"><name>name</name><hired>hired</hired></emp></emps>"))"#;
+        let syntax = SYNTAX.replace("import.xml()", template);
+        let mut dw = DWSyntax::parse(&syntax).unwrap();
+        dw.data.clear();
+        assert_eq!(dw.import_string(SaveAsType::Xml, &xml).unwrap(), 2);
+        assert_eq!(dw.data_rows(), rows);
+    }
+
+    #[test]
+    fn test_import_export_json() {
+        //`ExportJson`导出的数据:已修改的字段为[当前值,原始值],删除缓冲区不导入
+        let export = concat!(
+            "{\r\n\"identity\":\"70c86603-983b-4bd9-adbc-259436e43cbd\",\r\n\"version\":1,\r\n",
+            "\"platform\":\"PowerBuilder\",\r\n\"mapping-method\":0,\r\n\"dataobject\":{\r\n",
+            "\"name\":\"d_emp\",\r\n\"meta-columns\":[\r\n",
+            "{\"name\":\"id\",\"index\":0,\"datatype\":\"long\",\"nullable\":1},\r\n",
+            "{\"name\":\"name\",\"index\":1,\"datatype\":\"char(20)\",\"nullable\":1},\r\n",
+            "{\"name\":\"hired\",\"index\":2,\"datatype\":\"date\",\"nullable\":1}],\r\n",
+            "\"primary-rows\":[\r\n",
+            "{\"row-status\":1,\"columns\":{\"id\":[7],\"name\":[\"Ann\",\"Anna\"],\"hired\":[\"2019-05-06\"]}},\r\n",
+            "{\"row-status\":3,\"columns\":{\"name\":[\"Bob\"],\"id\":[8],\"hired\":[null]}}],\r\n",
+            "\"filter-rows\":[\r\n",
+            "{\"row-status\":0,\"columns\":{\"id\":[9],\"name\":[\"Cid\"],\"hired\":[\"2018-01-01\"]}}],\r\n",
+            "\"delete-rows\":[\r\n",
+            "{\"row-status\":0,\"columns\":{\"id\":[10],\"name\":[\"Dan\"],\"hired\":[null]}}]\r\n}\r\n}"
+        );
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.data.clear();
+        assert_eq!(dw.import_string(SaveAsType::Json, export).unwrap(), 3);
+        let ids: Vec<_> = dw.data_rows().into_iter().map(|row| row[0].clone()).collect();
+        assert_eq!(ids, vec![7.0.into(), 8.0.into(), 9.0.into()]);
+        assert_eq!(dw.data_rows()[0][1], DataValue::String("Ann".into()));
+    }
+
+    #[test]
+    fn test_datastore_export_json() {
+        //`DataStore`导出行状态、原始值及过滤、删除缓冲区
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        dw.import_string(SaveAsType::Csv, "7,Ann,2019-05-06\r\n8,Bob,\r\n9,Cid,2018-01-01").unwrap();
+        let mut ds = DataStore::new(&dw).unwrap();
+        ds.set_item(2, "name", "Amy").unwrap();
+        let row = ds.insert_row(ds.row_count()).unwrap();
+        ds.set_item(row, "id", 11.).unwrap();
+        ds.delete_row(3).unwrap();
+        ds.set_filter("id < 9").unwrap();
+        ds.filter().unwrap();
+        let json = ds.export_json("d_emp");
+        assert!(json.contains(r#"[{"row-status":0,"columns":{"id":[1],"#));
+        assert!(json.contains(r#"{"row-status":1,"columns":{"id":[7],"name":["Amy","Ann"]"#));
+        assert!(json.contains(r#""filter-rows":[{"row-status":0,"columns":{"id":[9]"#));
+        assert!(json.contains(r#"{"row-status":3,"columns":{"id":[11,null]"#));
+        assert!(json.contains(r#""delete-rows":[{"row-status":0,"columns":{"id":[8]"#));
+        dw.data.clear();
+        assert_eq!(dw.import_string(SaveAsType::Json, &json).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_import_error() {
+        //导入失败时不修改`data`项
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let rows = dw.data_rows();
+        for (ty, input) in [
+            (SaveAsType::Csv, "1,2"),
+            (SaveAsType::Csv, "x,\"a\",2020-01-01"),
+            (SaveAsType::Text, "1\t\"a\t2020-01-01"),
+            (SaveAsType::Json, r#"[{"id":1,"name":"a""#),
+            (SaveAsType::Json, r#"{"dataobject":{"primary-rows":[{"columns":{"id":["x"]}}]}}"#),
+            (SaveAsType::PlainJson, r#"[{"id":1} {"id":2}]"#),
+            (SaveAsType::PlainJson, r#"[{"id":"abc"}]"#),
+            (SaveAsType::Xml, "<d_emp><r><id>3</r></d_emp>"),
+            (SaveAsType::Xml, "<d_emp><r><id>3</id></r>"),
+            (SaveAsType::Xml, "<d_emp><r><id>x</id></r></d_emp>")
+        ] {
+            assert!(dw.import_string(ty, input).is_err(), "{ty:?}: {input}");
+            assert_eq!(dw.data_rows(), rows, "{ty:?}: {input}");
+        }
+
+        //模板没有`detail`带区
+        let template = r#"import.xml(template=(name="t" xml="<emps><emp id=~"id~"/></emps>"))"#;
+        let syntax = SYNTAX.replace("import.xml()", template);
+        let mut dw = DWSyntax::parse(&syntax).unwrap();
+        let err = dw.import_string(SaveAsType::Xml, "<emps><emp id=\"1\"/></emps>").unwrap_err();
+        assert_eq!(err, "xml template has no detail band");
+    }
+}
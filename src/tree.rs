@@ -0,0 +1,332 @@
+use crate::{ast::*, data::*, prelude::*};
+use std::ops::Range;
+
+/// 树形显示风格
+///
+/// # Syntax
+///
+/// ```txt
+/// datawindow(processing=8 ...)
+/// tree(indent=64 showconnectlines=yes showleafnodeconnectlines=yes showtreenodeicon=no
+///     defaultexpandtolevel=1 stateiconalignmode=0 selectnodebymouse=yes)
+/// group(level=1 by=("dept") header.expandedtreenodeiconname="open.bmp"
+///     header.collapsedtreenodeiconname="close.bmp" ...)
+/// detail(treenodeiconname="leaf.bmp" ...)
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TreeView {
+    /// `indent`
+    pub indent: u32,
+    /// `showconnectlines`
+    pub show_connect_lines: bool,
+    /// `showleafnodeconnectlines`
+    pub show_leaf_node_connect_lines: bool,
+    /// `showtreenodeicon`
+    pub show_tree_node_icon: bool,
+    /// `defaultexpandtolevel`
+    pub default_expand_to_level: u32,
+    /// `stateiconalignmode`
+    pub state_icon_align_mode: u32,
+    /// `selectnodebymouse`
+    pub select_node_by_mouse: bool,
+    /// 树节点级别(对应分组)
+    pub levels: Vec<TreeLevel>,
+    /// 叶子节点图标(`detail.treenodeiconname`)
+    pub detail_icon: Option<String>
+}
+
+/// 树节点级别
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TreeLevel {
+    /// 分组级别
+    pub level: u32,
+    /// 分组依据的字段
+    pub by: Vec<String>,
+    /// `header.treenodeiconname`
+    pub icon: Option<String>,
+    /// `header.expandedtreenodeiconname`
+    pub expanded_icon: Option<String>,
+    /// `header.collapsedtreenodeiconname`
+    pub collapsed_icon: Option<String>
+}
+
+/// 树节点
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    /// 分组级别
+    pub level: u32,
+    /// 分组依据字段的值
+    pub keys: Vec<DataValue>,
+    /// 包含的数据行
+    pub rows: Range<usize>,
+    /// 下级节点
+    pub children: Vec<TreeNode>
+}
+
+impl TreeView {
+    /// 按分组级别将`data`项中的数据构造为树
+    ///
+    /// 与分组带区一致,相邻且分组值相同的行归入同一节点,最后一级节点的`rows`即为叶子节点
+    pub fn nodes(&self, syn: &DWSyntax) -> Result<Vec<TreeNode>, String> {
+        let mut levels = Vec::with_capacity(self.levels.len());
+        for level in &self.levels {
+            let columns = level
+                .by
+                .iter()
+                .map(|name| syn.table_column_index(name).ok_or_else(|| format!("unknown column: {name}")))
+                .collect::<Result<Vec<_>, _>>()?;
+            levels.push((level.level, columns));
+        }
+        let rows = syn.data_rows();
+        Ok(build_nodes(&rows, &levels, 0..rows.len()))
+    }
+}
+
+fn build_nodes(rows: &[Vec<DataValue>], levels: &[(u32, Vec<usize>)], range: Range<usize>) -> Vec<TreeNode> {
+    let (level, columns) = match levels.first() {
+        Some(v) => v,
+        None => return vec![]
+    };
+    let keys = |row: usize| columns.iter().map(|i| rows[row][*i].clone()).collect::<Vec<_>>();
+    let mut nodes: Vec<TreeNode> = vec![];
+    for row in range {
+        let key = keys(row);
+        match nodes.last_mut() {
+            Some(node) if node.keys == key => node.rows.end = row + 1,
+            _ => {
+                nodes.push(TreeNode {
+                    level: *level,
+                    keys: key,
+                    rows: row..row + 1,
+                    children: vec![]
+                })
+            }
+        }
+    }
+    for node in &mut nodes {
+        node.children = build_nodes(rows, &levels[1..], node.rows.clone());
+    }
+    nodes
+}
+
+impl<'a> DWSyntax<'a> {
+    /// 获取树形显示风格的定义
+    ///
+    /// 没有`tree`语法项时返回`None`
+    pub fn tree_view(&self) -> Option<TreeView> {
+        let tree = self.items.iter().find(|item| item.kind == "tree")?;
+        let get = |key: &'static str| tree.values.get(&key.into_key());
+        let number = |key: &'static str| get(key).and_then(|v| v.to_number()).unwrap_or_default() as u32;
+        let flag = |key: &'static str| get(key).and_then(|v| v.to_bool()).unwrap_or_default();
+        let icon = |values: &HashMap<Key, Value>, key: &'static str| {
+            values.get(&key.into_key()).and_then(|v| v.as_str()).map(unescape).filter(|v| !v.is_empty())
+        };
+        Some(TreeView {
+            indent: number("indent"),
+            show_connect_lines: flag("showconnectlines"),
+            show_leaf_node_connect_lines: flag("showleafnodeconnectlines"),
+            show_tree_node_icon: flag("showtreenodeicon"),
+            default_expand_to_level: number("defaultexpandtolevel"),
+            state_icon_align_mode: number("stateiconalignmode"),
+            select_node_by_mouse: flag("selectnodebymouse"),
+            levels: self
+                .groups()
+                .into_iter()
+                .map(|group| {
+                    TreeLevel {
+                        level: group.level.unwrap_or_default(),
+                        by: group.group_by().into_iter().map(str::to_owned).collect(),
+                        icon: icon(&group.values, "header.treenodeiconname"),
+                        expanded_icon: icon(&group.values, "header.expandedtreenodeiconname"),
+                        collapsed_icon: icon(&group.values, "header.collapsedtreenodeiconname")
+                    }
+                })
+                .collect(),
+            detail_icon: icon(&self.detail, "treenodeiconname")
+        })
+    }
+
+    /// 设置树形显示风格
+    ///
+    /// 写入`tree`语法项(不存在时创建)、各分组及`detail`带区的节点图标,并设置`processing=8`
+    ///
+    /// 节点图标为`None`时保留原有设置,为空字符串时删除
+    ///
+    /// 不修改分组结构,`levels`中不存在的分组级别将返回错误
+    pub fn set_tree_view(&mut self, tree: &TreeView) -> Result<(), String> {
+        for level in &tree.levels {
+            if self.group(level.level).is_none() {
+                return Err(format!("invalid group level: {}", level.level));
+            }
+        }
+        fn flag<'r>(v: bool) -> Value<'r> { Value::Literal(if v { "yes" } else { "no" }.into()) }
+        fn icon<'r>(values: &mut HashMap<Key<'r>, Value<'r>>, key: &'static str, icon: &Option<String>) {
+            match icon.as_deref() {
+                Some("") => {
+                    values.remove(&key.into_key());
+                },
+                Some(icon) => {
+                    values.insert(key.into_key(), Value::DoubleQuotedString(escape(icon).into()));
+                },
+                None => {}
+            }
+        }
+        let index = match self.items.iter().position(|item| item.kind == "tree") {
+            Some(index) => index,
+            None => {
+                self.items.push(Item {
+                    kind: "tree".into_key(),
+                    name: None,
+                    id: None,
+                    level: None,
+                    values: HashMap::default()
+                });
+                self.items.len() - 1
+            }
+        };
+        let values = &mut self.items[index].values;
+        values.insert("indent".into_key(), Value::Number(tree.indent as f64));
+        values.insert("showconnectlines".into_key(), flag(tree.show_connect_lines));
+        values.insert("showleafnodeconnectlines".into_key(), flag(tree.show_leaf_node_connect_lines));
+        values.insert("showtreenodeicon".into_key(), flag(tree.show_tree_node_icon));
+        values.insert("defaultexpandtolevel".into_key(), Value::Number(tree.default_expand_to_level as f64));
+        values.insert("stateiconalignmode".into_key(), Value::Number(tree.state_icon_align_mode as f64));
+        values.insert("selectnodebymouse".into_key(), flag(tree.select_node_by_mouse));
+        for level in &tree.levels {
+            //SAFETY: 已检查
+            let group = self.group_mut(level.level).unwrap();
+            icon(&mut group.values, "header.treenodeiconname", &level.icon);
+            icon(&mut group.values, "header.expandedtreenodeiconname", &level.expanded_icon);
+            icon(&mut group.values, "header.collapsedtreenodeiconname", &level.collapsed_icon);
+        }
+        icon(&mut self.detail, "treenodeiconname", &tree.detail_icon);
+        self.datawindow.insert("processing".into_key(), Value::Number(8.));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presentation::PresentationStyle;
+
+    const SYNTAX: &str = r#"release 19;
+datawindow(units=0 processing=0)
+detail(height=76 treenodeiconname="leaf.bmp")
+table(column=(type=char(10) name=dept dbname="dept") column=(type=char(10) name=team dbname="team") column=(type=char(10) name=emp dbname="emp"))
+group(level=1 header.height=76 trailer.height=0 by=("dept") header.expandedtreenodeiconname="open.bmp")
+group(level=2 header.height=76 trailer.height=0 by=("team"))
+data("a", "x", "e1", "a", "x", "e2", "a", "y", "e3", "b", "x", "e4", )
+"#;
+
+    fn tree_syntax() -> DWSyntax<'static> {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let tree = TreeView {
+            indent: 64,
+            show_connect_lines: true,
+            default_expand_to_level: 1,
+            ..Default::default()
+        };
+        dw.set_tree_view(&tree).unwrap();
+        dw
+    }
+
+    #[test]
+    fn test_tree_view() {
+        let dw = DWSyntax::parse(SYNTAX).unwrap();
+        assert_eq!(dw.tree_view(), None);
+        let dw = tree_syntax();
+        assert_eq!(dw.presentation_style(), PresentationStyle::TreeView);
+
+        let tree = dw.tree_view().unwrap();
+        assert_eq!(tree.indent, 64);
+        assert!(tree.show_connect_lines);
+        assert!(!tree.select_node_by_mouse);
+        assert_eq!(tree.detail_icon.as_deref(), Some("leaf.bmp"));
+        assert_eq!(tree.levels.len(), 2);
+        assert_eq!(tree.levels[0].by, vec!["dept"]);
+        assert_eq!(tree.levels[0].expanded_icon.as_deref(), Some("open.bmp"));
+    }
+
+    #[test]
+    fn test_set_tree_view() {
+        let mut dw = tree_syntax();
+        let mut tree = dw.tree_view().unwrap();
+        tree.levels[1].icon = Some("team.bmp".to_owned());
+        tree.levels[0].expanded_icon = Some(String::new());
+        tree.detail_icon = None;
+        dw.set_tree_view(&tree).unwrap();
+        assert_eq!(dw.items.iter().filter(|item| item.kind == "tree").count(), 1);
+        let tree = dw.tree_view().unwrap();
+        assert_eq!(tree.levels[1].icon.as_deref(), Some("team.bmp"));
+        assert_eq!(tree.levels[0].expanded_icon, None);
+        assert_eq!(tree.detail_icon.as_deref(), Some("leaf.bmp"));
+    }
+
+    #[test]
+    fn test_set_tree_view_error() {
+        //分组级别不存在时不做任何修改
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let syntax = dw.to_string();
+        let tree = TreeView {
+            indent: 80,
+            levels: vec![TreeLevel {
+                level: 3,
+                icon: Some("x.bmp".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(dw.set_tree_view(&tree).unwrap_err(), "invalid group level: 3");
+        assert_eq!(dw.to_string(), syntax);
+        assert_eq!(dw.tree_view(), None);
+    }
+
+    #[test]
+    fn test_tree_nodes() {
+        let dw = tree_syntax();
+        let tree = dw.tree_view().unwrap();
+        let nodes = tree.nodes(&dw).unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].keys, vec![DataValue::String("a".to_owned())]);
+        assert_eq!(nodes[0].rows, 0..3);
+        assert_eq!(nodes[0].children.len(), 2);
+        assert_eq!(nodes[0].children[0].level, 2);
+        assert_eq!(nodes[0].children[0].rows, 0..2);
+        assert_eq!(nodes[0].children[1].rows, 2..3);
+        assert_eq!(nodes[1].rows, 3..4);
+        assert_eq!(nodes[1].children[0].keys, vec![DataValue::String("x".to_owned())]);
+
+        //没有分组级别时没有节点
+        let empty = TreeView::default();
+        assert!(empty.nodes(&dw).unwrap().is_empty());
+        let mut tree = tree.clone();
+        tree.levels[1].by = vec!["nosuch".to_owned()];
+        assert_eq!(tree.nodes(&dw).unwrap_err(), "unknown column: nosuch");
+    }
+
+    #[cfg(feature = "query")]
+    #[test]
+    fn test_tree_describe_modify() {
+        let mut dw = tree_syntax();
+        assert_eq!(dw.describe("datawindow.tree.indent"), "64");
+        assert_eq!(dw.modify("datawindow.tree.indent=80 datawindow.tree.selectnodebymouse=yes"), "");
+        let tree = dw.tree_view().unwrap();
+        assert_eq!(tree.indent, 80);
+        assert!(tree.select_node_by_mouse);
+    }
+
+    #[cfg(feature = "query")]
+    #[test]
+    fn test_tree_modify_error() {
+        //非TreeView风格没有`tree`语法项,不会自动创建
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let syntax = dw.to_string();
+        assert_eq!(dw.describe("datawindow.tree.indent"), "!");
+        let err = dw.try_modify("datawindow.tree.indent=80").unwrap_err();
+        assert!(matches!(err, crate::ModifyError::UnknownObject { .. }), "{err:?}");
+        assert_eq!(dw.to_string(), syntax);
+        assert_eq!(dw.tree_view(), None);
+        assert_ne!(dw.presentation_style(), PresentationStyle::TreeView);
+    }
+}
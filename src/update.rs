@@ -0,0 +1,400 @@
+use crate::{argument::Placeholder, ast::*, data::*, prelude::*};
+
+/// 更新时`WHERE`子句包含的字段(`table.updatewhere`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateWhere {
+    /// `0`: 主键
+    #[default]
+    Key,
+    /// `1`: 主键及可更新字段
+    KeyAndUpdatable,
+    /// `2`: 主键及修改的字段
+    KeyAndModified
+}
+
+impl UpdateWhere {
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            0 => Some(UpdateWhere::Key),
+            1 => Some(UpdateWhere::KeyAndUpdatable),
+            2 => Some(UpdateWhere::KeyAndModified),
+            _ => None
+        }
+    }
+
+    pub fn code(&self) -> u32 {
+        match self {
+            UpdateWhere::Key => 0,
+            UpdateWhere::KeyAndUpdatable => 1,
+            UpdateWhere::KeyAndModified => 2
+        }
+    }
+}
+
+/// 更新属性
+///
+/// # Syntax
+///
+/// ```txt
+/// table(column=(type=long key=yes update=yes updatewhereclause=yes name=id dbname="emp.id" identity=yes)
+///     update="emp" updatewhere=1 updatekeyinplace=no)
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UpdateSpec {
+    /// 更新表(`update`)
+    pub table: String,
+    /// `updatewhere`
+    pub where_mode: UpdateWhere,
+    /// 原位修改主键(`updatekeyinplace`),否则修改主键时先删除再插入
+    pub key_in_place: bool,
+    /// 与`table`字段一一对应
+    pub columns: Vec<UpdateColumn>
+}
+
+/// 字段的更新属性
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UpdateColumn {
+    /// 字段名
+    pub name: String,
+    /// 数据库字段名(`dbname`)
+    pub dbname: String,
+    /// 主键(`key`)
+    pub key: bool,
+    /// 可更新(`update`)
+    pub updatable: bool,
+    /// 可用于`WHERE`子句(`updatewhereclause`)
+    pub where_clause: bool,
+    /// 自增字段(`identity`),不参与插入和修改
+    pub identity: bool
+}
+
+/// 参数化SQL语句
+///
+/// `params`按占位符的顺序排列
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlStatement {
+    pub sql: String,
+    pub params: Vec<DataValue>
+}
+
+impl UpdateSpec {
+    /// 检查更新属性
+    pub fn validate(&self) -> Result<(), String> {
+        if self.table.trim().is_empty() {
+            return Err("update table is empty".to_owned());
+        }
+        if !self.columns.iter().any(|col| col.key) {
+            return Err(format!("no key column for update table: {}", self.table));
+        }
+        if !self.columns.iter().any(|col| col.updatable) {
+            return Err(format!("no updatable column for update table: {}", self.table));
+        }
+        if let Some(col) = self.columns.iter().find(|col| col.dbname.trim().is_empty()) {
+            return Err(format!("column has no dbname: {}", col.name));
+        }
+        Ok(())
+    }
+
+    /// 生成插入新行的SQL
+    ///
+    /// 包含值不为空的可更新字段(自增字段除外)
+    pub fn insert_sql(
+        &self,
+        current: &[DataValue],
+        placeholder: Placeholder
+    ) -> Result<SqlStatement, String> {
+        self.check_row(current)?;
+        let mut names = vec![];
+        let mut values = vec![];
+        let mut params = vec![];
+        for (col, value) in self.columns.iter().zip(current) {
+            if !col.updatable || col.identity || value.is_null() {
+                continue;
+            }
+            names.push(col.column_name());
+            params.push(value.clone());
+            let mut value = String::new();
+            placeholder.write(&mut value, params.len());
+            values.push(value);
+        }
+        if names.is_empty() {
+            return Err("no column to insert".to_owned());
+        }
+        Ok(SqlStatement {
+            sql: format!("INSERT INTO {} ({}) VALUES ({})", self.table, names.join(", "), values.join(", ")),
+            params
+        })
+    }
+
+    /// 生成修改行的SQL
+    ///
+    /// 没有修改时返回空列表,未设置`key_in_place`且修改了主键时返回删除和插入语句
+    pub fn update_sql(
+        &self,
+        original: &[DataValue],
+        current: &[DataValue],
+        placeholder: Placeholder
+    ) -> Result<Vec<SqlStatement>, String> {
+        self.check_row(original)?;
+        self.check_row(current)?;
+        let modified = modified_flags(original, current);
+        self.update_modified(original, current, &modified, placeholder)
+    }
+
+    /// 按修改标志生成修改行的SQL
+    pub(crate) fn update_modified(
+        &self,
+        original: &[DataValue],
+        current: &[DataValue],
+        modified: &[bool],
+        placeholder: Placeholder
+    ) -> Result<Vec<SqlStatement>, String> {
+        let key_modified = self.columns.iter().zip(modified).any(|(col, modified)| col.key && *modified);
+        if key_modified && !self.key_in_place {
+            return Ok(vec![
+                self.delete_modified(original, modified, placeholder)?,
+                self.insert_sql(current, placeholder)?
+            ]);
+        }
+        let mut sets = vec![];
+        let mut params = vec![];
+        for ((col, value), modified) in self.columns.iter().zip(current).zip(modified) {
+            if !col.updatable || col.identity || !*modified {
+                continue;
+            }
+            params.push(value.clone());
+            let mut set = format!("{} = ", col.column_name());
+            placeholder.write(&mut set, params.len());
+            sets.push(set);
+        }
+        if sets.is_empty() {
+            return Ok(vec![]);
+        }
+        let wheres = self.where_clause(original, modified, placeholder, &mut params);
+        Ok(vec![SqlStatement {
+            sql: format!("UPDATE {} SET {} WHERE {wheres}", self.table, sets.join(", ")),
+            params
+        }])
+    }
+
+    /// 生成删除行的SQL
+    ///
+    /// `current`为删除前的当前值,`updatewhere=2`时`WHERE`子句包含修改过的字段
+    pub fn delete_sql(
+        &self,
+        original: &[DataValue],
+        current: &[DataValue],
+        placeholder: Placeholder
+    ) -> Result<SqlStatement, String> {
+        self.check_row(original)?;
+        self.check_row(current)?;
+        self.delete_modified(original, &modified_flags(original, current), placeholder)
+    }
+
+    /// 按修改标志生成删除行的SQL
+    pub(crate) fn delete_modified(
+        &self,
+        original: &[DataValue],
+        modified: &[bool],
+        placeholder: Placeholder
+    ) -> Result<SqlStatement, String> {
+        let mut params = vec![];
+        let wheres = self.where_clause(original, modified, placeholder, &mut params);
+        Ok(SqlStatement {
+            sql: format!("DELETE FROM {} WHERE {wheres}", self.table),
+            params
+        })
+    }
+
+    /// 按`updatewhere`生成`WHERE`子句,使用原始值
+    ///
+    /// 参数追加到`params`,占位符接着已有参数编号
+    fn where_clause(
+        &self,
+        original: &[DataValue],
+        modified: &[bool],
+        placeholder: Placeholder,
+        params: &mut Vec<DataValue>
+    ) -> String {
+        let mut wheres = vec![];
+        for ((col, value), modified) in self.columns.iter().zip(original).zip(modified) {
+            let include = col.key ||
+                col.updatable &&
+                    col.where_clause &&
+                    match self.where_mode {
+                        UpdateWhere::Key => false,
+                        UpdateWhere::KeyAndUpdatable => true,
+                        UpdateWhere::KeyAndModified => *modified
+                    };
+            if !include {
+                continue;
+            }
+            if value.is_null() {
+                wheres.push(format!("{} IS NULL", col.column_name()));
+            } else {
+                params.push(value.clone());
+                let mut cond = format!("{} = ", col.column_name());
+                placeholder.write(&mut cond, params.len());
+                wheres.push(cond);
+            }
+        }
+        wheres.join(" AND ")
+    }
+
+    fn check_row(&self, row: &[DataValue]) -> Result<(), String> {
+        self.validate()?;
+        if row.len() != self.columns.len() {
+            return Err(format!("expected {} values, got {}", self.columns.len(), row.len()));
+        }
+        Ok(())
+    }
+}
+
+impl UpdateColumn {
+    /// 数据库字段名(不含表名)
+    fn column_name(&self) -> &str {
+        let dbname = self.dbname.trim();
+        dbname.rsplit_once('.').map(|(_, name)| name).unwrap_or(dbname)
+    }
+}
+
+/// 比较原始值和当前值得到每个字段的修改标志
+fn modified_flags(original: &[DataValue], current: &[DataValue]) -> Vec<bool> {
+    original.iter().zip(current).map(|(a, b)| a != b).collect()
+}
+
+impl<'a> ItemTable<'a> {
+    /// 获取更新属性
+    ///
+    /// 没有设置更新表时返回`None`
+    pub fn update_spec(&self) -> Option<UpdateSpec> {
+        let table = self.values.get(&"update".into_key())?.as_str().map(unescape)?;
+        let updatewhere = self.values.get(&"updatewhere".into_key()).and_then(|v| v.to_number());
+        let flag = |values: &HashMap<Key, Value>, key: &'static str| {
+            values.get(&key.into_key()).and_then(|v| v.to_bool()).unwrap_or_default()
+        };
+        Some(UpdateSpec {
+            table,
+            where_mode: updatewhere.and_then(|v| UpdateWhere::from_code(v as u32)).unwrap_or_default(),
+            key_in_place: flag(&self.values, "updatekeyinplace"),
+            columns: self
+                .columns
+                .iter()
+                .map(|col| {
+                    UpdateColumn {
+                        name: col.name.as_ref().map(|v| v.to_string()).unwrap_or_default(),
+                        dbname: col
+                            .values
+                            .get(&"dbname".into_key())
+                            .and_then(|v| v.as_str())
+                            .map(unescape)
+                            .unwrap_or_default(),
+                        key: flag(&col.values, "key"),
+                        updatable: flag(&col.values, "update"),
+                        where_clause: flag(&col.values, "updatewhereclause"),
+                        identity: flag(&col.values, "identity")
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// 设置更新属性
+    ///
+    /// 检查更新属性并按字段名写入对应的`table`字段
+    pub fn set_update_spec(&mut self, spec: &UpdateSpec) -> Result<(), String> {
+        spec.validate()?;
+        let mut indexes = Vec::with_capacity(spec.columns.len());
+        for col in &spec.columns {
+            let index = self
+                .columns
+                .iter()
+                .position(|v| matches!(&v.name, Some(v) if v.eq_ignore_ascii_case(&col.name)))
+                .ok_or_else(|| format!("unknown column: {}", col.name))?;
+            indexes.push(index);
+        }
+        fn yes<'r>() -> Value<'r> { Value::Literal("yes".into()) }
+        for (col, index) in spec.columns.iter().zip(indexes) {
+            let values = &mut self.columns[index].values;
+            for (key, flag) in [("key", col.key), ("update", col.updatable), ("identity", col.identity)] {
+                if flag {
+                    values.insert(key.into_key(), yes());
+                } else {
+                    #[cfg(feature = "preserve_order")]
+                    values.shift_remove(&key.into_key());
+                    #[cfg(not(feature = "preserve_order"))]
+                    values.remove(&key.into_key());
+                }
+            }
+            values.insert(
+                "updatewhereclause".into_key(),
+                if col.where_clause { yes() } else { Value::Literal("no".into()) }
+            );
+            values.insert("dbname".into_key(), Value::DoubleQuotedString(escape(&col.dbname).into()));
+        }
+        self.values.insert("update".into_key(), Value::DoubleQuotedString(escape(&spec.table).into()));
+        self.values.insert("updatewhere".into_key(), Value::Number(spec.where_mode.code() as f64));
+        self.values.insert(
+            "updatekeyinplace".into_key(),
+            Value::Literal(if spec.key_in_place { "yes" } else { "no" }.into())
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = r#"release 19;
+table(column=(type=long key=yes update=yes updatewhereclause=yes name=id dbname="emp.id")
+ column=(type=char(20) update=yes updatewhereclause=yes name=name dbname="emp.name")
+ column=(type=long update=yes updatewhereclause=yes name=dept dbname="emp.dept")
+ column=(type=char(10) updatewhereclause=yes name=dept_name dbname="dept.name")
+ retrieve="SELECT emp.id, emp.name, emp.dept, dept.name FROM emp, dept" update="emp" updatewhere=1 updatekeyinplace=no)
+"#;
+
+    #[test]
+    fn test_update_spec() {
+        let mut dw = DWSyntax::parse(SYNTAX).unwrap();
+        let mut spec = dw.table.update_spec().unwrap();
+        assert_eq!(spec.table, "emp");
+        assert_eq!(spec.where_mode, UpdateWhere::KeyAndUpdatable);
+        assert!(spec.columns[0].key && !spec.columns[1].key);
+        assert!(!spec.columns[3].updatable);
+
+        let original: Vec<DataValue> = vec![1.0.into(), "Tom".into(), 10.0.into(), "IT".into()];
+        let current: Vec<DataValue> = vec![1.0.into(), "Tim".into(), 10.0.into(), "IT".into()];
+        let stmts = spec.update_sql(&original, &current, Placeholder::Question).unwrap();
+        assert_eq!(stmts[0].sql, "UPDATE emp SET name = ? WHERE id = ? AND name = ? AND dept = ?");
+        assert_eq!(stmts[0].params, vec!["Tim".into(), 1.0.into(), "Tom".into(), 10.0.into()]);
+        assert!(spec.update_sql(&original, &original, Placeholder::Question).unwrap().is_empty());
+
+        //修改主键
+        let current: Vec<DataValue> = vec![2.0.into(), "Tom".into(), DataValue::Null, "IT".into()];
+        let stmts = spec.update_sql(&original, &current, Placeholder::Question).unwrap();
+        assert_eq!(stmts[0].sql, "DELETE FROM emp WHERE id = ? AND name = ? AND dept = ?");
+        assert_eq!(stmts[1].sql, "INSERT INTO emp (id, name) VALUES (?, ?)");
+        spec.key_in_place = true;
+        spec.where_mode = UpdateWhere::KeyAndModified;
+        let stmts = spec.update_sql(&original, &current, Placeholder::Question).unwrap();
+        assert_eq!(stmts[0].sql, "UPDATE emp SET id = ?, dept = ? WHERE id = ? AND dept = ?");
+        let stmts = spec.update_sql(&original, &current, Placeholder::Dollar).unwrap();
+        assert_eq!(stmts[0].sql, "UPDATE emp SET id = $1, dept = $2 WHERE id = $3 AND dept = $4");
+        let stmt = spec.delete_sql(&original, &original, Placeholder::Question).unwrap();
+        assert_eq!(stmt.sql, "DELETE FROM emp WHERE id = ?");
+        let stmt = spec.delete_sql(&original, &current, Placeholder::AtP).unwrap();
+        assert_eq!(stmt.sql, "DELETE FROM emp WHERE id = @p1 AND dept = @p2");
+        assert_eq!(stmt.params, vec![1.0.into(), 10.0.into()]);
+
+        spec.table = "employee".to_owned();
+        dw.table.set_update_spec(&spec).unwrap();
+        assert_eq!(dw.table.update_spec(), Some(spec.clone()));
+        assert!(dw.to_string().contains("update=\"employee\" updatewhere=2 updatekeyinplace=yes"));
+
+        spec.columns[0].key = false;
+        assert!(spec.validate().is_err());
+        assert!(dw.table.set_update_spec(&spec).is_err());
+        assert!(spec.insert_sql(&original, Placeholder::Question).is_err());
+    }
+}